@@ -0,0 +1,53 @@
+use std::{
+    fmt::Display,
+    net::{IpAddr, Ipv4Addr},
+    str::FromStr,
+};
+
+use log::warn;
+
+pub const DEFAULT_PORT: u16 = 8080;
+pub const DEFAULT_HOST: IpAddr = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+
+/// Parses the `name` env var value, falling back to `default` when it is missing or malformed.
+pub fn parse_env_value<T: FromStr + Display>(name: &str, raw: Option<String>, default: T) -> T {
+    match raw {
+        Some(raw) => raw.trim().parse().unwrap_or_else(|_| {
+            warn!("Invalid {} '{}', using default {}", name, raw, default);
+            default
+        }),
+        None => default,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Unit Tests
+
+    #[test]
+    fn test_parse_env_value() {
+        assert_eq!(
+            parse_env_value("PORT", Some("9090".to_string()), DEFAULT_PORT),
+            9090
+        );
+        assert_eq!(parse_env_value("PORT", None, DEFAULT_PORT), DEFAULT_PORT);
+        assert_eq!(
+            parse_env_value("PORT", Some("80a".to_string()), DEFAULT_PORT),
+            DEFAULT_PORT
+        );
+        assert_eq!(
+            parse_env_value("PORT", Some("70000".to_string()), DEFAULT_PORT),
+            DEFAULT_PORT
+        );
+        assert_eq!(
+            parse_env_value("HOST", Some(" 127.0.0.1 ".to_string()), DEFAULT_HOST),
+            IpAddr::V4(Ipv4Addr::LOCALHOST)
+        );
+        assert_eq!(
+            parse_env_value("HOST", Some("localhost".to_string()), DEFAULT_HOST),
+            DEFAULT_HOST
+        );
+    }
+}
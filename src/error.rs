@@ -1,10 +1,30 @@
-use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+use std::fmt::Display;
+
+use actix_web::{
+    error::{JsonPayloadError, QueryPayloadError},
+    http::StatusCode,
+    HttpRequest, HttpResponse, ResponseError,
+};
 use derive_more::{Display, Error};
-use log::error;
-use mongodb::bson;
+use log::{error, warn};
+use mongodb::{
+    bson,
+    error::{Error as MongoError, ErrorKind, WriteFailure},
+};
+use serde::Serialize;
 use utoipa::ToSchema;
-use validator::ValidationErrors;
+use validator::{ValidationErrors, ValidationErrorsKind};
+
+/// Messages of one invalid request field, with its JSON path (`seasonList[0].overview`).
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct FieldError {
+    #[schema(example = "seasonList[0].overview")]
+    pub field: String,
+    #[schema(example = json!(["The season overview cannot be empty"]))]
+    pub messages: Vec<String>,
+}
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Display, PartialEq, Error, ToSchema)]
 pub enum AppError {
     #[display(fmt = "Empty List")]
@@ -23,16 +43,30 @@ pub enum AppError {
     ImdbIdInUse,
     #[display(fmt = "The field passed not exists in entity or is not allowed.")]
     FieldNotAllowed,
+    #[display(fmt = "Missing or invalid credentials.")]
+    Unauthorized,
+    #[display(fmt = "The query string is too long.")]
+    UriTooLong,
+    #[display(fmt = "Too many requests, try again later.")]
+    TooManyRequests,
     #[display(fmt = "An internal server error ocurred.")]
     InternalServerError,
     #[display(fmt = "Error in Validation: ({_0})")]
     ValidationAppError(#[error(not(source))] String),
+    #[display(fmt = "Bad Request: ({_0})")]
+    BadRequest(#[error(not(source))] String),
+    /// Request body validation failures, answered as a JSON array of `FieldError`
+    #[display(fmt = "Error in Validation: ({})", "format_field_errors(_0)")]
+    InvalidFields(#[error(not(source))] Vec<FieldError>),
 }
 
 impl ResponseError for AppError {
     fn error_response(&self) -> HttpResponse {
         match *self {
             AppError::Empty => HttpResponse::build(self.status_code()).finish(),
+            AppError::InvalidFields(ref errors) => {
+                HttpResponse::build(self.status_code()).json(errors)
+            }
             _ => HttpResponse::build(self.status_code()).json(self.to_string()),
         }
     }
@@ -47,12 +81,83 @@ impl ResponseError for AppError {
             AppError::NotExists => StatusCode::BAD_REQUEST,
             AppError::ImdbIdInUse => StatusCode::BAD_REQUEST,
             AppError::FieldNotAllowed => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized => StatusCode::UNAUTHORIZED,
+            AppError::UriTooLong => StatusCode::URI_TOO_LONG,
+            AppError::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
             AppError::InternalServerError => StatusCode::INTERNAL_SERVER_ERROR,
             AppError::ValidationAppError(_) => StatusCode::BAD_REQUEST,
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::InvalidFields(_) => StatusCode::BAD_REQUEST,
         }
     }
 }
 
+/// Logs a failed database call with its context and maps it to `InternalServerError`, so
+/// `.map_err(internal_error("..."))?` replaces panicking on a dropped connection.
+pub fn internal_error<C: Display, E: Display>(context: C) -> impl Fn(E) -> AppError {
+    move |err| {
+        error!("{} [{}]", context, err);
+        AppError::InternalServerError
+    }
+}
+
+/// The `ObjectId` MongoDB generated for an insert; any other `_id` type is a server error.
+pub fn inserted_object_id(inserted_id: &bson::Bson) -> Result<bson::oid::ObjectId, AppError> {
+    inserted_id
+        .as_object_id()
+        .ok_or(format!("inserted _id is not an ObjectId: {}", inserted_id))
+        .map_err(internal_error("Error reading the inserted id"))
+}
+
+/// MongoDB's duplicate key error, raised by the unique `imdbId` indexes.
+pub const DUPLICATE_KEY_CODE: i32 = 11000;
+
+/// `internal_error` for inserts: a duplicate key means a concurrent create with the same
+/// `imdbId` got past the `find_one` checks and lost against the unique index.
+pub fn insert_error<C: Display>(context: C) -> impl Fn(MongoError) -> AppError {
+    move |err| {
+        if is_duplicate_key(&err) {
+            warn!("{} [{}]", context, AppError::AlreadyExists);
+            return AppError::AlreadyExists;
+        }
+        error!("{} [{}]", context, err);
+        AppError::InternalServerError
+    }
+}
+
+fn is_duplicate_key(err: &MongoError) -> bool {
+    matches!(
+        err.kind.as_ref(),
+        ErrorKind::Write(WriteFailure::WriteError(write_error))
+            if write_error.code == DUPLICATE_KEY_CODE
+    )
+}
+
+/// `QueryConfig` error handler, so a bad query param (`?page=abc`) answers with the same
+/// validation body as the rest of the API instead of actix's plain-text error.
+pub fn query_error_handler(err: QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    // Drop actix's "Query deserialize error" prefix, keep serde's reason
+    let detail = match err {
+        QueryPayloadError::Deserialize(err) => err.to_string(),
+        err => err.to_string(),
+    };
+    let msg = format!("query: {}", detail);
+    error!("Error in Validation: [{msg}]");
+    AppError::ValidationAppError(msg).into()
+}
+
+/// `JsonConfig` error handler, so a malformed request body answers with a JSON `AppError`
+/// instead of actix's plain-text error.
+pub fn json_error_handler(err: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    // Drop actix's "Json deserialize error" prefix, keep serde's reason
+    let detail = match err {
+        JsonPayloadError::Deserialize(err) => err.to_string(),
+        err => err.to_string(),
+    };
+    error!("Error in request body: [{detail}]");
+    AppError::BadRequest(detail).into()
+}
+
 impl From<bson::oid::Error> for AppError {
     fn from(value: bson::oid::Error) -> Self {
         let _ = value;
@@ -63,47 +168,39 @@ impl From<bson::oid::Error> for AppError {
 
 impl From<ValidationErrors> for AppError {
     fn from(err: ValidationErrors) -> Self {
-        let msg = format_validation_errors(&err);
-        error!("Error in Validation: [{msg}]");
-        AppError::ValidationAppError(msg)
+        let mut field_errors = Vec::new();
+        collect_field_errors(&err, "", &mut field_errors);
+        field_errors.sort_by(|a, b| a.field.cmp(&b.field));
+        let err = AppError::InvalidFields(field_errors);
+        error!("{err}");
+        err
     }
 }
 
-fn format_validation_errors(errors: &ValidationErrors) -> String {
-    format_errors(errors, 0)
-}
-
-fn format_errors(errors: &ValidationErrors, depth: usize) -> String {
-    let indent = " ".repeat(depth);
+fn format_field_errors(errors: &[FieldError]) -> String {
     errors
-        .errors()
         .iter()
-        .map(|(field, error)| match error {
-            validator::ValidationErrorsKind::Struct(nested_errors) => {
-                format!(
-                    "{}{}: {}",
-                    indent,
-                    field,
-                    format_errors(nested_errors, depth + 1)
-                )
+        .map(|error| format!("{}: {}", error.field, error.messages.join(", ")))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Flattens `errors` into one `FieldError` per field, nested structs and lists included.
+fn collect_field_errors(errors: &ValidationErrors, prefix: &str, out: &mut Vec<FieldError>) {
+    for (field, kind) in errors.errors() {
+        let path = format!("{}{}", prefix, to_camel_case(field));
+        match kind {
+            ValidationErrorsKind::Struct(nested) => {
+                collect_field_errors(nested, &format!("{}.", path), out)
             }
-            validator::ValidationErrorsKind::List(list_errors) => {
-                let nested = list_errors
-                    .iter()
-                    .map(|(index, errors)| {
-                        format!(
-                            "{} [{}]: {}",
-                            indent,
-                            index,
-                            format_errors(errors, depth + 2)
-                        )
-                    })
-                    .collect::<Vec<_>>()
-                    .join(";");
-                format!("{}{}: {}", indent, field, nested)
+            ValidationErrorsKind::List(items) => {
+                for (index, nested) in items {
+                    collect_field_errors(nested, &format!("{}[{}].", path, index), out);
+                }
             }
-            validator::ValidationErrorsKind::Field(field_errors) => {
-                let messages = field_errors
+            ValidationErrorsKind::Field(field_errors) => out.push(FieldError {
+                field: path,
+                messages: field_errors
                     .iter()
                     .map(|error| {
                         error
@@ -112,11 +209,140 @@ fn format_errors(errors: &ValidationErrors, depth: usize) -> String {
                             .map(|m| m.to_string())
                             .unwrap_or_else(|| "Unknown error".to_string())
                     })
-                    .collect::<Vec<_>>()
-                    .join(", ");
-                format!("{}{}: {}", indent, field, messages)
-            }
+                    .collect(),
+            }),
+        }
+    }
+}
+
+/// Validator reports the Rust field names; the request bodies use camelCase.
+fn to_camel_case(field: &str) -> String {
+    let mut parts = field.split('_');
+    let mut camel = parts.next().unwrap_or_default().to_string();
+    for part in parts {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            camel.extend(first.to_uppercase());
+            camel.push_str(chars.as_str());
+        }
+    }
+    camel
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::body::to_bytes;
+    use mongodb::{bson::doc, error::WriteError};
+    use validator::Validate;
+
+    use super::*;
+
+    // Auxiliary Functions
+
+    fn build_write_error(code: i32) -> MongoError {
+        let write_error: WriteError = bson::from_document(doc! {
+            "code": code,
+            "errmsg": "E11000 duplicate key error collection: cinema-rust-db.movies index: imdbId_1",
         })
-        .collect::<Vec<_>>()
-        .join("; ")
+        .unwrap();
+        MongoError::from(ErrorKind::Write(WriteFailure::WriteError(write_error)))
+    }
+
+    #[derive(Validate)]
+    struct EpisodeForm {
+        #[validate(length(min = 1, message = "The episode title cannot be empty"))]
+        title: String,
+    }
+
+    #[derive(Validate)]
+    struct SeasonForm {
+        #[validate(length(min = 1, message = "The season overview cannot be empty"))]
+        overview: String,
+        #[validate(nested)]
+        episode_list: Vec<EpisodeForm>,
+    }
+
+    #[derive(Validate)]
+    struct SeriesForm {
+        #[validate(
+            length(min = 1, message = "The series title cannot be empty"),
+            contains(pattern = "tt", message = "The title must contain 'tt'")
+        )]
+        title: String,
+        #[validate(nested)]
+        season_list: Vec<SeasonForm>,
+    }
+
+    // Unit Tests
+
+    #[test]
+    fn test_inserted_object_id() {
+        let oid = bson::oid::ObjectId::new();
+        assert_eq!(inserted_object_id(&bson::Bson::ObjectId(oid)), Ok(oid));
+        assert_eq!(
+            inserted_object_id(&bson::Bson::String("tt12345".to_string())),
+            Err(AppError::InternalServerError)
+        );
+    }
+
+    #[test]
+    fn test_insert_error_duplicate_key_already_exists() {
+        let map_err = insert_error("Error creating movie with imdbId: 'tt12345'");
+        assert_eq!(map_err(build_write_error(11000)), AppError::AlreadyExists);
+        assert_eq!(
+            map_err(build_write_error(121)),
+            AppError::InternalServerError
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_validation_app_error_body_stays_a_string() {
+        let err = AppError::ValidationAppError("Malformed cursor: 'abc'".to_string());
+        let body = to_bytes(err.error_response().into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!("Error in Validation: (Malformed cursor: 'abc')")
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_validation_errors_body_groups_messages_by_field() {
+        let series = SeriesForm {
+            title: String::new(),
+            season_list: vec![SeasonForm {
+                overview: String::new(),
+                episode_list: vec![
+                    EpisodeForm {
+                        title: "Piloto".to_string(),
+                    },
+                    EpisodeForm {
+                        title: String::new(),
+                    },
+                ],
+            }],
+        };
+        let err = AppError::from(series.validate().unwrap_err());
+        assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+
+        let body = to_bytes(err.error_response().into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!([
+                {
+                    "field": "seasonList[0].episodeList[1].title",
+                    "messages": ["The episode title cannot be empty"]
+                },
+                {
+                    "field": "seasonList[0].overview",
+                    "messages": ["The season overview cannot be empty"]
+                },
+                {
+                    "field": "title",
+                    "messages": ["The series title cannot be empty", "The title must contain 'tt'"]
+                },
+            ])
+        );
+    }
 }
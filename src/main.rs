@@ -1,36 +1,64 @@
+mod config;
 mod error;
+mod middleware;
 mod models;
 mod routes;
 mod services;
 #[cfg(test)]
 mod tests;
 
-use std::{collections::HashMap, net::Ipv4Addr};
+use std::{
+    collections::HashMap,
+    env,
+    io::{self, Write},
+};
 
 use actix_web::{
     get,
-    middleware::Logger,
+    middleware::{Logger, NormalizePath},
     web::{self, Data, ServiceConfig},
     App, HttpResponse, HttpServer, Responder,
 };
+use config::{parse_env_value, DEFAULT_HOST, DEFAULT_PORT};
 use env_logger::Env;
-use log::info;
+use log::{error, info, warn};
+use middleware::{
+    anonymous_writes_allowed, build_cors, current_request_id, jwt_decoding_key,
+    parse_allowed_origins, require_auth, MaxQueryLength, RateLimit, RequestIdHeader,
+};
 use routes::{
+    admin::{
+        get_duplicate_movies, get_genre_stats, get_incomplete_media, get_raw_movie_by_id,
+        post_backfill_review_parents, post_cache_purge, post_dedupe_review_ids, post_merge_movies,
+        post_normalize_durations, post_recount_reviews, post_reindex,
+    },
     movie::{
-        create_movie, delete_movie_by_id, get_movie_by_id, get_movie_by_imdb_id, get_movies,
-        patch_movie_by_id, update_movie_by_id,
+        create_movie, create_movies_bulk, delete_movie_by_id, get_movie_by_id,
+        get_movie_by_imdb_id, get_movie_count, get_movie_date_range, get_movie_genres,
+        get_movie_imdb_ids, get_movie_patchable_fields, get_movies, get_random_movie,
+        patch_movie_by_id, patch_movie_many_by_id, put_movie_genres, update_movie_by_id,
+        upsert_movie_by_imdb_id,
     },
     review::{
-        create_review, delete_review_by_id, get_review_by_id, get_reviews, get_reviews_by_imdb_id,
-        patch_review_by_id, update_review_by_id,
+        create_review, create_reviews_bulk, delete_review_by_id, export_reviews_ndjson,
+        get_latest_reviews, get_review_by_id, get_review_patchable_fields, get_reviews,
+        get_reviews_by_imdb_id, get_reviews_with_parents, patch_review_by_id,
+        reassign_review_by_id, update_review_by_id,
     },
     series::{
-        create_series, delete_series_by_id, get_series, get_series_by_id, get_series_by_imdb_id,
-        patch_series_by_id, update_series_by_id,
+        add_series_season_episode, create_series, delete_series_by_id, get_series,
+        get_series_by_id, get_series_by_imdb_id, get_series_count, get_series_flattened_episodes,
+        get_series_genres, get_series_patchable_fields, get_series_season,
+        get_series_total_runtime, patch_series_by_id, replace_series_seasons, update_series_by_id,
+    },
+    stats::get_global_stats,
+    title::{
+        get_most_reviewed_titles, get_popular_titles, get_title_by_imdb_id, post_missing_titles,
+        search_titles,
     },
 };
 use serde_json::Map;
-use services::db::Database;
+use services::db::{Database, HealthCheck};
 use utoipa::OpenApi;
 use utoipa_redoc::{Redoc, Servable};
 use utoipa_scalar::{Scalar, Servable as ScalarServable};
@@ -53,28 +81,42 @@ async fn ping() -> impl Responder {
 #[utoipa::path(
     path = "/api/v1/health",
     responses(
-        (status = 200, description = "Health check", body = String, content_type = "application/json", example = json!(HashMap::from([("status".to_string(), "UP".to_string()), ("message".to_string(), "All systems working correctly.".to_string())]))),
+        (status = 207, description = "Health check, the API and MongoDB are reachable", body = String, content_type = "application/json", example = json!(HashMap::from([("status".to_string(), "UP".to_string()), ("dbStatus".to_string(), "UP".to_string()), ("message".to_string(), "All systems working correctly.".to_string())]))),
+        (status = 503, description = "MongoDB does not answer the ping", body = String, content_type = "application/json", example = json!(HashMap::from([("status".to_string(), "DOWN".to_string()), ("dbStatus".to_string(), "DOWN".to_string()), ("message".to_string(), "The database is not reachable.".to_string())]))),
     ),
     tag = "General"
 )]
 #[get("/health")]
-async fn health() -> impl Responder {
+async fn health(db: Data<Database>) -> impl Responder {
+    health_response(db.get_ref()).await
+}
+
+async fn health_response<H: HealthCheck>(checker: &H) -> HttpResponse {
+    let (status, message) = match checker.ping().await {
+        Ok(()) => ("UP", "All systems working correctly."),
+        Err(_) => ("DOWN", "The database is not reachable."),
+    };
     let mut response = Map::new();
     response.insert(
         "status".to_string(),
-        serde_json::Value::String("UP".to_string()),
+        serde_json::Value::String(status.to_string()),
+    );
+    response.insert(
+        "dbStatus".to_string(),
+        serde_json::Value::String(status.to_string()),
     );
     response.insert(
         "message".to_string(),
-        serde_json::Value::String("All systems working correctly.".to_string()),
+        serde_json::Value::String(message.to_string()),
     );
-    HttpResponse::MultiStatus().json(response)
+    match status {
+        "UP" => HttpResponse::MultiStatus().json(response),
+        _ => HttpResponse::ServiceUnavailable().json(response),
+    }
 }
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    const PORT: u16 = 8080;
-
     #[derive(OpenApi)]
     #[openapi(
         servers(
@@ -86,43 +128,109 @@ async fn main() -> std::io::Result<()> {
             routes::movie::get_movies,
             routes::movie::get_movie_by_id,
             routes::movie::get_movie_by_imdb_id,
+            routes::movie::get_random_movie,
+            routes::movie::get_movie_count,
             routes::movie::create_movie,
+            routes::movie::create_movies_bulk,
             routes::movie::delete_movie_by_id,
             routes::movie::update_movie_by_id,
             routes::movie::patch_movie_by_id,
+            routes::movie::patch_movie_many_by_id,
+            routes::movie::put_movie_genres,
+            routes::movie::upsert_movie_by_imdb_id,
+            routes::movie::get_movie_patchable_fields,
+            routes::movie::get_movie_date_range,
+            routes::movie::get_movie_genres,
+            routes::movie::get_movie_imdb_ids,
             routes::series::get_series,
             routes::series::get_series_by_id,
+            routes::series::get_series_count,
+            routes::series::get_series_genres,
+            routes::series::get_series_total_runtime,
+            routes::series::get_series_season,
+            routes::series::add_series_season_episode,
+            routes::series::replace_series_seasons,
+            routes::series::get_series_flattened_episodes,
             routes::series::get_series_by_imdb_id,
             routes::series::create_series,
             routes::series::delete_series_by_id,
             routes::series::update_series_by_id,
             routes::series::patch_series_by_id,
+            routes::series::get_series_patchable_fields,
             routes::review::get_reviews,
+            routes::review::get_reviews_with_parents,
+            routes::review::export_reviews_ndjson,
             routes::review::get_reviews_by_imdb_id,
+            routes::review::get_latest_reviews,
             routes::review::get_review_by_id,
             routes::review::create_review,
+            routes::review::create_reviews_bulk,
             routes::review::delete_review_by_id,
             routes::review::update_review_by_id,
-            routes::review::patch_review_by_id
+            routes::review::patch_review_by_id,
+            routes::review::reassign_review_by_id,
+            routes::review::get_review_patchable_fields,
+            routes::admin::get_raw_movie_by_id,
+            routes::admin::get_incomplete_media,
+            routes::admin::get_duplicate_movies,
+            routes::admin::get_genre_stats,
+            routes::stats::get_global_stats,
+            routes::admin::post_merge_movies,
+            routes::admin::post_normalize_durations,
+            routes::admin::post_recount_reviews,
+            routes::admin::post_dedupe_review_ids,
+            routes::admin::post_reindex,
+            routes::admin::post_backfill_review_parents,
+            routes::admin::post_cache_purge,
+            routes::title::get_popular_titles,
+            routes::title::get_most_reviewed_titles,
+            routes::title::post_missing_titles,
+            routes::title::search_titles,
+            routes::title::get_title_by_imdb_id
         ),
         components(
-            schemas(error::AppError, models::movie::MovieDoc, models::movie::MovieRequest, models::movie::MovieResponse, routes::movie::PatchParams, models::series::SeriesDoc, models::series::SeriesRequest, models::series::SeriesResponse, models::series::Season, models::series::Episode, models::review::ReviewResponseDoc, models::review::ReviewRequest, models::review::ReviewUpdate)
+            schemas(error::AppError, error::FieldError, models::fields::PatchableField, models::admin::IncompleteMedia, models::admin::DuplicateGroup, models::admin::GenreStats, models::admin::GlobalStats, models::admin::MergeMoviesRequest, models::admin::CachePurgeRequest, models::title::PopularTitle, models::title::MostReviewedTitle, models::title::MissingTitlesRequest, models::title::SearchHit, services::maintenance::MaintenanceReport, models::movie::MovieDoc, models::movie::MovieRequest, models::movie::GenresRequest, models::movie::MovieResponse, routes::movie::PatchParams, models::series::SeriesDoc, models::series::SeriesRequest, models::series::SeriesResponse, models::series::Season, models::series::Episode, models::series::FlatEpisode, models::review::ReviewResponseDoc, models::review::ReviewParent, models::review::ReviewRequest, models::review::ReviewUpdate, models::review::ReviewReassign)
         ),
         tags(
             (name = "General", description = "Some endpoints for general purposes."),
             (name = "Movies", description = "Movies management endpoints."),
             (name = "Series", description = "Series management endpoints."),
-            (name = "Reviews", description = "Reviews management endpoints.")
+            (name = "Reviews", description = "Reviews management endpoints."),
+            (name = "Titles", description = "Endpoints across movies and series."),
+            (name = "Search", description = "Search across movies and series."),
+            (name = "Stats", description = "Public totals for dashboards."),
+            (name = "Admin", description = "Admin endpoints for support and diagnostics.")
         )
     )]
     struct ApiDoc;
 
     let openapi = ApiDoc::openapi();
 
+    env_logger::Builder::from_env(Env::default().default_filter_or("info"))
+        .format(format_log_line)
+        .init();
     let db = Database::init().await;
     let db_data = Data::new(db);
-    env_logger::init_from_env(Env::default().default_filter_or("info"));
-    info!("🚀 API is UP and running on port {}!", PORT);
+    // Read after `Database::init`, which loads the .env file
+    let port = parse_env_value("PORT", env::var("PORT").ok(), DEFAULT_PORT);
+    let host = parse_env_value("HOST", env::var("HOST").ok(), DEFAULT_HOST);
+    let allowed_origins = env::var("ALLOWED_ORIGINS").ok();
+    match parse_allowed_origins(allowed_origins.as_deref()) {
+        Some(origins) => info!("CORS allowed origins: {}", origins.join(", ")),
+        None => warn!("ALLOWED_ORIGINS is not set, CORS allows any origin"),
+    }
+    if jwt_decoding_key(env::var("JWT_SECRET").ok().as_deref()).is_none() {
+        if !anonymous_writes_allowed() {
+            error!(
+                "JWT_SECRET is not set, set it or ALLOW_ANONYMOUS_WRITES=true to keep writes open"
+            );
+            panic!("JWT_SECRET is not set");
+        }
+        warn!("JWT_SECRET is not set and ALLOW_ANONYMOUS_WRITES=true, anyone can write movies, series and reviews");
+    }
+    // Built once so every worker shares the same counters
+    let rate_limit = RateLimit::from_env();
+    info!("🚀 API is UP and running on {}:{}!", host, port);
 
     HttpServer::new(move || {
         App::new()
@@ -134,46 +242,127 @@ async fn main() -> std::io::Result<()> {
             )
             .service(Redoc::with_url("/api/redoc", openapi.clone()))
             .service(Scalar::with_url("/api/scalar", openapi.clone()))
+            .wrap(MaxQueryLength::from_env())
+            .wrap(rate_limit.clone())
+            .wrap(build_cors(allowed_origins.as_deref()))
             .wrap(Logger::default())
+            .wrap(RequestIdHeader)
     })
-    .bind((Ipv4Addr::UNSPECIFIED, PORT))?
+    .bind((host, port))?
     .run()
     .await
 }
 
+/// env_logger's usual line plus the id of the request being handled, when there is one.
+fn format_log_line(buf: &mut env_logger::fmt::Formatter, record: &log::Record) -> io::Result<()> {
+    match current_request_id() {
+        Some(request_id) => writeln!(
+            buf,
+            "[{} {} {}] [request {}] {}",
+            buf.timestamp(),
+            record.level(),
+            record.target(),
+            request_id,
+            record.args()
+        ),
+        None => writeln!(
+            buf,
+            "[{} {} {}] {}",
+            buf.timestamp(),
+            record.level(),
+            record.target(),
+            record.args()
+        ),
+    }
+}
+
 pub fn routes_config(conf: &mut ServiceConfig) {
+    // Trimmed on the API scope only: the swagger-ui index needs its `/api/swagger-ui/` slash
     let scope = web::scope("/api/v1")
+        .wrap(NormalizePath::trim())
         .service(ping)
         .service(health)
+        .service(search_titles)
+        .service(get_title_by_imdb_id)
+        .service(get_global_stats)
         .service(
             web::scope("/movies")
+                .wrap(require_auth())
                 .service(get_movies)
                 .service(get_movie_by_id)
                 .service(get_movie_by_imdb_id)
+                .service(get_random_movie)
+                .service(get_movie_count)
+                .service(get_movie_genres)
                 .service(create_movie)
+                .service(create_movies_bulk)
                 .service(delete_movie_by_id)
                 .service(update_movie_by_id)
-                .service(patch_movie_by_id),
+                .service(patch_movie_by_id)
+                .service(patch_movie_many_by_id)
+                .service(put_movie_genres)
+                .service(upsert_movie_by_imdb_id)
+                .service(get_movie_patchable_fields)
+                .service(get_movie_date_range)
+                .service(get_movie_imdb_ids),
         )
         .service(
             web::scope("/series")
+                .wrap(require_auth())
                 .service(get_series)
                 .service(get_series_by_id)
+                .service(get_series_count)
+                .service(get_series_genres)
+                .service(get_series_total_runtime)
+                .service(get_series_season)
+                .service(add_series_season_episode)
+                .service(replace_series_seasons)
+                .service(get_series_flattened_episodes)
                 .service(get_series_by_imdb_id)
                 .service(create_series)
                 .service(delete_series_by_id)
                 .service(update_series_by_id)
-                .service(patch_series_by_id),
+                .service(patch_series_by_id)
+                .service(get_series_patchable_fields),
         )
         .service(
             web::scope("/reviews")
+                .wrap(require_auth())
                 .service(get_reviews)
+                .service(get_reviews_with_parents)
+                .service(export_reviews_ndjson)
+                .service(get_latest_reviews)
                 .service(get_review_by_id)
                 .service(get_reviews_by_imdb_id)
                 .service(create_review)
+                .service(create_reviews_bulk)
                 .service(delete_review_by_id)
                 .service(update_review_by_id)
-                .service(patch_review_by_id),
+                .service(patch_review_by_id)
+                .service(reassign_review_by_id)
+                .service(get_review_patchable_fields),
+        )
+        .service(
+            web::scope("/titles")
+                .service(get_popular_titles)
+                .service(get_most_reviewed_titles)
+                .service(post_missing_titles),
+        )
+        .service(
+            web::scope("/admin")
+                .service(get_raw_movie_by_id)
+                .service(get_incomplete_media)
+                .service(get_duplicate_movies)
+                .service(get_genre_stats)
+                .service(post_merge_movies)
+                .service(post_normalize_durations)
+                .service(post_recount_reviews)
+                .service(post_dedupe_review_ids)
+                .service(post_reindex)
+                .service(post_backfill_review_parents)
+                .service(post_cache_purge),
         );
-    conf.service(scope);
+    conf.app_data(web::QueryConfig::default().error_handler(error::query_error_handler))
+        .app_data(web::JsonConfig::default().error_handler(error::json_error_handler))
+        .service(scope);
 }
@@ -0,0 +1,542 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    env,
+    future::{ready, Ready},
+    net::{IpAddr, Ipv4Addr},
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use actix_cors::Cors;
+use actix_web::{
+    body::EitherBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{HeaderName, HeaderValue},
+    http::{header, Method},
+    Error, HttpMessage, HttpRequest, ResponseError,
+};
+use futures_util::future::LocalBoxFuture;
+use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+const DEFAULT_MAX_QUERY_LENGTH: usize = 2048;
+const DEFAULT_RATE_LIMIT_PER_MIN: usize = 120;
+/// Tracked clients above which the ones idle for a whole window are dropped.
+const RATE_LIMIT_SWEEP_THRESHOLD: usize = 1024;
+
+/// Methods used by the routes, allowed on cross-origin requests.
+pub const CORS_ALLOWED_METHODS: [&str; 5] = ["GET", "POST", "PUT", "PATCH", "DELETE"];
+
+/// Origins listed in `ALLOWED_ORIGINS` (comma-separated, trailing '/' ignored), or `None` to
+/// allow any origin: the development default when it is unset, empty or contains `*`.
+pub fn parse_allowed_origins(raw: Option<&str>) -> Option<Vec<String>> {
+    let origins: Vec<String> = raw?
+        .split(',')
+        .map(|origin| origin.trim().trim_end_matches('/').to_string())
+        .filter(|origin| !origin.is_empty())
+        .collect();
+    if origins.is_empty() || origins.iter().any(|origin| origin == "*") {
+        return None;
+    }
+    Some(origins)
+}
+
+/// CORS middleware for the raw `ALLOWED_ORIGINS` value, see `parse_allowed_origins`.
+pub fn build_cors(allowed_origins: Option<&str>) -> Cors {
+    let cors = match parse_allowed_origins(allowed_origins) {
+        Some(origins) => origins
+            .iter()
+            .fold(Cors::default(), |cors, origin| cors.allowed_origin(origin)),
+        None => Cors::default().allow_any_origin(),
+    };
+    cors.allowed_methods(CORS_ALLOWED_METHODS)
+        .allow_any_header()
+        .max_age(3600)
+}
+
+/// Rejects requests whose query string is longer than `max_len` bytes, so a huge `title` or
+/// `q` never reaches the regex-backed searches.
+#[derive(Debug, Clone, Copy)]
+pub struct MaxQueryLength {
+    max_len: usize,
+}
+
+impl MaxQueryLength {
+    pub fn new(max_len: usize) -> Self {
+        Self { max_len }
+    }
+
+    /// Reads `MAX_QUERY_LENGTH`, falling back to 2048 when it is unset or not a number.
+    pub fn from_env() -> Self {
+        let max_len = match env::var("MAX_QUERY_LENGTH") {
+            Ok(raw) => raw.trim().parse().unwrap_or_else(|_| {
+                warn!(
+                    "Invalid MAX_QUERY_LENGTH '{}', using default {}",
+                    raw, DEFAULT_MAX_QUERY_LENGTH
+                );
+                DEFAULT_MAX_QUERY_LENGTH
+            }),
+            Err(_) => DEFAULT_MAX_QUERY_LENGTH,
+        };
+        Self::new(max_len)
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for MaxQueryLength
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = MaxQueryLengthMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(MaxQueryLengthMiddleware {
+            service,
+            max_len: self.max_len,
+        }))
+    }
+}
+
+pub struct MaxQueryLengthMiddleware<S> {
+    service: S,
+    max_len: usize,
+}
+
+impl<S, B> Service<ServiceRequest> for MaxQueryLengthMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let query_len = req.query_string().len();
+        if query_len > self.max_len {
+            warn!(
+                "Warn in {} with a query of {} bytes (max {}) [{}]",
+                req.path(),
+                query_len,
+                self.max_len,
+                AppError::UriTooLong
+            );
+            let res = AppError::UriTooLong.error_response().map_into_right_body();
+            return Box::pin(async move { Ok(req.into_response(res)) });
+        }
+        let fut = self.service.call(req);
+        Box::pin(async move { fut.await.map(ServiceResponse::map_into_left_body) })
+    }
+}
+
+/// `role` claim of a token; tokens without one belong to regular users. `support` staff can
+/// read the raw admin documents with the internal fields masked.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Admin,
+    Support,
+    #[default]
+    User,
+}
+
+/// Claims of the Bearer tokens accepted on the write endpoints.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    pub exp: usize,
+    #[serde(default)]
+    pub role: Role,
+}
+
+/// HS256 key of `secret`, `None` when it is unset or empty.
+pub fn jwt_decoding_key(secret: Option<&str>) -> Option<DecodingKey> {
+    secret
+        .filter(|secret| !secret.is_empty())
+        .map(|secret| DecodingKey::from_secret(secret.as_bytes()))
+}
+
+/// Claims of a valid `Bearer <jwt>` header value (HS256, not expired), `Unauthorized` otherwise.
+pub fn validate_bearer(
+    header: Option<&str>,
+    key: Option<&DecodingKey>,
+) -> Result<Claims, AppError> {
+    let token = header
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(AppError::Unauthorized)?;
+    let key = key.ok_or(AppError::Unauthorized)?;
+    decode::<Claims>(token.trim(), key, &Validation::new(Algorithm::HS256))
+        .map(|data| data.claims)
+        .map_err(|_| AppError::Unauthorized)
+}
+
+/// Whether `ALLOW_ANONYMOUS_WRITES=true` keeps writes open when `JWT_SECRET` is unset.
+pub fn anonymous_writes_allowed() -> bool {
+    env::var("ALLOW_ANONYMOUS_WRITES")
+        .map(|val| val.trim().eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+/// Requires a Bearer JWT on every request but GET, HEAD and OPTIONS, so reads stay public.
+/// Without a secret every write is rejected, unless `allow_anonymous` opts back into open writes.
+#[derive(Clone)]
+pub struct RequireAuth {
+    key: Option<DecodingKey>,
+    allow_anonymous: bool,
+}
+
+impl RequireAuth {
+    pub fn new(secret: Option<&str>, allow_anonymous: bool) -> Self {
+        Self {
+            key: jwt_decoding_key(secret),
+            allow_anonymous,
+        }
+    }
+}
+
+/// `RequireAuth` with the HS256 secret of the `JWT_SECRET` env var and the
+/// `ALLOW_ANONYMOUS_WRITES` flag.
+pub fn require_auth() -> RequireAuth {
+    RequireAuth::new(
+        env::var("JWT_SECRET").ok().as_deref(),
+        anonymous_writes_allowed(),
+    )
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequireAuth
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RequireAuthMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequireAuthMiddleware {
+            service,
+            key: self.key.clone(),
+            allow_anonymous: self.allow_anonymous,
+        }))
+    }
+}
+
+pub struct RequireAuthMiddleware<S> {
+    service: S,
+    key: Option<DecodingKey>,
+    allow_anonymous: bool,
+}
+
+impl<S, B> Service<ServiceRequest> for RequireAuthMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let open_writes = self.key.is_none() && self.allow_anonymous;
+        if !open_writes && ![Method::GET, Method::HEAD, Method::OPTIONS].contains(req.method()) {
+            let header = req
+                .headers()
+                .get(header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok());
+            match validate_bearer(header, self.key.as_ref()) {
+                Ok(claims) => {
+                    req.extensions_mut().insert(claims);
+                }
+                Err(err) => {
+                    warn!("Warn in {} {} [{}]", req.method(), req.path(), err);
+                    let res = err.error_response().map_into_right_body();
+                    return Box::pin(async move { Ok(req.into_response(res)) });
+                }
+            }
+        }
+        let fut = self.service.call(req);
+        Box::pin(async move { fut.await.map(ServiceResponse::map_into_left_body) })
+    }
+}
+
+/// Sliding window rate limiter keyed by client IP: at most `limit` requests in any `window`.
+/// Clones share the counters, so a single instance covers every worker.
+#[derive(Debug, Clone)]
+pub struct RateLimit {
+    limit: usize,
+    window: Duration,
+    hits: Arc<Mutex<HashMap<IpAddr, VecDeque<Instant>>>>,
+}
+
+impl RateLimit {
+    pub fn new(limit: usize, window: Duration) -> Self {
+        Self {
+            limit,
+            window,
+            hits: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Reads `RATE_LIMIT_PER_MIN` (120 by default, 0 disables the limiter).
+    pub fn from_env() -> Self {
+        let limit = match env::var("RATE_LIMIT_PER_MIN") {
+            Ok(raw) => raw.trim().parse().unwrap_or_else(|_| {
+                warn!(
+                    "Invalid RATE_LIMIT_PER_MIN '{}', using default {}",
+                    raw, DEFAULT_RATE_LIMIT_PER_MIN
+                );
+                DEFAULT_RATE_LIMIT_PER_MIN
+            }),
+            Err(_) => DEFAULT_RATE_LIMIT_PER_MIN,
+        };
+        Self::new(limit, Duration::from_secs(60))
+    }
+
+    /// Records a request from `ip` at `now`, returning `false` when it goes over the limit
+    /// (rejected requests are not recorded).
+    pub fn hit(&self, ip: IpAddr, now: Instant) -> bool {
+        if self.limit == 0 {
+            return true;
+        }
+        let mut hits = self.hits.lock().unwrap();
+        if hits.len() > RATE_LIMIT_SWEEP_THRESHOLD {
+            hits.retain(|_, times| {
+                times
+                    .back()
+                    .is_some_and(|last| now.duration_since(*last) < self.window)
+            });
+        }
+        let times = hits.entry(ip).or_default();
+        while times
+            .front()
+            .is_some_and(|first| now.duration_since(*first) >= self.window)
+        {
+            times.pop_front();
+        }
+        if times.len() >= self.limit {
+            return false;
+        }
+        times.push_back(now);
+        true
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RateLimitMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitMiddleware {
+            service,
+            rate_limit: self.clone(),
+        }))
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: S,
+    rate_limit: RateLimit,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        // The peer address, not X-Forwarded-For, which any client can forge
+        let ip = req
+            .peer_addr()
+            .map_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED), |addr| addr.ip());
+        if !self.rate_limit.hit(ip, Instant::now()) {
+            warn!(
+                "Warn in {} from {} [{}]",
+                req.path(),
+                ip,
+                AppError::TooManyRequests
+            );
+            let res = AppError::TooManyRequests
+                .error_response()
+                .map_into_right_body();
+            return Box::pin(async move { Ok(req.into_response(res)) });
+        }
+        let fut = self.service.call(req);
+        Box::pin(async move { fut.await.map(ServiceResponse::map_into_left_body) })
+    }
+}
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    static CURRENT_REQUEST_ID: String;
+}
+
+/// UUID given to each request by `RequestIdHeader`, kept in the request extensions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestId(pub String);
+
+/// Id of the request `req` belongs to, `None` when `RequestIdHeader` is not mounted.
+pub fn request_id(req: &HttpRequest) -> Option<String> {
+    req.extensions().get::<RequestId>().map(|id| id.0.clone())
+}
+
+/// Id of the request whose handler is running, so the repositories can log it without taking
+/// the request. `None` outside a request (startup, seeding).
+pub fn current_request_id() -> Option<String> {
+    CURRENT_REQUEST_ID.try_with(String::clone).ok()
+}
+
+/// Tags every request with a fresh UUID: stored as `RequestId`, echoed in `X-Request-Id` and
+/// available to log lines through `current_request_id` while the request is handled.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RequestIdHeader;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestIdHeader
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestIdHeaderMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestIdHeaderMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct RequestIdHeaderMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestIdHeaderMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let id = Uuid::new_v4().to_string();
+        req.extensions_mut().insert(RequestId(id.clone()));
+        let service = Rc::clone(&self.service);
+        // The inner `call` runs inside the scope too, so its synchronous part sees the id
+        Box::pin(CURRENT_REQUEST_ID.scope(id, async move {
+            let mut res = service.call(req).await?;
+            // Echo the id stored on the request, the one handlers read through `request_id`
+            let id = request_id(res.request()).unwrap_or_default();
+            if let Ok(value) = HeaderValue::from_str(&id) {
+                res.headers_mut()
+                    .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+            }
+            Ok(res)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Unit Tests
+
+    #[test]
+    fn test_parse_allowed_origins() {
+        assert_eq!(
+            parse_allowed_origins(Some(
+                "https://cinema.example.com, https://admin.example.com/,"
+            )),
+            Some(vec![
+                "https://cinema.example.com".to_string(),
+                "https://admin.example.com".to_string()
+            ])
+        );
+        assert_eq!(parse_allowed_origins(None), None);
+        assert_eq!(parse_allowed_origins(Some(" , ")), None);
+        assert_eq!(
+            parse_allowed_origins(Some("https://cinema.example.com,*")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_validate_bearer_reads_role_claim() {
+        let key = jwt_decoding_key(Some("cinema-test-secret"));
+        let bearer = |claims: serde_json::Value| {
+            let token = jsonwebtoken::encode(
+                &jsonwebtoken::Header::default(),
+                &claims,
+                &jsonwebtoken::EncodingKey::from_secret(b"cinema-test-secret"),
+            )
+            .unwrap();
+            format!("Bearer {}", token)
+        };
+        let exp = chrono::Utc::now().timestamp() + 3600;
+
+        let admin = bearer(serde_json::json!({ "sub": "admin", "exp": exp, "role": "admin" }));
+        let claims = validate_bearer(Some(&admin), key.as_ref()).unwrap();
+        assert_eq!(claims.role, Role::Admin);
+
+        // Tokens issued before the role claim existed are regular users
+        let legacy = bearer(serde_json::json!({ "sub": "tester", "exp": exp }));
+        let claims = validate_bearer(Some(&legacy), key.as_ref()).unwrap();
+        assert_eq!(claims.role, Role::User);
+    }
+
+    #[test]
+    fn test_rate_limit_window_slides() {
+        let rate_limit = RateLimit::new(2, Duration::from_secs(60));
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let start = Instant::now();
+        assert!(rate_limit.hit(ip, start));
+        assert!(rate_limit.hit(ip, start + Duration::from_secs(30)));
+        assert!(!rate_limit.hit(ip, start + Duration::from_secs(59)));
+        // The first hit leaves the window
+        assert!(rate_limit.hit(ip, start + Duration::from_secs(60)));
+        assert!(!rate_limit.hit(ip, start + Duration::from_secs(61)));
+    }
+}
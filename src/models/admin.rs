@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct IncompleteMedia {
+    #[serde(rename(serialize = "type", deserialize = "type"))]
+    #[schema(example = "movie")]
+    pub media_type: String,
+    #[serde(rename(serialize = "_id", deserialize = "_id"))]
+    pub _id: String,
+    #[schema(example = "tt12345")]
+    pub imdb_id: String,
+    #[schema(example = "El lobo de Wall Street")]
+    pub title: String,
+    #[schema(example = json!(["backdrop"]))]
+    pub missing_fields: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct DuplicateGroup {
+    #[schema(example = "casino|1995")]
+    pub key: String,
+    #[schema(example = json!(["66a3a6c2f1a1b2c3d4e5f601", "66a3a6c2f1a1b2c3d4e5f602"]))]
+    pub ids: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeMoviesRequest {
+    #[schema(example = "66a3a6c2f1a1b2c3d4e5f601")]
+    pub keep_id: String,
+    #[schema(example = "66a3a6c2f1a1b2c3d4e5f602")]
+    pub remove_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CachePurgeRequest {
+    /// Movie or series ids to evict; every cached entry is purged when omitted
+    #[schema(example = json!(["66a3a6c2f1a1b2c3d4e5f601"]))]
+    pub ids: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GenreStats {
+    #[schema(example = "Drama")]
+    pub genre: String,
+    #[schema(example = 12)]
+    pub movie_count: u32,
+    /// Mean rating of the genre's reviews rounded to 2 decimals, `null` when there are none
+    #[schema(example = 3.75)]
+    #[serde(default)]
+    pub average_rating: Option<f64>,
+    #[schema(example = 40)]
+    pub total_reviews: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GlobalStats {
+    #[schema(example = 120)]
+    pub total_movies: u64,
+    #[schema(example = 35)]
+    pub total_series: u64,
+    #[schema(example = 980)]
+    pub total_reviews: u64,
+    /// Mean rating of every review rounded to 2 decimals, `null` when there are none
+    #[schema(example = 3.87)]
+    pub average_rating: Option<f64>,
+}
@@ -0,0 +1,102 @@
+use actix_web::http::StatusCode;
+use serde::Serialize;
+
+use crate::error::AppError;
+
+/// Outcome of one element of a bulk request, `index` being its position in the request array.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkItemResult<T> {
+    pub index: usize,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkResult<T> {
+    pub succeeded: usize,
+    pub failed: usize,
+    pub results: Vec<BulkItemResult<T>>,
+}
+
+impl<T> BulkResult<T> {
+    pub fn from_results(results: Vec<Result<T, AppError>>) -> Self {
+        let results: Vec<BulkItemResult<T>> = results
+            .into_iter()
+            .enumerate()
+            .map(|(index, result)| match result {
+                Ok(data) => BulkItemResult {
+                    index,
+                    success: true,
+                    data: Some(data),
+                    error: None,
+                },
+                Err(err) => BulkItemResult {
+                    index,
+                    success: false,
+                    data: None,
+                    error: Some(err.to_string()),
+                },
+            })
+            .collect();
+        let succeeded = results.iter().filter(|item| item.success).count();
+        Self {
+            succeeded,
+            failed: results.len() - succeeded,
+            results,
+        }
+    }
+
+    /// 201 when every item was created, 400 when none was and 207 Multi-Status when mixed.
+    pub fn status_code(&self) -> StatusCode {
+        match (self.succeeded, self.failed) {
+            (_, 0) => StatusCode::CREATED,
+            (0, _) => StatusCode::BAD_REQUEST,
+            _ => StatusCode::MULTI_STATUS,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Unit Tests
+
+    #[test]
+    fn test_bulk_result_all_succeed() {
+        let bulk = BulkResult::from_results(vec![Ok("a"), Ok("b")]);
+        assert_eq!(bulk.succeeded, 2);
+        assert_eq!(bulk.failed, 0);
+        assert_eq!(bulk.status_code(), StatusCode::CREATED);
+    }
+
+    #[test]
+    fn test_bulk_result_mixed() {
+        let bulk = BulkResult::from_results(vec![Ok("a"), Err(AppError::AlreadyExists), Ok("c")]);
+        assert_eq!(bulk.status_code(), StatusCode::MULTI_STATUS);
+
+        let json = serde_json::to_value(&bulk).unwrap();
+        assert_eq!(json["succeeded"], 2);
+        assert_eq!(json["results"][1]["index"], 1);
+        assert_eq!(json["results"][1]["success"], false);
+        assert_eq!(
+            json["results"][1]["error"],
+            AppError::AlreadyExists.to_string()
+        );
+        assert!(json["results"][1].get("data").is_none());
+        assert_eq!(json["results"][2]["data"], "c");
+    }
+
+    #[test]
+    fn test_bulk_result_all_fail() {
+        let bulk: BulkResult<String> =
+            BulkResult::from_results(vec![Err(AppError::NotExists), Err(AppError::WrongImdbId)]);
+        assert_eq!(bulk.failed, 2);
+        assert_eq!(bulk.status_code(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,144 @@
+use std::{error::Error, fmt, str::FromStr};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+lazy_static! {
+    static ref RE_DURATION: Regex =
+        Regex::new(r"^(?:(\d{1,2})h(?: (\d{1,2})m)?|(\d{1,3})m)$").unwrap();
+}
+
+/// Runtime stored as hours and minutes, always normalized so that `minutes < 60`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct RuntimeDuration {
+    pub hours: u32,
+    pub minutes: u32,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseDurationError(String);
+
+impl fmt::Display for ParseDurationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Duration '{}' must match one of these formats: '00h 00m', '00h' or '00m'",
+            self.0
+        )
+    }
+}
+
+impl Error for ParseDurationError {}
+
+impl RuntimeDuration {
+    pub fn from_minutes(total_minutes: u32) -> Self {
+        Self {
+            hours: total_minutes / 60,
+            minutes: total_minutes % 60,
+        }
+    }
+
+    pub fn total_minutes(&self) -> u32 {
+        self.hours * 60 + self.minutes
+    }
+}
+
+impl FromStr for RuntimeDuration {
+    type Err = ParseDurationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let caps = RE_DURATION
+            .captures(s.trim())
+            .ok_or_else(|| ParseDurationError(s.to_string()))?;
+        let num = |idx: usize| -> u32 {
+            caps.get(idx)
+                .and_then(|m| m.as_str().parse().ok())
+                .unwrap_or(0)
+        };
+        let (hours, minutes) = (num(1), num(2) + num(3));
+        if caps.get(1).is_some() && minutes > 59 {
+            return Err(ParseDurationError(s.to_string()));
+        }
+        Ok(Self::from_minutes(hours * 60 + minutes))
+    }
+}
+
+impl fmt::Display for RuntimeDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}h {}m", self.hours, self.minutes)
+    }
+}
+
+// Ser/De as the "Xh Ym" string so stored documents stay backward compatible
+impl Serialize for RuntimeDuration {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for RuntimeDuration {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw = String::deserialize(deserializer)?;
+        raw.parse().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Unit Tests
+
+    #[test]
+    fn test_parse_and_format_round_trip() {
+        for raw in ["2h 59m", "0h 45m", "1h 0m"] {
+            let duration: RuntimeDuration = raw.parse().unwrap();
+            assert_eq!(duration.to_string(), raw);
+        }
+    }
+
+    #[test]
+    fn test_parse_short_formats_are_normalized() {
+        assert_eq!(
+            "2h".parse::<RuntimeDuration>().unwrap().to_string(),
+            "2h 0m"
+        );
+        assert_eq!(
+            "95m".parse::<RuntimeDuration>().unwrap(),
+            RuntimeDuration {
+                hours: 1,
+                minutes: 35
+            }
+        );
+        assert_eq!(
+            "1h 5m".parse::<RuntimeDuration>().unwrap().total_minutes(),
+            65
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_inputs() {
+        for raw in ["", "2 h", "2h59m", "abc", "1h 75m", "-1h 5m"] {
+            assert!(raw.parse::<RuntimeDuration>().is_err(), "{raw}");
+        }
+    }
+
+    #[test]
+    fn test_serde_uses_string_form() {
+        let duration: RuntimeDuration = "2h 54m".parse().unwrap();
+        let json = serde_json::to_string(&duration).unwrap();
+        assert_eq!(json, "\"2h 54m\"");
+        assert_eq!(
+            serde_json::from_str::<RuntimeDuration>(&json).unwrap(),
+            duration
+        );
+        assert!(serde_json::from_str::<RuntimeDuration>("\"2 hours\"").is_err());
+    }
+}
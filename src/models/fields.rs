@@ -0,0 +1,151 @@
+use mongodb::bson::{doc, Bson, Document};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use utoipa::ToSchema;
+
+use crate::error::AppError;
+
+/// Field that can be changed through the PATCH endpoints, with the type expected for its value.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchableField {
+    #[schema(example = "title")]
+    pub name: &'static str,
+    #[schema(example = "string")]
+    pub field_type: &'static str,
+}
+
+const fn field(name: &'static str, field_type: &'static str) -> PatchableField {
+    PatchableField { name, field_type }
+}
+
+pub const MOVIE_PATCHABLE_FIELDS: &[PatchableField] = &[
+    field("imdbId", "string"),
+    field("title", "string"),
+    field("overview", "string"),
+    field("duration", "duration"),
+    field("director", "string"),
+    field("releaseDate", "date"),
+    field("trailerLink", "url"),
+    field("genres", "array<string>"),
+    field("poster", "url"),
+    field("backdrop", "url"),
+];
+
+pub const SERIES_PATCHABLE_FIELDS: &[PatchableField] = &[
+    field("imdbId", "string"),
+    field("title", "string"),
+    field("overview", "string"),
+    field("numberOfSeasons", "integer"),
+    field("creator", "string"),
+    field("releaseDate", "date"),
+    field("trailerLink", "url"),
+    field("genres", "array<string>"),
+    field("seasonList", "array<Season>"),
+    field("poster", "url"),
+    field("backdrop", "url"),
+];
+
+pub const REVIEW_PATCHABLE_FIELDS: &[PatchableField] = &[
+    field("title", "string"),
+    field("rating", "number"),
+    field("body", "string"),
+];
+
+pub fn is_patchable(fields: &[PatchableField], name: &str) -> bool {
+    fields.iter().any(|field| field.name == name)
+}
+
+/// Fields a `fields[movie]` sparse fieldset may select on a single Movie.
+pub const MOVIE_FIELDS: &[&str] = &[
+    "imdbId",
+    "title",
+    "overview",
+    "duration",
+    "director",
+    "releaseDate",
+    "trailerLink",
+    "genres",
+    "poster",
+    "backdrop",
+    "reviewIds",
+    "averageRating",
+    "createdAt",
+    "updatedAt",
+];
+
+/// Fields a `fields[movie]` sparse fieldset may select on the MovieResponse items of a list.
+pub const MOVIE_RESPONSE_FIELDS: &[&str] = &[
+    "imdbId",
+    "title",
+    "director",
+    "duration",
+    "releaseDate",
+    "poster",
+    "reviewCount",
+];
+
+/// Parses a comma separated sparse fieldset (`title,poster`), rejecting the fields that are not
+/// in `allowed` so a typo does not silently return an empty object.
+pub fn parse_sparse_fields(raw: &str, allowed: &[&str]) -> Result<Vec<String>, AppError> {
+    let fields: Vec<String> = raw
+        .split(',')
+        .map(|field| field.trim().to_string())
+        .filter(|field| !field.is_empty())
+        .collect();
+    if fields.is_empty() {
+        return Err(AppError::ValidationAppError(
+            "fields: The sparse fieldset cannot be empty".to_string(),
+        ));
+    }
+    let unknown: Vec<&str> = fields
+        .iter()
+        .filter(|field| !allowed.contains(&field.as_str()))
+        .map(|field| field.as_str())
+        .collect();
+    if !unknown.is_empty() {
+        return Err(AppError::ValidationAppError(format!(
+            "fields: Unknown fields '{}', allowed: {}",
+            unknown.join(", "),
+            allowed.join(", ")
+        )));
+    }
+    Ok(fields)
+}
+
+/// Keeps only the selected keys (and `_id`, always returned) of a serialized document.
+pub fn select_fields(mut doc: Map<String, Value>, fields: &[String]) -> Map<String, Value> {
+    doc.retain(|key, _| key == "_id" || fields.contains(key));
+    doc
+}
+
+/// MongoDB projection for a movie sparse fieldset. `_id` is always kept, and `averageRating`,
+/// computed from the reviews, projects `reviewIds` instead.
+pub fn build_movie_projection(fields: &[String]) -> Document {
+    let mut projection = doc! { "_id": 1 };
+    for field in fields {
+        match field.as_str() {
+            "averageRating" => projection.insert("reviewIds", 1),
+            field => projection.insert(field, 1),
+        };
+    }
+    projection
+}
+
+/// JSON of a projected document with the same shapes as the Doc structs: ObjectIds as hex
+/// strings and dates as RFC 3339 strings.
+pub fn projected_to_json(projected: Document) -> Map<String, Value> {
+    projected
+        .into_iter()
+        .map(|(key, val)| (key, bson_to_json(val)))
+        .collect()
+}
+
+fn bson_to_json(val: Bson) -> Value {
+    match val {
+        Bson::ObjectId(oid) => Value::String(oid.to_hex()),
+        Bson::DateTime(date) => Value::String(date.to_chrono().to_rfc3339()),
+        Bson::Array(items) => Value::Array(items.into_iter().map(bson_to_json).collect()),
+        val => val.into_relaxed_extjson(),
+    }
+}
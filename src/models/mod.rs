@@ -1,3 +1,10 @@
+pub mod admin;
+pub mod bulk;
+pub mod duration;
+pub mod fields;
 pub mod movie;
 pub mod review;
 pub mod series;
+pub mod timestamps;
+pub mod title;
+pub mod validation;
@@ -1,24 +1,32 @@
-use std::error::Error;
+use std::{borrow::Cow, collections::HashSet, error::Error};
 
 use lazy_static::lazy_static;
-use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{oid::ObjectId, DateTime};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use validator::{Validate, ValidationError};
 
+use super::{
+    duration::RuntimeDuration,
+    review::ReviewResponseDoc,
+    timestamps::{iso_date_format, legacy_timestamp},
+    validation::{
+        validate_calendar_date, validate_duration, validate_image_host, validate_trailer_host,
+        RE_MOVIE_IMDB_ID, RE_PERSON_NAME, RE_REMOTE_IMAGES,
+    },
+};
+
+/// Most genres a movie can have through `PUT /{id}/genres`.
+pub const MAX_GENRES: usize = 10;
+
 lazy_static! {
-    static ref RE_IMDB_ID: Regex = Regex::new(r"^tt\d+$").unwrap();
-    static ref RE_DURATION: Regex = Regex::new(r"^(\d{1,2})h\s(\d{1,2})m$").unwrap();
-    static ref RE_DIRECTOR: Regex =
-        Regex::new(r"^([a-zA-Z]+\.?)\s([a-zA-Z]+\.?)(?:\s([a-zA-Z]+))?$").unwrap();
     static ref RE_RELEASE_DATE: Regex =
         Regex::new(r"^(\d{4})-([1-9]|0[1-9]|1[0-2])-([1-9]|0[1-9]|[12]\d|3[01])$").unwrap();
     static ref RE_TRAILER_LINK: Regex = Regex::new(r"^((?:https?:)?//)?((?:www|m)\.)?((?:youtube(-nocookie)?\.com|youtu.be))(/(?:[\w\-]+\\?v=|embed/|live/|v/)?)([\w\-]+)(\S+)?$").unwrap();
-    static ref RE_REMOTE_IMAGES: Regex = Regex::new(r"(https?://\S+(?:png|jpe?g|webp)\S*)").unwrap();
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Movie {
     #[serde(rename(serialize = "_id", deserialize = "_id"))]
@@ -26,7 +34,7 @@ pub struct Movie {
     pub imdb_id: String,
     pub title: String,
     pub overview: String,
-    pub duration: String,
+    pub duration: RuntimeDuration,
     pub director: String,
     pub release_date: String,
     pub trailer_link: String,
@@ -34,6 +42,11 @@ pub struct Movie {
     pub poster: String,
     pub backdrop: String,
     pub review_ids: Vec<ObjectId>,
+    /// Read as the Unix epoch on documents stored before the timestamps existed.
+    #[serde(default = "legacy_timestamp")]
+    pub created_at: DateTime,
+    #[serde(default = "legacy_timestamp")]
+    pub updated_at: DateTime,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -62,13 +75,25 @@ pub struct MovieDoc {
     #[schema(example = "https://image.tmdb.org/t/p/original/7Nwnmyzrtd0FkcRyPqmdzTPppQa.jpg")]
     pub backdrop: String,
     pub review_ids: Vec<String>,
+    /// Mean rating of its reviews (findById only), `null` when it has none
+    #[schema(example = 4.33)]
+    pub average_rating: Option<f64>,
+    /// Its reviews, only present with `includeReviews=true` (findById only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reviews: Option<Vec<ReviewResponseDoc>>,
+    #[serde(with = "iso_date_format")]
+    #[schema(value_type = String, format = DateTime, example = "2024-05-07T11:56:05.792+00:00")]
+    pub created_at: DateTime,
+    #[serde(with = "iso_date_format")]
+    #[schema(value_type = String, format = DateTime, example = "2024-05-07T11:56:05.792+00:00")]
+    pub updated_at: DateTime,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
 #[serde(rename_all = "camelCase")]
 pub struct MovieRequest {
     #[validate(regex(
-        path = *RE_IMDB_ID,
+        path = *RE_MOVIE_IMDB_ID,
         message = "The imdbId must match the following format: 'tt0000'"
     ))]
     pub imdb_id: String,
@@ -76,13 +101,13 @@ pub struct MovieRequest {
     pub title: String,
     #[validate(length(min = 1, message = "The film synopsis cannot be empty"))]
     pub overview: String,
-    #[validate(regex(
-        path = *RE_DURATION,
-        message = "The duration must match the following format: '00h 00m'"
+    #[validate(custom(
+        function = "validate_duration",
+        message = "The duration must match the following formats: '00h 00m', '00h' or '00m'"
     ))]
     pub duration: String,
     #[validate(regex(
-        path = *RE_DIRECTOR,
+        path = *RE_PERSON_NAME,
         message = "The director's name must match the following format: 'Name Surname'"
     ))]
     pub director: String,
@@ -90,11 +115,16 @@ pub struct MovieRequest {
         path = *RE_RELEASE_DATE,
         message = "The release date of the movie must match the following format: 'YYYY-MM-DD'"
     ))]
+    #[validate(custom(
+        function = "validate_calendar_date",
+        message = "The release date of the movie must be a valid calendar date"
+    ))]
     pub release_date: String,
     #[validate(regex(
         path = *RE_TRAILER_LINK,
         message = "The movie trailer link has to be a valid YouTube URL"
     ))]
+    #[validate(custom(function = "validate_trailer_host"))]
     pub trailer_link: String,
     #[validate(custom(
         function = "validate_non_empty_vec",
@@ -105,14 +135,28 @@ pub struct MovieRequest {
         path = *RE_REMOTE_IMAGES,
         message = "The movie poster must be a valid URL with one of these extensions: (.jpg, .jpeg, .png or .webp)"
     ))]
+    #[validate(custom(function = "validate_image_host"))]
     pub poster: String,
     #[validate(regex(
         path = *RE_REMOTE_IMAGES,
         message = "The movie backdrop image must be a valid URL with one of these extensions: (.jpg, .jpeg, .png or .webp)"
     ))]
+    #[validate(custom(function = "validate_image_host"))]
     pub backdrop: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct GenresRequest {
+    #[validate(custom(
+        function = "validate_non_empty_vec",
+        message = "The movie has to have at least one genre"
+    ))]
+    #[validate(custom(function = "validate_genre_list"))]
+    #[schema(example = json!(["Crimen", "Drama"]))]
+    pub genres: Vec<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct MovieResponse {
@@ -120,24 +164,29 @@ pub struct MovieResponse {
     pub imdb_id: String,
     #[schema(example = "El lobo de Wall Street")]
     pub title: String,
+    #[schema(example = "Martin Scorsese")]
+    pub director: String,
     #[schema(example = "2h 59m")]
     pub duration: String,
     #[schema(example = "2014-01-17")]
     pub release_date: String,
     #[schema(example = "https://image.tmdb.org/t/p/original/jTlIYjvS16XOpsfvYCTmtEHV10K.jpg")]
     pub poster: String,
+    #[schema(example = 3)]
+    pub review_count: usize,
 }
 
 impl TryFrom<MovieRequest> for Movie {
     type Error = Box<dyn Error>;
 
     fn try_from(item: MovieRequest) -> Result<Self, Self::Error> {
+        let now = DateTime::now();
         Ok(Self {
             _id: ObjectId::new(),
             imdb_id: item.imdb_id,
             title: item.title,
             overview: item.overview,
-            duration: item.duration,
+            duration: item.duration.parse()?,
             director: item.director,
             release_date: item.release_date,
             trailer_link: item.trailer_link,
@@ -145,6 +194,8 @@ impl TryFrom<MovieRequest> for Movie {
             poster: item.poster,
             backdrop: item.backdrop,
             review_ids: Vec::new(),
+            created_at: now,
+            updated_at: now,
         })
     }
 }
@@ -156,9 +207,36 @@ impl TryFrom<Movie> for MovieResponse {
         Ok(Self {
             imdb_id: item.imdb_id,
             title: item.title,
-            duration: item.duration,
+            director: item.director,
+            duration: item.duration.to_string(),
+            release_date: item.release_date,
+            poster: item.poster,
+            review_count: item.review_ids.len(),
+        })
+    }
+}
+
+impl TryFrom<Movie> for MovieDoc {
+    type Error = Box<dyn Error>;
+
+    fn try_from(item: Movie) -> Result<Self, Self::Error> {
+        Ok(Self {
+            _id: item._id.to_hex(),
+            imdb_id: item.imdb_id,
+            title: item.title,
+            overview: item.overview,
+            duration: item.duration.to_string(),
+            director: item.director,
             release_date: item.release_date,
+            trailer_link: item.trailer_link,
+            genres: item.genres,
             poster: item.poster,
+            backdrop: item.backdrop,
+            review_ids: item.review_ids.iter().map(|id| id.to_hex()).collect(),
+            average_rating: None,
+            reviews: None,
+            created_at: item.created_at,
+            updated_at: item.updated_at,
         })
     }
 }
@@ -169,3 +247,127 @@ fn validate_non_empty_vec(vec: &[String]) -> Result<(), ValidationError> {
     }
     Ok(())
 }
+
+/// Caps the list at [`MAX_GENRES`] and rejects blank or repeated (case-insensitive) genres.
+fn validate_genre_list(genres: &[String]) -> Result<(), ValidationError> {
+    let invalid = |code: &'static str, msg: String| {
+        Err(ValidationError::new(code).with_message(Cow::Owned(msg)))
+    };
+    if genres.len() > MAX_GENRES {
+        return invalid(
+            "too_many_genres",
+            format!("The movie cannot have more than {} genres", MAX_GENRES),
+        );
+    }
+    if genres.iter().any(|genre| genre.trim().is_empty()) {
+        return invalid("blank_genre", "The genres cannot be blank".to_string());
+    }
+    let mut seen: HashSet<String> = HashSet::new();
+    let duplicated: Vec<&str> = genres
+        .iter()
+        .filter(|genre| !seen.insert(genre.trim().to_lowercase()))
+        .map(|genre| genre.as_str())
+        .collect();
+    if !duplicated.is_empty() {
+        return invalid(
+            "duplicated_genres",
+            format!("These genres are duplicated: {}", duplicated.join(", ")),
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Auxiliary Functions
+
+    fn build_genres_request(genres: &[&str]) -> GenresRequest {
+        GenresRequest {
+            genres: genres.iter().map(|genre| genre.to_string()).collect(),
+        }
+    }
+
+    fn build_movie_request(duration: &str) -> MovieRequest {
+        MovieRequest {
+            imdb_id: "tt12345".to_string(),
+            title: "Casino".to_string(),
+            overview: "Película que trata de la mafia de los casinos de Las Vegas".to_string(),
+            director: "Martin Scorsese".to_string(),
+            duration: duration.to_string(),
+            release_date: "1990-3-4".to_string(),
+            genres: vec!["Crímen".to_string(), "Drama".to_string()],
+            trailer_link: "https://youtube.com/video/ds1281o3l1h".to_string(),
+            poster: "https://moviedb.com/casino/poster.jpg".to_string(),
+            backdrop: "https://moviedb.com/casino/poster.jpg".to_string(),
+        }
+    }
+
+    fn genres_error(request: GenresRequest) -> String {
+        let err = request.validate().unwrap_err();
+        let field_errors = err.field_errors();
+        let genre_errors = field_errors.get("genres").unwrap();
+        assert_eq!(genre_errors.len(), 1);
+        genre_errors[0].message.as_ref().unwrap().to_string()
+    }
+
+    // Unit Tests
+
+    #[test]
+    fn test_movie_request_duration_formats() {
+        for duration in ["2h", "45m", "2h 5m", "120m"] {
+            let movie = build_movie_request(duration);
+            assert!(movie.validate().is_ok(), "{duration}");
+            assert!(Movie::try_from(movie).is_ok(), "{duration}");
+        }
+        for duration in ["2h5m", "2h 75m", "2 h", "1h 05", ""] {
+            let err = build_movie_request(duration).validate().unwrap_err();
+            assert_eq!(
+                err.field_errors()["duration"][0].message.as_deref(),
+                Some("The duration must match the following formats: '00h 00m', '00h' or '00m'"),
+                "{duration}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_genres_request_valid() {
+        assert!(build_genres_request(&["Crimen", "Drama"])
+            .validate()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_genres_request_empty() {
+        assert_eq!(
+            genres_error(build_genres_request(&[])),
+            "The movie has to have at least one genre"
+        );
+    }
+
+    #[test]
+    fn test_genres_request_duplicated() {
+        assert_eq!(
+            genres_error(build_genres_request(&["Drama", "Crimen", "drama"])),
+            "These genres are duplicated: drama"
+        );
+    }
+
+    #[test]
+    fn test_genres_request_blank() {
+        assert_eq!(
+            genres_error(build_genres_request(&["Drama", "  "])),
+            "The genres cannot be blank"
+        );
+    }
+
+    #[test]
+    fn test_genres_request_over_limit() {
+        let genres: Vec<String> = (0..=MAX_GENRES).map(|i| format!("Genre {}", i)).collect();
+        assert_eq!(
+            genres_error(GenresRequest { genres }),
+            format!("The movie cannot have more than {} genres", MAX_GENRES)
+        );
+    }
+}
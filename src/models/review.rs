@@ -1,15 +1,11 @@
 use std::error::Error;
 
-use lazy_static::lazy_static;
 use mongodb::bson::{oid::ObjectId, DateTime};
-use regex::Regex;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
-use validator::Validate;
+use validator::{Validate, ValidationError};
 
-lazy_static! {
-    static ref RE_IMDB_ID: Regex = Regex::new(r"^tt\d+$").unwrap();
-}
+use super::{timestamps::iso_date_format, validation::validate_any_imdb_id};
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -17,10 +13,16 @@ pub struct Review {
     #[serde(rename(serialize = "_id", deserialize = "_id"))]
     pub _id: ObjectId,
     pub title: String,
-    pub rating: u32,
+    pub rating: f32,
     pub body: String,
     pub created_at: DateTime,
     pub updated_at: DateTime,
+    /// Movie or series holding the review, set on creation. Reviews stored before it existed
+    /// have none until `POST /admin/maintenance/backfillReviewParents` runs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<ObjectId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_type: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -29,7 +31,7 @@ pub struct ReviewResponse {
     #[serde(rename(serialize = "_id", deserialize = "_id"))]
     pub _id: ObjectId,
     pub title: String,
-    pub rating: u32,
+    pub rating: f32,
     pub body: String,
     #[serde(with = "iso_date_format")]
     pub created_at: DateTime,
@@ -44,8 +46,8 @@ pub struct ReviewResponseDoc {
     pub _id: String,
     #[schema(example = "Una secuela muy a la altura de la anterior.")]
     pub title: String,
-    #[schema(example = 4)]
-    pub rating: u32,
+    #[schema(example = 4.5)]
+    pub rating: f32,
     #[schema(example = "La verdad que nos quedamos con ganas de más en esta película.")]
     pub body: String,
     #[schema(value_type = String, format = DateTime, example = "2024-05-07T11:56:05.792+00:00")]
@@ -54,17 +56,68 @@ pub struct ReviewResponseDoc {
     pub updated_at: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewParent {
+    #[schema(example = "tt12345")]
+    pub imdb_id: String,
+    #[schema(example = "El padrino")]
+    pub title: String,
+    #[serde(rename(serialize = "type", deserialize = "type"))]
+    #[schema(example = "movie")]
+    pub parent_type: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewWithParent {
+    #[serde(flatten)]
+    pub review: ReviewResponse,
+    pub body_preview: String,
+    pub parent: Option<ReviewParent>,
+}
+
+/// Max characters (ellipsis included) of review bodies shown in list previews.
+pub const REVIEW_PREVIEW_CHARS: usize = 200;
+
+impl ReviewWithParent {
+    pub fn new(review: ReviewResponse, parent: Option<ReviewParent>) -> Self {
+        Self {
+            body_preview: truncate_on_char_boundary(&review.body, REVIEW_PREVIEW_CHARS),
+            review,
+            parent,
+        }
+    }
+}
+
+/// Cuts `text` to at most `max_chars` characters, ending with '…' when something was cut.
+/// Works on chars, never bytes, so multibyte text (á, ñ, emojis) cannot split mid-character.
+pub fn truncate_on_char_boundary(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    if max_chars == 0 {
+        return String::new();
+    }
+    let mut truncated: String = text.chars().take(max_chars - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
 #[serde(rename_all = "camelCase")]
 pub struct ReviewRequest {
     #[validate(length(min = 1, message = "The review title cannot be empty"))]
     pub title: String,
-    #[validate(range(min = 0, max = 5, message = "The rating must be between 0 and 5"))]
-    pub rating: u32,
+    #[validate(custom(
+        function = "validate_half_star_rating",
+        message = "The rating must be between 0 and 5 in steps of 0.5"
+    ))]
+    pub rating: f32,
     #[validate(length(min = 1, message = "The review body cannot be empty"))]
     pub body: String,
-    #[validate(regex(
-        path = *RE_IMDB_ID,
+    #[validate(custom(
+        function = "validate_any_imdb_id",
         message = "The imdbId must match the following format: 'tt0000'"
     ))]
     pub imdb_id: String,
@@ -74,23 +127,48 @@ pub struct ReviewRequest {
 pub struct ReviewUpdate {
     #[validate(length(min = 1, message = "The review title cannot be empty"))]
     pub title: String,
-    #[validate(range(min = 0, max = 5, message = "The rating must be between 0 and 5"))]
-    pub rating: u32,
+    #[validate(custom(
+        function = "validate_half_star_rating",
+        message = "The rating must be between 0 and 5 in steps of 0.5"
+    ))]
+    pub rating: f32,
     #[validate(length(min = 1, message = "The review body cannot be empty"))]
     pub body: String,
 }
 
+/// Body of `PATCH /{id}/reassign`: the movie or series the review should belong to.
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewReassign {
+    #[validate(custom(
+        function = "validate_any_imdb_id",
+        message = "The imdbId must match the following format: 'tt0000'"
+    ))]
+    #[schema(example = "tt0068646")]
+    pub imdb_id: String,
+}
+
 impl TryFrom<ReviewRequest> for Review {
     type Error = Box<dyn Error>;
 
     fn try_from(item: ReviewRequest) -> Result<Self, Self::Error> {
+        Self::try_from(&item)
+    }
+}
+
+impl TryFrom<&ReviewRequest> for Review {
+    type Error = Box<dyn Error>;
+
+    fn try_from(item: &ReviewRequest) -> Result<Self, Self::Error> {
         Ok(Self {
             _id: ObjectId::new(),
-            title: item.title,
+            title: item.title.clone(),
             rating: item.rating,
-            body: item.body,
+            body: item.body.clone(),
             created_at: DateTime::now(),
             updated_at: DateTime::now(),
+            parent_id: None,
+            parent_type: None,
         })
     }
 }
@@ -106,6 +184,8 @@ impl TryFrom<ReviewUpdate> for Review {
             body: item.body,
             created_at: DateTime::now(),
             updated_at: DateTime::now(),
+            parent_id: None,
+            parent_type: None,
         })
     }
 }
@@ -125,28 +205,84 @@ impl TryFrom<Review> for ReviewResponse {
     }
 }
 
-// Ser/De for ReviewResponse model datetime fields
-mod iso_date_format {
-    use chrono::{DateTime, Utc};
-    use mongodb::bson::DateTime as BsonDateTime;
-    use serde::{self, Deserialize, Deserializer, Serializer};
+/// Review as embedded in the `findById` docs, with the id and dates as plain strings.
+impl From<ReviewResponse> for ReviewResponseDoc {
+    fn from(item: ReviewResponse) -> Self {
+        let iso = |date: DateTime| chrono::DateTime::<chrono::Utc>::from(date).to_rfc3339();
+        Self {
+            _id: item._id.to_hex(),
+            title: item.title,
+            rating: item.rating,
+            body: item.body,
+            created_at: iso(item.created_at),
+            updated_at: iso(item.updated_at),
+        }
+    }
+}
+
+/// Ratings go from 0 to 5 in half-star steps: 0.5, 1.0, ... 4.5, 5.0.
+pub fn validate_half_star_rating(rating: f32) -> Result<(), ValidationError> {
+    if !(0.0..=5.0).contains(&rating) || (rating * 2.0).fract() != 0.0 {
+        return Err(ValidationError::new("half_star_rating"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Unit Tests
 
-    pub fn serialize<S>(date: &BsonDateTime, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let datetime: DateTime<Utc> = date.clone().into();
-        let formatted_date = datetime.to_rfc3339();
-        serializer.serialize_str(&formatted_date)
+    #[test]
+    fn test_truncate_on_char_boundary_multibyte() {
+        let body = "Una película magnífica, con una interpretación única de Michael Corleone.";
+        for max_chars in 1..body.chars().count() {
+            let preview = truncate_on_char_boundary(body, max_chars);
+            assert_eq!(preview.chars().count(), max_chars);
+            assert!(preview.ends_with('…'));
+        }
+        assert_eq!(truncate_on_char_boundary(body, 12), "Una películ…");
+        assert_eq!(truncate_on_char_boundary("Ñoño", 3), "Ño…");
     }
 
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<BsonDateTime, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let formatted_date = String::deserialize(deserializer)?;
-        let datetime =
-            DateTime::parse_from_rfc3339(&formatted_date).map_err(serde::de::Error::custom)?;
-        Ok(BsonDateTime::from_chrono(datetime.with_timezone(&Utc)))
+    #[test]
+    fn test_validate_half_star_rating() {
+        for rating in [0.0, 0.5, 3.5, 5.0] {
+            assert!(validate_half_star_rating(rating).is_ok());
+        }
+        for rating in [3.7, 6.0, -0.5, 5.5, f32::NAN] {
+            assert!(validate_half_star_rating(rating).is_err());
+        }
+
+        let update = ReviewUpdate {
+            title: "Muy buena".to_string(),
+            rating: 3.7,
+            body: "Le falta algo de ritmo".to_string(),
+        };
+        let errors = update.validate().unwrap_err();
+        assert!(errors.to_string().contains("in steps of 0.5"));
+    }
+
+    #[test]
+    fn test_truncate_on_char_boundary_short_text_unchanged() {
+        assert_eq!(truncate_on_char_boundary("Olé", 3), "Olé");
+        assert_eq!(truncate_on_char_boundary("", 5), "");
+        assert_eq!(truncate_on_char_boundary("Olé", 0), "");
+    }
+
+    #[test]
+    fn test_create_review_keeps_request_body() {
+        let request = ReviewRequest {
+            title: "Obra maestra".to_string(),
+            rating: 5.0,
+            body: "La mejor película de mafiosos jamás rodada.".to_string(),
+            imdb_id: "tt0068646".to_string(),
+        };
+
+        let review = Review::try_from(&request).unwrap();
+        assert_eq!(review.title, request.title);
+        assert_eq!(review.body, request.body);
+        assert_eq!(review.rating, request.rating);
     }
 }
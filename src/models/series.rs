@@ -1,21 +1,28 @@
-use std::error::Error;
+use std::{borrow::Cow, env, error::Error};
 
 use lazy_static::lazy_static;
-use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{oid::ObjectId, DateTime};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use validator::{Validate, ValidationError};
 
+use super::{
+    duration::RuntimeDuration,
+    review::ReviewResponseDoc,
+    timestamps::{iso_date_format, legacy_timestamp},
+    validation::{
+        validate_calendar_date, validate_duration, validate_image_host, validate_trailer_host,
+        RE_PERSON_NAME, RE_REMOTE_IMAGES, RE_SERIES_IMDB_ID,
+    },
+};
+
 lazy_static! {
-    static ref RE_IMDB_ID: Regex = Regex::new(r"^tt\d+$").unwrap();
-    static ref RE_CREATOR: Regex =
-        Regex::new(r"^([a-zA-Z]+\.?)\s([a-zA-Z]+\.?)(?:\s([a-zA-Z]+))?$").unwrap();
     static ref RE_RELEASE_DATE: Regex =
         Regex::new(r"^(\d{4})-([1-9]|0[1-9]|1[0-2])-([1-9]|0[1-9]|[12]\d|3[01])$").unwrap();
     static ref RE_TRAILER_LINK: Regex = Regex::new(r"^((?:https?:)?//)?((?:www|m)\.)?((?:youtube(-nocookie)?\.com|youtu.be))(/(?:[\w\-]+\\?v=|embed/|live/|v/)?)([\w\-]+)(\S+)?$").unwrap();
-    static ref RE_REMOTE_IMAGES: Regex = Regex::new(r"(https?://\S+(?:png|jpe?g|webp)\S*)").unwrap();
-    static ref RE_DURATION: Regex = Regex::new(r"^(?:(\d{1,2})h(?: (\d{1,2})m)?|(\d{1,2})m)$").unwrap();
+    static ref MAX_SEASONS: usize = read_limit("MAX_SEASONS", 50);
+    static ref MAX_EPISODES_PER_SEASON: usize = read_limit("MAX_EPISODES_PER_SEASON", 500);
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema, Validate)]
@@ -27,9 +34,13 @@ pub struct Episode {
         path = *RE_RELEASE_DATE,
         message = "The release date of the episode must match the following format: 'YYYY-MM-DD'"
     ))]
+    #[validate(custom(
+        function = "validate_calendar_date",
+        message = "The release date of the episode must be a valid calendar date"
+    ))]
     release_date: String,
-    #[validate(regex(
-        path = *RE_DURATION,
+    #[validate(custom(
+        function = "validate_duration",
         message = "The duration must match the following formats: '00h 00m', '00h' or '00m'"
     ))]
     duration: String,
@@ -47,15 +58,17 @@ pub struct Season {
         function = "validate_non_empty_vec",
         message = "The season has to have at least one episode"
     ))]
+    #[validate(custom(function = "validate_max_episodes"))]
     episode_list: Vec<Episode>,
     #[validate(regex(
         path = *RE_REMOTE_IMAGES,
         message = "The series poster must be a valid URL with one of these extensions: (.jpg, .jpeg, .png or .webp)"
     ))]
+    #[validate(custom(function = "validate_image_host"))]
     poster: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Series {
     #[serde(rename(serialize = "_id", deserialize = "_id"))]
@@ -72,6 +85,11 @@ pub struct Series {
     pub poster: String,
     pub backdrop: String,
     pub review_ids: Vec<ObjectId>,
+    /// Read as the Unix epoch on documents stored before the timestamps existed.
+    #[serde(default = "legacy_timestamp")]
+    pub created_at: DateTime,
+    #[serde(default = "legacy_timestamp")]
+    pub updated_at: DateTime,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
@@ -101,13 +119,25 @@ pub struct SeriesDoc {
     #[schema(example = "https://image.tmdb.org/t/p/original/xtAQ7j9Yd0j4Rjbvx1hW0ENpXjf.jpg")]
     pub backdrop: String,
     pub review_ids: Vec<String>,
+    /// Mean rating of its reviews (findById only), `null` when it has none
+    #[schema(example = 4.33)]
+    pub average_rating: Option<f64>,
+    /// Its reviews, only present with `includeReviews=true` (findById only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reviews: Option<Vec<ReviewResponseDoc>>,
+    #[serde(with = "iso_date_format")]
+    #[schema(value_type = String, format = DateTime, example = "2024-05-07T11:56:05.792+00:00")]
+    pub created_at: DateTime,
+    #[serde(with = "iso_date_format")]
+    #[schema(value_type = String, format = DateTime, example = "2024-05-07T11:56:05.792+00:00")]
+    pub updated_at: DateTime,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
 #[serde(rename_all = "camelCase")]
 pub struct SeriesRequest {
     #[validate(regex(
-        path = *RE_IMDB_ID,
+        path = *RE_SERIES_IMDB_ID,
         message = "The imdbId must match the following format: 'tt0000'"
     ))]
     pub imdb_id: String,
@@ -118,7 +148,7 @@ pub struct SeriesRequest {
     #[validate(range(min = 0, message = "Number of season of series must be more than 0"))]
     pub number_of_seasons: u32,
     #[validate(regex(
-        path = *RE_CREATOR,
+        path = *RE_PERSON_NAME,
         message = "The creator's name must match the following format: 'Name Surname'"
     ))]
     pub creator: String,
@@ -126,11 +156,16 @@ pub struct SeriesRequest {
         path = *RE_RELEASE_DATE,
         message = "The release date of the series must match the following format: 'YYYY-MM-DD'"
     ))]
+    #[validate(custom(
+        function = "validate_calendar_date",
+        message = "The release date of the series must be a valid calendar date"
+    ))]
     pub release_date: String,
     #[validate(regex(
         path = *RE_TRAILER_LINK,
         message = "The series trailer link has to be a valid YouTube URL"
     ))]
+    #[validate(custom(function = "validate_trailer_host"))]
     pub trailer_link: String,
     #[validate(custom(
         function = "validate_non_empty_vec",
@@ -142,19 +177,35 @@ pub struct SeriesRequest {
         function = "validate_non_empty_vec",
         message = "The series has to have at least one season"
     ))]
+    #[validate(custom(function = "validate_max_seasons"))]
     pub season_list: Vec<Season>,
     #[validate(regex(
         path = *RE_REMOTE_IMAGES,
         message = "The series poster must be a valid URL with one of these extensions: (.jpg, .jpeg, .png or .webp)"
     ))]
+    #[validate(custom(function = "validate_image_host"))]
     pub poster: String,
     #[validate(regex(
         path = *RE_REMOTE_IMAGES,
         message = "The series backdrop image must be a valid URL with one of these extensions: (.jpg, .jpeg, .png or .webp)"
     ))]
+    #[validate(custom(function = "validate_image_host"))]
     pub backdrop: String,
 }
 
+/// Body of `PUT /{id}/seasons`: the whole season list, sent as a bare JSON array.
+#[derive(Debug, Serialize, Deserialize, Validate)]
+#[serde(transparent)]
+pub struct SeasonListRequest {
+    #[validate(nested)]
+    #[validate(custom(
+        function = "validate_non_empty_vec",
+        message = "The series has to have at least one season"
+    ))]
+    #[validate(custom(function = "validate_max_seasons"))]
+    pub season_list: Vec<Season>,
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SeriesResponse {
@@ -162,18 +213,67 @@ pub struct SeriesResponse {
     pub imdb_id: String,
     #[schema(example = "La Casa del Dragón")]
     pub title: String,
+    #[schema(example = "Ryan J. Condal")]
+    pub creator: String,
     #[schema(example = 2)]
     pub number_of_seasons: u32,
     #[schema(example = "2021-06-21")]
     pub release_date: String,
     #[schema(example = "https://image.tmdb.org/t/p/original/fAos5hPi7TB49KpuIAjvQNZkvwM.jpg")]
     pub poster: String,
+    #[schema(example = 3)]
+    pub review_count: usize,
+}
+
+/// Episode of the flattened guide; indexes are 1-based positions inside the series.
+#[derive(Debug, Serialize, Deserialize, ToSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FlatEpisode {
+    #[schema(example = 1)]
+    pub season_index: u32,
+    #[schema(example = 2)]
+    pub episode_index: u32,
+    #[schema(example = "Los príncipes rebeldes")]
+    pub title: String,
+    #[schema(example = "2022-08-28")]
+    pub release_date: String,
+    #[schema(example = "1h 6m")]
+    pub duration: String,
+    #[schema(example = "Daemon se enfrenta a Viserys tras el robo del huevo de dragón.")]
+    pub description: String,
+}
+
+impl Season {
+    /// Whether another episode would go over `MAX_EPISODES_PER_SEASON`.
+    pub fn is_full(&self) -> bool {
+        self.episode_list.len() >= *MAX_EPISODES_PER_SEASON
+    }
+}
+
+impl Series {
+    /// Season at the 1-based `index`, `None` when it is 0 or past the last season.
+    pub fn season(&self, index: usize) -> Option<&Season> {
+        self.season_list.get(index.checked_sub(1)?)
+    }
+
+    /// Sums every episode duration across all seasons (unparseable durations count as 0).
+    pub fn total_runtime(&self) -> RuntimeDuration {
+        let total_minutes = self
+            .season_list
+            .iter()
+            .flat_map(|season| season.episode_list.iter())
+            .filter_map(|episode| episode.duration.parse::<RuntimeDuration>().ok())
+            .map(|duration| duration.total_minutes())
+            .sum();
+        RuntimeDuration::from_minutes(total_minutes)
+    }
 }
 
 impl TryFrom<SeriesRequest> for Series {
     type Error = Box<dyn Error>;
 
     fn try_from(item: SeriesRequest) -> Result<Self, Self::Error> {
+        let now = DateTime::now();
         Ok(Self {
             _id: ObjectId::new(),
             imdb_id: item.imdb_id,
@@ -188,6 +288,8 @@ impl TryFrom<SeriesRequest> for Series {
             poster: item.poster,
             backdrop: item.backdrop,
             review_ids: Vec::new(),
+            created_at: now,
+            updated_at: now,
         })
     }
 }
@@ -199,9 +301,37 @@ impl TryFrom<Series> for SeriesResponse {
         Ok(Self {
             imdb_id: item.imdb_id,
             title: item.title,
+            creator: item.creator,
             number_of_seasons: item.number_of_seasons,
             release_date: item.release_date,
             poster: item.poster,
+            review_count: item.review_ids.len(),
+        })
+    }
+}
+
+impl TryFrom<Series> for SeriesDoc {
+    type Error = Box<dyn Error>;
+
+    fn try_from(item: Series) -> Result<Self, Self::Error> {
+        Ok(Self {
+            _id: item._id.to_hex(),
+            imdb_id: item.imdb_id,
+            title: item.title,
+            overview: item.overview,
+            number_of_seasons: item.number_of_seasons,
+            creator: item.creator,
+            release_date: item.release_date,
+            trailer_link: item.trailer_link,
+            genres: item.genres,
+            season_list: item.season_list,
+            poster: item.poster,
+            backdrop: item.backdrop,
+            review_ids: item.review_ids.iter().map(|id| id.to_hex()).collect(),
+            average_rating: None,
+            reviews: None,
+            created_at: item.created_at,
+            updated_at: item.updated_at,
         })
     }
 }
@@ -212,3 +342,188 @@ fn validate_non_empty_vec<T>(vec: &[T]) -> Result<(), ValidationError> {
     }
     Ok(())
 }
+
+fn read_limit(var: &str, default: usize) -> usize {
+    env::var(var)
+        .ok()
+        .and_then(|val| val.parse().ok())
+        .filter(|limit| *limit > 0)
+        .unwrap_or(default)
+}
+
+fn validate_max_len<T>(vec: &[T], max: usize, msg: String) -> Result<(), ValidationError> {
+    if vec.len() > max {
+        return Err(ValidationError::new("vector_too_long").with_message(Cow::Owned(msg)));
+    }
+    Ok(())
+}
+
+fn validate_max_seasons(vec: &[Season]) -> Result<(), ValidationError> {
+    validate_max_len(
+        vec,
+        *MAX_SEASONS,
+        format!("The series cannot have more than {} seasons", *MAX_SEASONS),
+    )
+}
+
+fn validate_max_episodes(vec: &[Episode]) -> Result<(), ValidationError> {
+    validate_max_len(
+        vec,
+        *MAX_EPISODES_PER_SEASON,
+        format!(
+            "The season cannot have more than {} episodes",
+            *MAX_EPISODES_PER_SEASON
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Auxiliary Functions
+
+    fn build_episode(duration: &str) -> Episode {
+        Episode {
+            title: "Piloto".to_string(),
+            release_date: "2008-01-20".to_string(),
+            duration: duration.to_string(),
+            description: "Walter White descubre que tiene cáncer.".to_string(),
+        }
+    }
+
+    fn build_series(season_list: Vec<Season>) -> Series {
+        Series {
+            _id: ObjectId::new(),
+            imdb_id: "tt0903747".to_string(),
+            title: "Breaking Bad".to_string(),
+            overview: "Serie sobre Walter White.".to_string(),
+            number_of_seasons: season_list.len() as u32,
+            creator: "Vince Gilligan".to_string(),
+            release_date: "2008-01-20".to_string(),
+            trailer_link: "https://youtu.be/HhesaQXLuRY".to_string(),
+            genres: vec!["Drama".to_string()],
+            season_list,
+            poster: "https://moviedb.com/breaking_bad/poster.jpg".to_string(),
+            backdrop: "https://moviedb.com/breaking_bad/backdrop.jpg".to_string(),
+            review_ids: vec![],
+            created_at: DateTime::now(),
+            updated_at: DateTime::now(),
+        }
+    }
+
+    fn build_season(episodes: usize) -> Season {
+        Season {
+            overview: "Temporada".to_string(),
+            episode_list: vec![build_episode("45m"); episodes],
+            poster: "https://moviedb.com/breaking_bad/season.jpg".to_string(),
+        }
+    }
+
+    // Unit Tests
+
+    #[test]
+    fn test_episode_release_date_must_exist() {
+        let mut episode = build_episode("45m");
+        assert!(episode.validate().is_ok());
+
+        episode.release_date = "2021-02-31".to_string();
+        let err = episode.validate().unwrap_err();
+        let field_errors = err.field_errors();
+        let date_errors = field_errors.get("release_date").unwrap();
+        assert_eq!(date_errors.len(), 1);
+        assert_eq!(
+            date_errors[0].message.as_ref().unwrap(),
+            "The release date of the episode must be a valid calendar date"
+        );
+
+        episode.release_date = "2020-02-29".to_string();
+        assert!(episode.validate().is_ok());
+    }
+
+    #[test]
+    fn test_season_by_one_based_index() {
+        let series = build_series(vec![build_season(1), build_season(3)]);
+        assert_eq!(series.season(1).unwrap().episode_list.len(), 1);
+        assert_eq!(series.season(2).unwrap().episode_list.len(), 3);
+        assert!(series.season(0).is_none());
+        assert!(series.season(3).is_none());
+    }
+
+    #[test]
+    fn test_appended_episode_validation() {
+        assert!(build_episode("45m").validate().is_ok());
+        // Would be dropped by total_runtime if it got stored
+        assert!(build_episode("1h 90m").validate().is_err());
+
+        let err = Episode {
+            title: String::new(),
+            ..build_episode("3 horas")
+        }
+        .validate()
+        .unwrap_err();
+        let field_errors = err.field_errors();
+        assert!(field_errors.contains_key("title") && field_errors.contains_key("duration"));
+
+        assert!(!build_season(1).is_full());
+        assert!(build_season(*MAX_EPISODES_PER_SEASON).is_full());
+    }
+
+    #[test]
+    fn test_season_over_episode_limit() {
+        assert!(build_season(*MAX_EPISODES_PER_SEASON).validate().is_ok());
+
+        let err = build_season(*MAX_EPISODES_PER_SEASON + 1)
+            .validate()
+            .unwrap_err();
+        let field_errors = err.field_errors();
+        let episode_errors = field_errors.get("episode_list").unwrap();
+        assert_eq!(episode_errors[0].code, "vector_too_long");
+        assert_eq!(
+            episode_errors[0].message.as_ref().unwrap(),
+            &format!(
+                "The season cannot have more than {} episodes",
+                *MAX_EPISODES_PER_SEASON
+            )
+        );
+    }
+
+    #[test]
+    fn test_series_over_season_limit() {
+        assert!(validate_max_seasons(&vec![build_season(1); *MAX_SEASONS]).is_ok());
+
+        let err = validate_max_seasons(&vec![build_season(1); *MAX_SEASONS + 1]).unwrap_err();
+        assert_eq!(err.code, "vector_too_long");
+        assert_eq!(
+            err.message.unwrap(),
+            format!("The series cannot have more than {} seasons", *MAX_SEASONS)
+        );
+    }
+
+    #[test]
+    fn test_total_runtime_multi_season() {
+        let series = build_series(vec![
+            Season {
+                overview: "Temporada 1".to_string(),
+                episode_list: vec![build_episode("58m"), build_episode("48m")],
+                poster: "https://moviedb.com/breaking_bad/s1.jpg".to_string(),
+            },
+            Season {
+                overview: "Temporada 2".to_string(),
+                episode_list: vec![build_episode("1h 5m"), build_episode("1h")],
+                poster: "https://moviedb.com/breaking_bad/s2.jpg".to_string(),
+            },
+        ]);
+
+        let total = series.total_runtime();
+        assert_eq!(total.total_minutes(), 58 + 48 + 65 + 60);
+        assert_eq!(total.to_string(), "3h 51m");
+    }
+
+    #[test]
+    fn test_total_runtime_without_episodes() {
+        let series = build_series(vec![]);
+        assert_eq!(series.total_runtime().total_minutes(), 0);
+        assert_eq!(series.total_runtime().to_string(), "0h 0m");
+    }
+}
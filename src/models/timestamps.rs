@@ -0,0 +1,32 @@
+use mongodb::bson::DateTime;
+
+/// `createdAt`/`updatedAt` of movies and series stored before they had timestamps.
+pub fn legacy_timestamp() -> DateTime {
+    DateTime::from_millis(0)
+}
+
+// Ser/De for the datetime fields of the response models
+pub mod iso_date_format {
+    use chrono::{DateTime, Utc};
+    use mongodb::bson::DateTime as BsonDateTime;
+    use serde::{self, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(date: &BsonDateTime, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let datetime: DateTime<Utc> = (*date).into();
+        let formatted_date = datetime.to_rfc3339();
+        serializer.serialize_str(&formatted_date)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<BsonDateTime, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let formatted_date = String::deserialize(deserializer)?;
+        let datetime =
+            DateTime::parse_from_rfc3339(&formatted_date).map_err(serde::de::Error::custom)?;
+        Ok(BsonDateTime::from_chrono(datetime.with_timezone(&Utc)))
+    }
+}
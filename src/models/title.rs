@@ -0,0 +1,90 @@
+use std::borrow::Cow;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
+
+use super::validation::is_valid_imdb_id;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PopularTitle {
+    #[serde(rename(serialize = "type", deserialize = "type"), default)]
+    #[schema(example = "movie")]
+    pub media_type: String,
+    #[schema(example = "tt12345")]
+    pub imdb_id: String,
+    #[schema(example = "El lobo de Wall Street")]
+    pub title: String,
+    #[schema(example = "https://image.tmdb.org/t/p/original/jTlIYjvS16XOpsfvYCTmtEHV10K.jpg")]
+    pub poster: String,
+    #[schema(example = 12)]
+    pub review_count: u32,
+    #[schema(example = 4.25)]
+    pub average_rating: f64,
+    #[serde(default)]
+    #[schema(example = 4.02)]
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MostReviewedTitle {
+    #[serde(rename(serialize = "type", deserialize = "type"), default)]
+    #[schema(example = "movie")]
+    pub media_type: String,
+    #[schema(example = "tt0068646")]
+    pub imdb_id: String,
+    #[schema(example = "El padrino")]
+    pub title: String,
+    #[schema(example = "https://image.tmdb.org/t/p/original/5HlLUsmsv60cZVTzVns9ICZD6zU.jpg")]
+    pub poster: String,
+    #[schema(example = 12)]
+    pub review_count: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    #[serde(rename(serialize = "type", deserialize = "type"), default)]
+    #[schema(example = "series")]
+    pub media_type: String,
+    #[schema(example = "tt0903747")]
+    pub imdb_id: String,
+    #[schema(example = "Breaking Bad")]
+    pub title: String,
+    #[schema(example = "2008-01-20")]
+    pub release_date: String,
+    #[schema(example = "https://image.tmdb.org/t/p/original/ggFHVNu6YYI5L9pCfOacjizRGt.jpg")]
+    pub poster: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingTitlesRequest {
+    #[validate(length(
+        min = 1,
+        max = 1000,
+        message = "The imdbIds list must have between 1 and 1000 ids"
+    ))]
+    #[validate(custom(function = "validate_imdb_id_list"))]
+    #[schema(example = json!(["tt0068646", "tt0071562"]))]
+    pub imdb_ids: Vec<String>,
+}
+
+fn validate_imdb_id_list(imdb_ids: &[String]) -> Result<(), ValidationError> {
+    let wrong: Vec<&str> = imdb_ids
+        .iter()
+        .filter(|imdb_id| !is_valid_imdb_id(imdb_id))
+        .map(|imdb_id| imdb_id.as_str())
+        .collect();
+    if wrong.is_empty() {
+        return Ok(());
+    }
+    Err(
+        ValidationError::new("invalid_imdb_ids").with_message(Cow::Owned(format!(
+            "These imdbIds do not match the format 'tt0000': {}",
+            wrong.join(", ")
+        ))),
+    )
+}
@@ -0,0 +1,304 @@
+use std::{borrow::Cow, env};
+
+use chrono::NaiveDate;
+use lazy_static::lazy_static;
+use log::warn;
+use regex::Regex;
+use validator::ValidationError;
+
+use super::duration::RuntimeDuration;
+
+const DEFAULT_IMDB_ID_PATTERN: &str = r"^tt\d+$";
+/// Poster and backdrop URLs, shared by the request validation and the admin incomplete-media
+/// query so both agree on what counts as an image.
+pub const IMAGE_URL_PATTERN: &str = r"https?://\S+(?:png|jpe?g|webp)";
+const YOUTUBE_HOSTS: [&str; 3] = ["youtube.com", "youtube-nocookie.com", "youtu.be"];
+
+lazy_static! {
+    /// Director/creator names: two to five words of Unicode letters, where a word may contain
+    /// hyphens or apostrophes (`Bong Joon-ho`, `Peter O'Toole`) or end in a dot (`J. J. Abrams`).
+    pub static ref RE_PERSON_NAME: Regex =
+        Regex::new(r"^\p{L}[\p{L}'’-]*\.?(?:\s\p{L}[\p{L}'’-]*\.?){1,4}$").unwrap();
+    pub static ref RE_REMOTE_IMAGES: Regex = Regex::new(IMAGE_URL_PATTERN).unwrap();
+    static ref RE_DATE_PARTS: Regex = Regex::new(r"^(\d{4})-(\d{1,2})-(\d{1,2})$").unwrap();
+    pub static ref RE_MOVIE_IMDB_ID: Regex =
+        imdb_id_regex(env::var("MOVIE_IMDB_REGEX").ok().as_deref());
+    pub static ref RE_SERIES_IMDB_ID: Regex =
+        imdb_id_regex(env::var("SERIES_IMDB_REGEX").ok().as_deref());
+    static ref IMAGE_HOST_ALLOWLIST: Option<Vec<String>> =
+        parse_allowlist(env::var("IMAGE_HOST_ALLOWLIST").ok().as_deref());
+}
+
+/// Builds the imdbId regex from an operator override, falling back to `^tt\d+$` when the
+/// override is unset or does not compile.
+fn imdb_id_regex(pattern: Option<&str>) -> Regex {
+    match pattern.filter(|pattern| !pattern.trim().is_empty()) {
+        None => Regex::new(DEFAULT_IMDB_ID_PATTERN).unwrap(),
+        Some(pattern) => Regex::new(pattern).unwrap_or_else(|err| {
+            warn!(
+                "Invalid imdbId regex override '{}' ({}), using default",
+                pattern, err
+            );
+            Regex::new(DEFAULT_IMDB_ID_PATTERN).unwrap()
+        }),
+    }
+}
+
+/// Lookups accept any case for the `tt` prefix (`TT0068646`); stored ids always use lowercase
+/// `tt`. The rest of the id is left untouched, so a case-sensitive `MOVIE_IMDB_REGEX` or
+/// `SERIES_IMDB_REGEX` override still sees the suffix exactly as sent.
+pub fn normalize_imdb_id(imdb_id: &str) -> String {
+    let imdb_id = imdb_id.trim();
+    match imdb_id.get(..2) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("tt") => format!("tt{}", &imdb_id[2..]),
+        _ => imdb_id.to_string(),
+    }
+}
+
+/// Single imdbId check for callers that accept either entity (reviews, titles), so it only has
+/// to match one of the per-entity formats.
+pub fn is_valid_imdb_id(imdb_id: &str) -> bool {
+    RE_MOVIE_IMDB_ID.is_match(imdb_id) || RE_SERIES_IMDB_ID.is_match(imdb_id)
+}
+
+pub fn validate_any_imdb_id(imdb_id: &str) -> Result<(), ValidationError> {
+    if !is_valid_imdb_id(imdb_id) {
+        return Err(ValidationError::new("invalid_imdb_id"));
+    }
+    Ok(())
+}
+
+/// Rejects dates like `2021-02-31` that pass the format regex but don't exist.
+/// Malformed values are left to the format regex so only one error is reported.
+pub fn validate_calendar_date(date: &str) -> Result<(), ValidationError> {
+    let Some(caps) = RE_DATE_PARTS.captures(date) else {
+        return Ok(());
+    };
+    let (year, month, day) = (
+        caps[1].parse::<i32>().unwrap_or_default(),
+        caps[2].parse::<u32>().unwrap_or_default(),
+        caps[3].parse::<u32>().unwrap_or_default(),
+    );
+    if NaiveDate::from_ymd_opt(year, month, day).is_none() {
+        return Err(ValidationError::new("invalid_calendar_date"));
+    }
+    Ok(())
+}
+
+/// Movie and episode runtimes (`2h 5m`, `2h` or `45m`), checked by the same parser that builds
+/// `RuntimeDuration` so a valid request always converts.
+pub fn validate_duration(duration: &str) -> Result<(), ValidationError> {
+    duration
+        .parse::<RuntimeDuration>()
+        .map(|_| ())
+        .map_err(|_| ValidationError::new("invalid_duration"))
+}
+
+fn parse_hosts(raw: &str) -> Vec<String> {
+    raw.split(',')
+        .map(|host| host.trim().to_lowercase())
+        .filter(|host| !host.is_empty())
+        .collect()
+}
+
+/// `IMAGE_HOST_ALLOWLIST` hosts, `None` (host checks disabled) when it is unset or blank.
+fn parse_allowlist(raw: Option<&str>) -> Option<Vec<String>> {
+    raw.map(parse_hosts).filter(|hosts| !hosts.is_empty())
+}
+
+/// Host of a link, accepting the scheme-less forms the URL regexes allow (`youtu.be/x`, `//x`).
+fn link_host(link: &str) -> Option<String> {
+    let absolute = if link.contains("://") {
+        link.to_string()
+    } else {
+        format!("https://{}", link.trim_start_matches("//"))
+    };
+    url::Url::parse(&absolute)
+        .ok()?
+        .host_str()
+        .map(|host| host.to_lowercase())
+}
+
+/// Allowed hosts also cover their subdomains (`youtube.com` allows `www.youtube.com`).
+fn check_host<S: AsRef<str>>(link: &str, allowed: &[S], kind: &str) -> Result<(), ValidationError> {
+    let host = link_host(link).unwrap_or_default();
+    let is_allowed = allowed.iter().any(|allowed| {
+        let allowed = allowed.as_ref();
+        host == allowed || host.ends_with(&format!(".{}", allowed))
+    });
+    if is_allowed {
+        return Ok(());
+    }
+    let names: Vec<&str> = allowed.iter().map(|allowed| allowed.as_ref()).collect();
+    Err(
+        ValidationError::new("host_not_allowed").with_message(Cow::Owned(format!(
+            "The {} host '{}' is not allowed, use one of: {}",
+            kind,
+            host,
+            names.join(", ")
+        ))),
+    )
+}
+
+/// Only enforced when `IMAGE_HOST_ALLOWLIST` is set; its hosts are the allowed ones.
+pub fn validate_image_host(link: &str) -> Result<(), ValidationError> {
+    match IMAGE_HOST_ALLOWLIST.as_deref() {
+        Some(hosts) => check_host(link, hosts, "image"),
+        None => Ok(()),
+    }
+}
+
+/// Only enforced when `IMAGE_HOST_ALLOWLIST` is set, trailers are then limited to YouTube.
+pub fn validate_trailer_host(link: &str) -> Result<(), ValidationError> {
+    if IMAGE_HOST_ALLOWLIST.is_none() {
+        return Ok(());
+    }
+    check_host(link, &YOUTUBE_HOSTS, "trailer")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Unit Tests
+
+    #[test]
+    fn test_validate_calendar_date_rejects_impossible_dates() {
+        assert!(validate_calendar_date("2021-02-31").is_err());
+        assert!(validate_calendar_date("2023-02-29").is_err());
+        assert!(validate_calendar_date("2023-04-31").is_err());
+    }
+
+    #[test]
+    fn test_validate_calendar_date_accepts_valid_dates() {
+        assert!(validate_calendar_date("2024-02-29").is_ok());
+        assert!(validate_calendar_date("2014-1-17").is_ok());
+        // Left to the format regex
+        assert!(validate_calendar_date("17/01/2014").is_ok());
+    }
+
+    #[test]
+    fn test_imdb_id_regex_default_and_invalid_override() {
+        for re in [
+            imdb_id_regex(None),
+            imdb_id_regex(Some("")),
+            imdb_id_regex(Some("(tt")),
+        ] {
+            assert_eq!(re.as_str(), DEFAULT_IMDB_ID_PATTERN);
+            assert!(re.is_match("tt12345"));
+        }
+    }
+
+    #[test]
+    fn test_series_id_fails_movie_regex_under_override() {
+        let movie_re = imdb_id_regex(Some(r"^tt\d+m$"));
+        let series_re = imdb_id_regex(Some(r"^tt\d+s$"));
+        assert!(movie_re.is_match("tt12345m"));
+        assert!(!movie_re.is_match("tt12345s"));
+        assert!(series_re.is_match("tt12345s"));
+        assert!(!series_re.is_match("tt12345"));
+    }
+
+    #[test]
+    fn test_is_valid_imdb_id_default_format() {
+        assert!(is_valid_imdb_id("tt0068646"));
+        for imdb_id in ["", "tt", "0068646", "TT0068646", "tt0068646 ", "nm0000199"] {
+            assert!(!is_valid_imdb_id(imdb_id), "{imdb_id}");
+        }
+        assert!(validate_any_imdb_id("tt12345").is_ok());
+        assert!(validate_any_imdb_id("tt-12345").is_err());
+    }
+
+    #[test]
+    fn test_normalize_imdb_id_accepts_uppercase() {
+        for imdb_id in ["TT12345", "Tt12345", " tt12345 "] {
+            let normalized = normalize_imdb_id(imdb_id);
+            assert_eq!(normalized, "tt12345");
+            assert!(RE_MOVIE_IMDB_ID.is_match(&normalized));
+            assert!(RE_SERIES_IMDB_ID.is_match(&normalized));
+        }
+        assert!(!is_valid_imdb_id(&normalize_imdb_id("NM12345")));
+    }
+
+    #[test]
+    fn test_normalize_imdb_id_only_touches_the_prefix() {
+        assert_eq!(normalize_imdb_id("TTab12CD"), "ttab12CD");
+        assert_eq!(normalize_imdb_id(" NM12345 "), "NM12345");
+        assert_eq!(normalize_imdb_id("T"), "T");
+        assert_eq!(normalize_imdb_id("Té1"), "Té1");
+    }
+
+    #[test]
+    fn test_person_name_accepts_real_names() {
+        for name in [
+            "Martin Scorsese",
+            "Bong Joon-ho",
+            "Pedro Almodóvar",
+            "Guillermo del Toro",
+            "Peter O'Toole",
+            "J. J. Abrams",
+            "Alejandro González Iñárritu",
+        ] {
+            assert!(RE_PERSON_NAME.is_match(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_person_name_rejects_invalid_input() {
+        for name in [
+            "",
+            "Scorsese",
+            "Martin  Scorsese",
+            " Martin Scorsese",
+            "Martin Scorsese ",
+            "Martin 5corsese",
+            "-Bong Joon",
+            "Martin Scorsese!",
+            "a b c d e f",
+        ] {
+            assert!(!RE_PERSON_NAME.is_match(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_check_host_allowed() {
+        let image_hosts = parse_hosts(" image.tmdb.org , CDN.example.com,");
+        assert_eq!(image_hosts, vec!["image.tmdb.org", "cdn.example.com"]);
+        assert!(check_host(
+            "https://image.tmdb.org/t/p/original/poster.jpg",
+            &image_hosts,
+            "image"
+        )
+        .is_ok());
+        assert!(check_host("https://img.cdn.example.com/a.png", &image_hosts, "image").is_ok());
+        assert!(check_host("youtu.be/DEMZSa0esCU", &YOUTUBE_HOSTS, "trailer").is_ok());
+        assert!(check_host(
+            "https://www.youtube.com/watch?v=x",
+            &YOUTUBE_HOSTS,
+            "trailer"
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_parse_allowlist_unset_disables_checks() {
+        assert_eq!(parse_allowlist(None), None);
+        assert_eq!(parse_allowlist(Some(" , ")), None);
+        assert_eq!(
+            parse_allowlist(Some("image.tmdb.org")),
+            Some(vec!["image.tmdb.org".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_check_host_blocked() {
+        let image_hosts = parse_hosts("image.tmdb.org");
+        let err =
+            check_host("https://evil.com/image.tmdb.org.jpg", &image_hosts, "image").unwrap_err();
+        assert_eq!(
+            err.message.unwrap(),
+            "The image host 'evil.com' is not allowed, use one of: image.tmdb.org"
+        );
+        assert!(check_host("https://notyoutube.com/watch", &YOUTUBE_HOSTS, "trailer").is_err());
+    }
+}
@@ -0,0 +1,495 @@
+use std::env;
+
+use actix_web::{
+    get,
+    http::header,
+    post,
+    web::{Bytes, Data, Json, Path, Query},
+    HttpRequest, HttpResponse,
+};
+use log::warn;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use utoipa::IntoParams;
+
+use crate::{
+    error::AppError,
+    middleware::{jwt_decoding_key, validate_bearer, Role},
+    models::admin::{CachePurgeRequest, MergeMoviesRequest},
+    services::{admin_repo::AdminRepository, db::Database, maintenance},
+};
+
+const ADMIN_TOKEN_HEADER: &str = "X-Admin-Token";
+
+fn has_admin_token(req: &HttpRequest) -> bool {
+    let expected = env::var("ADMIN_TOKEN").unwrap_or_default();
+    let provided = req
+        .headers()
+        .get(ADMIN_TOKEN_HEADER)
+        .and_then(|val| val.to_str().ok())
+        .unwrap_or_default();
+    !expected.is_empty() && constant_time_eq(provided.as_bytes(), expected.as_bytes())
+}
+
+/// Compares every byte instead of stopping at the first difference, so response times don't
+/// tell how much of a guessed token was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Checks the admin token header against the `ADMIN_TOKEN` env var (denies everything if unset).
+pub fn check_admin_token(req: &HttpRequest) -> Result<(), AppError> {
+    if !has_admin_token(req) {
+        warn!("Warn in admin {} [{}]", req.path(), AppError::Unauthorized);
+        return Err(AppError::Unauthorized);
+    }
+    Ok(())
+}
+
+/// Caller role: `Admin` with the admin token, otherwise the `role` claim of a valid Bearer JWT,
+/// which must be a staff role (see `staff_role`).
+pub fn authenticate(req: &HttpRequest) -> Result<Role, AppError> {
+    if has_admin_token(req) {
+        return Ok(Role::Admin);
+    }
+    let header = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|val| val.to_str().ok());
+    let key = jwt_decoding_key(env::var("JWT_SECRET").ok().as_deref());
+    match validate_bearer(header, key.as_ref()).and_then(|claims| staff_role(claims.role)) {
+        Ok(role) => Ok(role),
+        Err(err) => {
+            warn!("Warn in admin {} [{}]", req.path(), err);
+            Err(err)
+        }
+    }
+}
+
+/// Regular users never reach the admin data: `admin` tokens see everything, `support` tokens
+/// get the masked view.
+fn staff_role(role: Role) -> Result<Role, AppError> {
+    match role {
+        Role::User => Err(AppError::Unauthorized),
+        role => Ok(role),
+    }
+}
+
+/// Strips the `RAW_FIELD_MASK` keys (comma separated, `version` by default) for non-admin roles.
+pub fn mask_fields(
+    mut raw_doc: Map<String, Value>,
+    role: Role,
+    masked: &[String],
+) -> Map<String, Value> {
+    if role == Role::Admin {
+        return raw_doc;
+    }
+    for key in masked {
+        raw_doc.remove(key);
+    }
+    raw_doc
+}
+
+fn raw_field_mask() -> Vec<String> {
+    env::var("RAW_FIELD_MASK")
+        .unwrap_or_else(|_| "version".to_string())
+        .split(',')
+        .map(|key| key.trim().to_string())
+        .filter(|key| !key.is_empty())
+        .collect()
+}
+
+/// Find raw movie document by id
+#[utoipa::path(
+    path = "/api/v1/admin/movies/{id}/raw",
+    responses(
+        (status = 200, description = "Fetch the raw stored Movie document (support tokens get the RAW_FIELD_MASK fields removed)", body = Object),
+        (status = 400, description = "Cannot parse ObjectId", body = AppError, example = json!(AppError::CannotParseObjId.to_string())),
+        (status = 401, description = "Unauthorized", body = AppError, example = json!(AppError::Unauthorized.to_string())),
+        (status = 404, description = "Not Found", body = AppError, example = json!(AppError::NotFound.to_string())),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
+    ),
+    params(
+        ("id", description = "Unique ObjectId of Movie"),
+        ("X-Admin-Token" = String, Header, description = "Admin token, or send a Bearer JWT with the 'admin' or 'support' role claim")
+    ),
+    tag = "Admin"
+)]
+#[get("/movies/{id}/raw")]
+pub async fn get_raw_movie_by_id(
+    req: HttpRequest,
+    db: Data<Database>,
+    path: Path<String>,
+) -> Result<HttpResponse, AppError> {
+    let role = authenticate(&req)?;
+    let id = path.into_inner();
+    match db.find_raw_movie_by_id(id.as_str()).await {
+        Ok(raw_doc) => Ok(HttpResponse::Ok().json(mask_fields(raw_doc, role, &raw_field_mask()))),
+        Err(err) => Err(err),
+    }
+}
+
+/// Find movies and series with missing or invalid images
+#[utoipa::path(
+    path = "/api/v1/admin/incompleteMedia",
+    responses(
+        (status = 200, description = "List movies and series whose poster or backdrop is empty or not a valid image URL", body = [IncompleteMedia]),
+        (status = 401, description = "Unauthorized", body = AppError, example = json!(AppError::Unauthorized.to_string())),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
+    ),
+    params(
+        ("X-Admin-Token" = String, Header, description = "Admin token")
+    ),
+    tag = "Admin"
+)]
+#[get("/incompleteMedia")]
+pub async fn get_incomplete_media(
+    req: HttpRequest,
+    db: Data<Database>,
+) -> Result<HttpResponse, AppError> {
+    check_admin_token(&req)?;
+    match db.find_incomplete_media().await {
+        Ok(res) => Ok(HttpResponse::Ok().json(res)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Find likely duplicate movies
+#[utoipa::path(
+    path = "/api/v1/admin/duplicates",
+    responses(
+        (status = 200, description = "List groups of movies sharing the same normalized title and release year", body = [DuplicateGroup]),
+        (status = 401, description = "Unauthorized", body = AppError, example = json!(AppError::Unauthorized.to_string())),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
+    ),
+    params(
+        ("X-Admin-Token" = String, Header, description = "Admin token")
+    ),
+    tag = "Admin"
+)]
+#[get("/duplicates")]
+pub async fn get_duplicate_movies(
+    req: HttpRequest,
+    db: Data<Database>,
+) -> Result<HttpResponse, AppError> {
+    check_admin_token(&req)?;
+    match db.find_duplicate_movies().await {
+        Ok(res) => Ok(HttpResponse::Ok().json(res)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Find stats by genre
+#[utoipa::path(
+    path = "/api/v1/admin/genreStats",
+    responses(
+        (status = 200, description = "Movie count, total reviews and average review rating of every genre, sorted by movie count", body = [GenreStats]),
+        (status = 401, description = "Unauthorized", body = AppError, example = json!(AppError::Unauthorized.to_string())),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
+    ),
+    params(
+        ("X-Admin-Token" = String, Header, description = "Admin token")
+    ),
+    tag = "Admin"
+)]
+#[get("/genreStats")]
+pub async fn get_genre_stats(
+    req: HttpRequest,
+    db: Data<Database>,
+) -> Result<HttpResponse, AppError> {
+    check_admin_token(&req)?;
+    match db.find_genre_stats().await {
+        Ok(res) => Ok(HttpResponse::Ok().json(res)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Merge two duplicate movies
+#[utoipa::path(
+    path = "/api/v1/admin/movies/merge",
+    request_body = MergeMoviesRequest,
+    responses(
+        (status = 200, description = "Move the reviews of the removed movie onto the kept one and delete it", body = Object, example = json!({"id": "66a3a6c2f1a1b2c3d4e5f601", "reviewCount": 3})),
+        (status = 400, description = "Cannot parse ObjectId", body = AppError, example = json!(AppError::CannotParseObjId.to_string())),
+        (status = 401, description = "Unauthorized", body = AppError, example = json!(AppError::Unauthorized.to_string())),
+        (status = 404, description = "Not Found", body = AppError, example = json!(AppError::NotFound.to_string())),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
+    ),
+    params(
+        ("X-Admin-Token" = String, Header, description = "Admin token")
+    ),
+    tag = "Admin"
+)]
+#[post("/movies/merge")]
+pub async fn post_merge_movies(
+    req: HttpRequest,
+    db: Data<Database>,
+    body: Json<MergeMoviesRequest>,
+) -> Result<HttpResponse, AppError> {
+    check_admin_token(&req)?;
+    match db
+        .merge_movies(body.keep_id.as_str(), body.remove_id.as_str())
+        .await
+    {
+        Ok(res) => Ok(HttpResponse::Ok().json(res)),
+        Err(err) => Err(err),
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceParams {
+    dry_run: Option<bool>,
+}
+
+/// Normalize movie durations
+#[utoipa::path(
+    path = "/api/v1/admin/maintenance/normalizeDurations",
+    responses(
+        (status = 200, description = "Rewrite every movie duration in the canonical 'Xh Ym' form", body = MaintenanceReport),
+        (status = 401, description = "Unauthorized", body = AppError, example = json!(AppError::Unauthorized.to_string())),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
+    ),
+    params(
+        MaintenanceParams,
+        ("X-Admin-Token" = String, Header, description = "Admin token")
+    ),
+    tag = "Admin"
+)]
+#[post("/maintenance/normalizeDurations")]
+pub async fn post_normalize_durations(
+    req: HttpRequest,
+    db: Data<Database>,
+    params: Query<MaintenanceParams>,
+) -> Result<HttpResponse, AppError> {
+    check_admin_token(&req)?;
+    match maintenance::normalize_durations(db.get_ref(), params.dry_run.unwrap_or(false)).await {
+        Ok(report) => Ok(HttpResponse::Ok().json(report)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Recount review references
+#[utoipa::path(
+    path = "/api/v1/admin/maintenance/recountReviews",
+    responses(
+        (status = 200, description = "Remove duplicated or dangling review ids from movies and series", body = MaintenanceReport),
+        (status = 401, description = "Unauthorized", body = AppError, example = json!(AppError::Unauthorized.to_string())),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
+    ),
+    params(
+        MaintenanceParams,
+        ("X-Admin-Token" = String, Header, description = "Admin token")
+    ),
+    tag = "Admin"
+)]
+#[post("/maintenance/recountReviews")]
+pub async fn post_recount_reviews(
+    req: HttpRequest,
+    db: Data<Database>,
+    params: Query<MaintenanceParams>,
+) -> Result<HttpResponse, AppError> {
+    check_admin_token(&req)?;
+    match maintenance::recount_reviews(db.get_ref(), params.dry_run.unwrap_or(false)).await {
+        Ok(report) => Ok(HttpResponse::Ok().json(report)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Dedupe review references
+#[utoipa::path(
+    path = "/api/v1/admin/maintenance/dedupeReviewIds",
+    responses(
+        (status = 200, description = "Remove repeated review ids from movies and series", body = MaintenanceReport),
+        (status = 401, description = "Unauthorized", body = AppError, example = json!(AppError::Unauthorized.to_string())),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
+    ),
+    params(
+        MaintenanceParams,
+        ("X-Admin-Token" = String, Header, description = "Admin token")
+    ),
+    tag = "Admin"
+)]
+#[post("/maintenance/dedupeReviewIds")]
+pub async fn post_dedupe_review_ids(
+    req: HttpRequest,
+    db: Data<Database>,
+    params: Query<MaintenanceParams>,
+) -> Result<HttpResponse, AppError> {
+    check_admin_token(&req)?;
+    match maintenance::dedupe_review_ids(db.get_ref(), params.dry_run.unwrap_or(false)).await {
+        Ok(report) => Ok(HttpResponse::Ok().json(report)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Reindex collections
+#[utoipa::path(
+    path = "/api/v1/admin/maintenance/reindex",
+    responses(
+        (status = 200, description = "Create the missing unique imdbId indexes, replacing non-unique ones", body = MaintenanceReport),
+        (status = 401, description = "Unauthorized", body = AppError, example = json!(AppError::Unauthorized.to_string())),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
+    ),
+    params(
+        MaintenanceParams,
+        ("X-Admin-Token" = String, Header, description = "Admin token")
+    ),
+    tag = "Admin"
+)]
+#[post("/maintenance/reindex")]
+pub async fn post_reindex(
+    req: HttpRequest,
+    db: Data<Database>,
+    params: Query<MaintenanceParams>,
+) -> Result<HttpResponse, AppError> {
+    check_admin_token(&req)?;
+    match maintenance::reindex(db.get_ref(), params.dry_run.unwrap_or(false)).await {
+        Ok(report) => Ok(HttpResponse::Ok().json(report)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Backfill review parents
+#[utoipa::path(
+    path = "/api/v1/admin/maintenance/backfillReviewParents",
+    responses(
+        (status = 200, description = "Set parentId and parentType on reviews stored without them", body = MaintenanceReport),
+        (status = 401, description = "Unauthorized", body = AppError, example = json!(AppError::Unauthorized.to_string())),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
+    ),
+    params(
+        MaintenanceParams,
+        ("X-Admin-Token" = String, Header, description = "Admin token")
+    ),
+    tag = "Admin"
+)]
+#[post("/maintenance/backfillReviewParents")]
+pub async fn post_backfill_review_parents(
+    req: HttpRequest,
+    db: Data<Database>,
+    params: Query<MaintenanceParams>,
+) -> Result<HttpResponse, AppError> {
+    check_admin_token(&req)?;
+    match maintenance::backfill_review_parents(db.get_ref(), params.dry_run.unwrap_or(false)).await
+    {
+        Ok(report) => Ok(HttpResponse::Ok().json(report)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Purge the findById cache
+///
+/// The cache lives in each server process, so on a deployment with several instances this only
+/// purges the instance that receives the request.
+#[utoipa::path(
+    path = "/api/v1/admin/cache/purge",
+    request_body(content = CachePurgeRequest, description = "Ids to evict, or an empty body to purge everything"),
+    responses(
+        (status = 200, description = "Evict movies and series from the findById cache of this instance after out-of-band changes", body = Object, example = json!({"purged": 1})),
+        (status = 400, description = "Malformed body", body = AppError, example = json!(AppError::BadRequest("unknown field `idz`, expected `ids` at line 1 column 6".to_string()).to_string())),
+        (status = 401, description = "Unauthorized", body = AppError, example = json!(AppError::Unauthorized.to_string()))
+    ),
+    params(
+        ("X-Admin-Token" = String, Header, description = "Admin token")
+    ),
+    tag = "Admin"
+)]
+#[post("/cache/purge")]
+pub async fn post_cache_purge(
+    req: HttpRequest,
+    db: Data<Database>,
+    body: Bytes,
+) -> Result<HttpResponse, AppError> {
+    check_admin_token(&req)?;
+    let ids = parse_purge_ids(&body)?;
+    let purged = db.purge_find_by_id_cache(ids.as_deref());
+    let mut res = Map::new();
+    res.insert("purged".to_string(), Value::from(purged));
+    Ok(HttpResponse::Ok().json(res))
+}
+
+/// Ids of a purge body, `None` (purge everything) only for an empty body or one without `ids`.
+/// Anything that does not parse is a 400, never a full purge.
+fn parse_purge_ids(body: &[u8]) -> Result<Option<Vec<String>>, AppError> {
+    if body.iter().all(u8::is_ascii_whitespace) {
+        return Ok(None);
+    }
+    match serde_json::from_slice::<CachePurgeRequest>(body) {
+        Ok(request) => Ok(request.ids),
+        Err(err) => {
+            let err = AppError::BadRequest(err.to_string());
+            warn!("Warn in admin /cache/purge [{}]", err);
+            Err(err)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Auxiliary Functions
+
+    fn build_raw_doc() -> Map<String, Value> {
+        serde_json::from_value(serde_json::json!({
+            "_id": "6640ce2b8d0a4a4e3c5a7d31",
+            "title": "Casino",
+            "version": 3,
+            "flags": ["legacy"],
+        }))
+        .unwrap()
+    }
+
+    // Unit Tests
+
+    #[test]
+    fn test_parse_purge_ids() {
+        assert_eq!(parse_purge_ids(b"").unwrap(), None);
+        assert_eq!(parse_purge_ids(b" \n").unwrap(), None);
+        assert_eq!(parse_purge_ids(b"{}").unwrap(), None);
+        assert_eq!(
+            parse_purge_ids(br#"{"ids": ["66a3a6c2f1a1b2c3d4e5f601"]}"#).unwrap(),
+            Some(vec!["66a3a6c2f1a1b2c3d4e5f601".to_string()])
+        );
+        for body in [
+            &br#"{"idz": ["66a3a6c2f1a1b2c3d4e5f601"]}"#[..],
+            br#"{"ids": "66a3a6c2f1a1b2c3d4e5f601"}"#,
+            b"ids=66a3a6c2f1a1b2c3d4e5f601",
+        ] {
+            assert!(
+                matches!(parse_purge_ids(body), Err(AppError::BadRequest(_))),
+                "{}",
+                String::from_utf8_lossy(body)
+            );
+        }
+    }
+
+    #[test]
+    fn test_mask_fields_by_role() {
+        let masked = vec!["version".to_string(), "flags".to_string()];
+
+        let admin_view = mask_fields(build_raw_doc(), Role::Admin, &masked);
+        assert_eq!(admin_view, build_raw_doc());
+
+        let support_view = mask_fields(build_raw_doc(), Role::Support, &masked);
+        assert!(!support_view.contains_key("version"));
+        assert!(!support_view.contains_key("flags"));
+        assert_eq!(support_view["title"], "Casino");
+    }
+
+    #[test]
+    fn test_staff_role_rejects_regular_users() {
+        assert_eq!(staff_role(Role::Admin), Ok(Role::Admin));
+        assert_eq!(staff_role(Role::Support), Ok(Role::Support));
+        assert_eq!(staff_role(Role::User), Err(AppError::Unauthorized));
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"s3cret-token", b"s3cret-token"));
+        assert!(!constant_time_eq(b"s3cret-token", b"s3cret-tokem"));
+        assert!(!constant_time_eq(b"s3cret", b"s3cret-token"));
+        assert!(!constant_time_eq(b"", b"s3cret-token"));
+    }
+}
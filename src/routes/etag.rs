@@ -0,0 +1,170 @@
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use actix_web::{
+    http::header::{
+        self, ETag, EntityTag, HttpDate, IfNoneMatch, LastModified, TryIntoHeaderValue,
+    },
+    HttpMessage, HttpRequest, HttpResponse,
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// Weak ETag of the serialized body. It hashes the whole response (average rating and embedded
+/// reviews included), since new reviews change it without touching the entity's `updatedAt`.
+pub fn weak_etag<T: Serialize>(body: &T) -> EntityTag {
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_vec(body)
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    EntityTag::new_weak(format!("{:016x}", hasher.finish()))
+}
+
+/// Sets `ETag` on the JSON response and answers 304 when `If-None-Match` lists it (or is `*`).
+pub fn conditional_json_response<T: Serialize>(req: &HttpRequest, body: &T) -> HttpResponse {
+    let etag = weak_etag(body);
+    let not_modified = match req.get_header::<IfNoneMatch>() {
+        Some(IfNoneMatch::Any) => true,
+        Some(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(&etag)),
+        None => false,
+    };
+    if not_modified {
+        return HttpResponse::NotModified()
+            .insert_header(ETag(etag))
+            .finish();
+    }
+    HttpResponse::Ok().insert_header(ETag(etag)).json(body)
+}
+
+/// `conditional_json_response` for a page of `list_key` items, plus `Last-Modified` set to their
+/// newest `updatedAt`. Only the ETag answers 304: it hashes the whole page, so deletions and
+/// changes to `totalItems` or `hasNext` invalidate it, while the newest date would not move.
+pub fn conditional_list_response(
+    req: &HttpRequest,
+    res: &Map<String, Value>,
+    list_key: &str,
+) -> HttpResponse {
+    let mut resp = conditional_json_response(req, res);
+    if let Some(value) = last_modified(res, list_key)
+        .and_then(|date| LastModified(HttpDate::from(date)).try_into_value().ok())
+    {
+        resp.headers_mut().insert(header::LAST_MODIFIED, value);
+    }
+    resp
+}
+
+// Truncated to whole seconds, the precision of HTTP dates
+fn last_modified(res: &Map<String, Value>, list_key: &str) -> Option<SystemTime> {
+    res.get(list_key)?
+        .as_array()?
+        .iter()
+        .filter_map(|item| item.get("updatedAt")?.as_str())
+        .filter_map(|date| chrono::DateTime::parse_from_rfc3339(date).ok())
+        .filter_map(|date| u64::try_from(date.timestamp()).ok())
+        .max()
+        .map(|secs| UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        http::{header, StatusCode},
+        test,
+    };
+    use serde_json::json;
+
+    use super::*;
+
+    // Unit Tests
+
+    #[actix_web::test]
+    async fn test_weak_etag_follows_body() {
+        let movie = json!({ "title": "Casino", "averageRating": 4.5 });
+        let etag = weak_etag(&movie);
+        assert!(etag.weak);
+        assert_eq!(etag, weak_etag(&movie));
+        // A new review changes the average, and with it the tag
+        assert_ne!(
+            etag,
+            weak_etag(&json!({ "title": "Casino", "averageRating": 4.0 }))
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_conditional_json_response_not_modified() {
+        let movie = json!({ "title": "Casino" });
+        let etag = weak_etag(&movie).to_string();
+
+        let req = test::TestRequest::get().to_http_request();
+        let resp = conditional_json_response(&req, &movie);
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get(header::ETAG).unwrap(), etag.as_str());
+
+        let req = test::TestRequest::get()
+            .insert_header((header::IF_NONE_MATCH, etag.as_str()))
+            .to_http_request();
+        let resp = conditional_json_response(&req, &movie);
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(resp.headers().get(header::ETAG).unwrap(), etag.as_str());
+
+        let req = test::TestRequest::get()
+            .insert_header((header::IF_NONE_MATCH, "W/\"0000000000000000\""))
+            .to_http_request();
+        let resp = conditional_json_response(&req, &movie);
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = test::TestRequest::get()
+            .insert_header((header::IF_NONE_MATCH, "*"))
+            .to_http_request();
+        let resp = conditional_json_response(&req, &movie);
+        assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+    }
+
+    #[actix_web::test]
+    async fn test_conditional_list_response_tracks_the_whole_page() {
+        let page = |reviews: Value, total: u64| -> Map<String, Value> {
+            serde_json::from_value(json!({ "reviews": reviews, "totalItems": total })).unwrap()
+        };
+        let newest = json!({ "title": "Muy buena", "updatedAt": "2024-05-08T09:00:00.120+00:00" });
+        let res = page(
+            json!([
+                { "title": "Obra maestra", "updatedAt": "2024-05-07T11:56:05.792+00:00" },
+                newest.clone(),
+            ]),
+            2,
+        );
+
+        let first =
+            conditional_list_response(&test::TestRequest::get().to_http_request(), &res, "reviews");
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(
+            first.headers().get(header::LAST_MODIFIED).unwrap(),
+            "Wed, 08 May 2024 09:00:00 GMT"
+        );
+        let etag = first.headers().get(header::ETAG).unwrap().clone();
+
+        let cached = test::TestRequest::get()
+            .insert_header((header::IF_NONE_MATCH, etag.clone()))
+            .to_http_request();
+        let second = conditional_list_response(&cached, &res, "reviews");
+        assert_eq!(second.status(), StatusCode::NOT_MODIFIED);
+
+        // Deleting the older review keeps the newest date, but not the tag
+        let deleted = page(json!([newest]), 1);
+        let third = conditional_list_response(&cached, &deleted, "reviews");
+        assert_eq!(third.status(), StatusCode::OK);
+        assert_eq!(
+            third.headers().get(header::LAST_MODIFIED).unwrap(),
+            "Wed, 08 May 2024 09:00:00 GMT"
+        );
+
+        // A date alone can't tell, so If-Modified-Since never answers 304
+        let since = test::TestRequest::get()
+            .insert_header((header::IF_MODIFIED_SINCE, "Wed, 08 May 2024 09:00:00 GMT"))
+            .to_http_request();
+        let fourth = conditional_list_response(&since, &res, "reviews");
+        assert_eq!(fourth.status(), StatusCode::OK);
+    }
+}
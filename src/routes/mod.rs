@@ -1,3 +1,7 @@
+pub mod admin;
+pub mod etag;
 pub mod movie;
 pub mod review;
 pub mod series;
+pub mod stats;
+pub mod title;
@@ -1,24 +1,72 @@
 use std::collections::HashMap;
 
 use crate::{
-    error::AppError,
-    models::movie::{Movie, MovieRequest},
-    services::{db::Database, movie_repo::MovieRepository},
+    error::{internal_error, AppError},
+    models::bulk::BulkResult,
+    models::fields::{
+        build_movie_projection, parse_sparse_fields, projected_to_json, select_fields,
+        MOVIE_FIELDS, MOVIE_PATCHABLE_FIELDS, MOVIE_RESPONSE_FIELDS,
+    },
+    models::movie::{GenresRequest, Movie, MovieDoc, MovieRequest},
+    routes::etag::conditional_json_response,
+    services::{
+        db::Database,
+        movie_repo::{created_movie_result, MovieFilter, MovieRepository},
+        pagination::non_empty_page,
+    },
 };
 use actix_web::{
     delete, get, patch, post, put,
     web::{Data, Json, Path, Query},
-    HttpResponse,
+    HttpRequest, HttpResponse,
 };
+use log::error;
+use mongodb::bson::{oid::ObjectId, Bson};
 use serde::Deserialize;
+use serde_json::{Map, Value};
 use utoipa::{IntoParams, ToSchema};
 use validator::Validate;
 
 #[derive(Debug, Deserialize, IntoParams)]
 pub struct Params {
     title: Option<String>,
+    /// Genre the movies must include, e.g. `Drama`; combined with `title` when both are set
+    genre: Option<String>,
+    /// Four-digit release year, e.g. `2014`
+    year: Option<String>,
+    /// Field to sort by: `reviewCount`, `title`, `releaseDate` or `duration` (runtime in minutes)
+    sort: Option<String>,
+    /// Sort direction: `asc` (default) or `desc`
+    order: Option<String>,
+    /// 1-based page number, the first page when missing or 0
     page: Option<u32>,
     size: Option<u32>,
+    /// Comma separated fields to return for each movie, e.g. `title,poster`
+    #[serde(rename = "fields[movie]")]
+    fields: Option<String>,
+    /// `true` answers an empty page with 200 and an empty list instead of 204
+    #[serde(rename = "emptyAsOk")]
+    empty_as_ok: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct CountParams {
+    title: Option<String>,
+    /// Genre the movies must include, e.g. `Drama`
+    genre: Option<String>,
+    /// Four-digit release year, e.g. `2014`
+    year: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct FieldsParams {
+    /// Comma separated fields to return (plus `_id`), e.g. `title,poster`; only those are read
+    /// from MongoDB. `fields` is accepted as an alias
+    #[serde(rename = "fields[movie]", alias = "fields")]
+    fields: Option<String>,
+    /// Embeds the movie reviews as `reviews` when `true`
+    #[serde(rename = "includeReviews")]
+    include_reviews: Option<bool>,
 }
 
 /// Find all movies
@@ -26,7 +74,11 @@ pub struct Params {
     path = "/api/v1/movies/findAll",
     responses(
         (status = 200, description = "List all movies with pagination", body = [MovieResponse]),
-        (status = 204, description = "Empty List", body = AppError, example = json!(AppError::Empty.to_string())),
+        (status = 204, description = "Empty List, unless emptyAsOk=true", body = AppError, example = json!(AppError::Empty.to_string())),
+        (status = 400, description = "Invalid sort, order, year or fields", body = AppError, examples(
+            ("FieldNotAllowed" = (value = json!(AppError::FieldNotAllowed.to_string()))),
+            ("ValidationError" = (value = json!(AppError::ValidationAppError("order: 'down' is not valid, use 'asc' or 'desc'".to_string()).to_string())))
+        )),
         (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string())),
     ),
     params(
@@ -39,45 +91,141 @@ pub async fn get_movies(
     db: Data<Database>,
     params: Query<Params>,
 ) -> Result<HttpResponse, AppError> {
+    let fields = params
+        .fields
+        .as_deref()
+        .map(|raw| parse_sparse_fields(raw, MOVIE_RESPONSE_FIELDS))
+        .transpose()?;
     match db
         .find_all_movies(
-            params.title.clone(),
-            params.page.clone(),
-            params.size.clone(),
+            MovieFilter {
+                title: params.title.clone(),
+                genre: params.genre.clone(),
+                year: params.year.clone(),
+            },
+            params.sort.clone(),
+            params.order.clone(),
+            params.page,
+            params.size,
         )
         .await
     {
-        Ok(res) => Ok(HttpResponse::Ok().json(res)),
+        Ok(res) => {
+            let res = non_empty_page(res, "movies", params.empty_as_ok.unwrap_or(false))?;
+            Ok(HttpResponse::Ok().json(match fields {
+                Some(fields) => sparse_movie_list(res, &fields),
+                None => res,
+            }))
+        }
         Err(err) => Err(err),
     }
 }
 
+/// Applies a sparse fieldset to every movie of a `/findAll` result.
+pub fn sparse_movie_list(mut res: Map<String, Value>, fields: &[String]) -> Map<String, Value> {
+    if let Some(Value::Array(movies)) = res.get_mut("movies") {
+        for movie in movies.iter_mut() {
+            if let Value::Object(movie_map) = movie {
+                *movie_map = select_fields(std::mem::take(movie_map), fields);
+            }
+        }
+    }
+    res
+}
+
 /// Find movie by id
 #[utoipa::path(
     path = "/api/v1/movies/findById/{id}",
     responses(
-        (status = 200, description = "Fetch Movie by id", body = MovieDoc),
-        (status = 400, description = "Cannot parse ObjectId", body = AppError, example = json!(AppError::CannotParseObjId.to_string())),
+        (status = 200, description = "Fetch Movie by id, with a weak ETag", body = MovieDoc),
+        (status = 304, description = "Not Modified, If-None-Match holds the current ETag"),
+        (status = 400, description = "Cannot parse ObjectId or invalid fields", body = AppError, example = json!(AppError::CannotParseObjId.to_string())),
         (status = 404, description = "Not Found", body = AppError, example = json!(AppError::NotFound.to_string())),
         (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
     ),
     params(
-        ("id", description = "Unique ObjectId of Movie")
+        ("id", description = "Unique ObjectId of Movie"),
+        FieldsParams
     ),
     tag = "Movies"
 )]
 #[get("/findById/{id}")]
 pub async fn get_movie_by_id(
+    req: HttpRequest,
     db: Data<Database>,
     path: Path<String>,
+    params: Query<FieldsParams>,
 ) -> Result<HttpResponse, AppError> {
     let id = path.into_inner();
+    let params = params.into_inner();
+    let include_reviews = params.include_reviews.unwrap_or(false);
+    let fields = params
+        .fields
+        .as_deref()
+        .map(|raw| parse_sparse_fields(raw, MOVIE_FIELDS))
+        .transpose()?;
+    if let Some(fields) = fields {
+        let mut projection = build_movie_projection(&fields);
+        if include_reviews {
+            projection.insert("reviewIds", 1);
+        }
+        let projected = db
+            .find_movie_projection_by_id(id.as_str(), projection)
+            .await?;
+        let review_ids: Vec<ObjectId> = projected
+            .get_array("reviewIds")
+            .map(|ids| ids.iter().filter_map(Bson::as_object_id).collect())
+            .unwrap_or_default();
+        let average_rating = if fields.iter().any(|field| field == "averageRating") {
+            Some(db.average_rating(&review_ids).await?)
+        } else {
+            None
+        };
+        let reviews = if include_reviews {
+            Some(db.embedded_reviews(&review_ids).await?)
+        } else {
+            None
+        };
+        let mut movie_map = projected_to_json(projected);
+        if let Some(average_rating) = average_rating {
+            movie_map.insert(
+                "averageRating".to_string(),
+                serde_json::to_value(average_rating).unwrap(),
+            );
+        }
+        let mut movie_map = select_fields(movie_map, &fields);
+        if let Some(reviews) = reviews {
+            movie_map.insert(
+                "reviews".to_string(),
+                serde_json::to_value(reviews).unwrap(),
+            );
+        }
+        return Ok(conditional_json_response(&req, &movie_map));
+    }
     match db.find_movie_by_id(id.as_str()).await {
-        Ok(movie) => Ok(HttpResponse::Ok().json(movie)),
+        Ok(movie) => {
+            let average_rating = db.average_rating(&movie.review_ids).await?;
+            let reviews = if include_reviews {
+                Some(db.embedded_reviews(&movie.review_ids).await?)
+            } else {
+                None
+            };
+            let mut movie_doc = to_movie_doc(movie)?;
+            movie_doc.average_rating = average_rating;
+            movie_doc.reviews = reviews;
+            Ok(conditional_json_response(&req, &movie_doc))
+        }
         Err(err) => Err(err),
     }
 }
 
+fn to_movie_doc(movie: Movie) -> Result<MovieDoc, AppError> {
+    MovieDoc::try_from(movie).map_err(|err| {
+        error!("Error converting Movie to MovieDoc [{}]", err);
+        AppError::InternalServerError
+    })
+}
+
 /// Find movie by imdbId
 #[utoipa::path(
     path = "/api/v1/movies/findByImdbId/{imdbId}",
@@ -99,7 +247,57 @@ pub async fn get_movie_by_imdb_id(
 ) -> Result<HttpResponse, AppError> {
     let imdb_id = path.into_inner();
     match db.find_movie_by_imdb_id(imdb_id.as_str()).await {
-        Ok(movie) => Ok(HttpResponse::Ok().json(movie)),
+        Ok(movie) => Ok(HttpResponse::Ok().json(to_movie_doc(movie)?)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Find a random movie
+#[utoipa::path(
+    path = "/api/v1/movies/random",
+    responses(
+        (status = 200, description = "Fetch one randomly selected Movie", body = MovieDoc),
+        (status = 204, description = "Empty List", body = AppError, example = json!(AppError::Empty.to_string())),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
+    ),
+    tag = "Movies"
+)]
+#[get("/random")]
+pub async fn get_random_movie(db: Data<Database>) -> Result<HttpResponse, AppError> {
+    match db.find_random_movie().await {
+        Ok(movie) => Ok(HttpResponse::Ok().json(to_movie_doc(movie)?)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Count movies
+#[utoipa::path(
+    path = "/api/v1/movies/count",
+    responses(
+        (status = 200, description = "Number of movies matching the same filters as findAll", body = String, content_type = "application/json", example = json!({"count": 42})),
+        (status = 400, description = "Invalid year", body = AppError, example = json!(AppError::ValidationAppError("year: '14' is not valid, use a four-digit year like '2014'".to_string()).to_string())),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
+    ),
+    params(
+        CountParams
+    ),
+    tag = "Movies"
+)]
+#[get("/count")]
+pub async fn get_movie_count(
+    db: Data<Database>,
+    params: Query<CountParams>,
+) -> Result<HttpResponse, AppError> {
+    let params = params.into_inner();
+    match db
+        .count_movies(MovieFilter {
+            title: params.title,
+            genre: params.genre,
+            year: params.year,
+        })
+        .await
+    {
+        Ok(res) => Ok(HttpResponse::Ok().json(res)),
         Err(err) => Err(err),
     }
 }
@@ -108,10 +306,10 @@ pub async fn get_movie_by_imdb_id(
 #[utoipa::path(
     path = "/api/v1/movies/new",
     responses(
-        (status = 201, description = "Created", body = String, content_type = "application/json", example = json!(HashMap::from([("message".to_string(), "Movie was successfully created. (id: '1234')".to_string())]))),
+        (status = 201, description = "Created", body = String, content_type = "application/json", example = json!({"id": "1234", "message": "Movie was successfully created. (id: '1234')"})),
         (status = 400, description = "Already Exists or Validation Error", body = AppError, examples(
             ("AlreadyExists" = (value = json!(AppError::AlreadyExists.to_string()))),
-            ("ValidationError" = (value = json!(AppError::ValidationAppError("title: The movie title cannot be empty".to_string()).to_string())))
+            ("ValidationError" = (value = json!([{"field": "title", "messages": ["The movie title cannot be empty"]}])))
         )),
         (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
     ),
@@ -138,7 +336,7 @@ pub async fn create_movie(
                 poster: request.poster.clone(),
                 backdrop: request.backdrop.clone(),
             })
-            .expect("Error converting request to Movie"),
+            .map_err(internal_error("Error converting request to Movie"))?,
         )
         .await
     {
@@ -147,13 +345,67 @@ pub async fn create_movie(
     }
 }
 
+/// Create several movies
+#[utoipa::path(
+    path = "/api/v1/movies/bulk",
+    responses(
+        (status = 201, description = "Every movie was created", body = Object, content_type = "application/json", example = json!({"succeeded": 1, "failed": 0, "results": [{"index": 0, "success": true, "data": {"id": "1234", "message": "Movie was successfully created. (id: '1234')"}}]})),
+        (status = 207, description = "Some movies were created and some failed, see each result", body = Object, content_type = "application/json", example = json!({"succeeded": 1, "failed": 1, "results": [{"index": 0, "success": true, "data": {"id": "1234", "message": "Movie was successfully created. (id: '1234')"}}, {"index": 1, "success": false, "error": AppError::AlreadyExists.to_string()}]})),
+        (status = 400, description = "No movie was created or the list is empty", body = AppError, example = json!(AppError::ValidationAppError("The bulk request cannot be empty".to_string()).to_string())),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string())),
+    ),
+    request_body = Vec<MovieRequest>,
+    tag = "Movies"
+)]
+#[post("/bulk")]
+pub async fn create_movies_bulk(
+    db: Data<Database>,
+    request: Json<Vec<MovieRequest>>,
+) -> Result<HttpResponse, AppError> {
+    if request.is_empty() {
+        return Err(AppError::ValidationAppError(
+            "The bulk request cannot be empty".to_string(),
+        ));
+    }
+    // Valid movies go to the database in a single insert_many, invalid ones keep their error
+    let mut movies = Vec::with_capacity(request.len());
+    let mut invalid: Vec<Option<AppError>> = Vec::with_capacity(request.len());
+    for item in request.into_inner() {
+        match to_bulk_movie(item) {
+            Ok(movie) => {
+                movies.push(movie);
+                invalid.push(None);
+            }
+            Err(err) => invalid.push(Some(err)),
+        }
+    }
+    let mut created = db.create_movies(movies).await?.into_iter();
+    let results = invalid
+        .into_iter()
+        .map(|invalid| match invalid {
+            Some(err) => Err(err),
+            None => created
+                .next()
+                .unwrap_or(Err(AppError::InternalServerError))
+                .map(created_movie_result),
+        })
+        .collect();
+    let bulk = BulkResult::from_results(results);
+    Ok(HttpResponse::build(bulk.status_code()).json(bulk))
+}
+
+fn to_bulk_movie(item: MovieRequest) -> Result<Movie, AppError> {
+    item.validate()?;
+    Movie::try_from(item).map_err(|err| AppError::ValidationAppError(err.to_string()))
+}
+
 /// Delete movie by id
 #[utoipa::path(
     path = "/api/v1/movies/delete/{id}",
     responses(
         (status = 200, description = "Deleted", body = String, content_type = "application/json", example = json!(HashMap::from([("message".to_string(), "Movie with id: '1234' was successfully deleted".to_string())]))),
         (status = 400, description = "Cannot parse ObjectId", body = AppError, example = json!(AppError::CannotParseObjId.to_string())),
-        (status = 404, description = "Not Exists", body = AppError, example = json!(AppError::NotExists.to_string())),
+        (status = 404, description = "Not Found", body = AppError, example = json!(AppError::NotFound.to_string())),
         (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
     ),
     params(
@@ -180,7 +432,7 @@ pub async fn delete_movie_by_id(
         (status = 200, description = "Updated", body = String, content_type = "application/json", example = json!(HashMap::from([("message".to_string(), "Movie with id: '1234' was successfully updated".to_string())]))),
         (status = 404, description = "Not Exists", body = AppError, example = json!(AppError::NotExists.to_string())),
         (status = 400, description = "Validation Error, Cannot parse ObjectId or ImdbId in use", body = AppError, examples(
-            ("ValidationError" = (value = json!(AppError::ValidationAppError("title: The movie title cannot be empty".to_string()).to_string()))),
+            ("ValidationError" = (value = json!([{"field": "title", "messages": ["The movie title cannot be empty"]}]))),
             ("Cannot parse ObjectId" = (value = json!(AppError::CannotParseObjId.to_string()))),
             ("ImdbId in use" = (value = json!(AppError::ImdbIdInUse.to_string())))
         )),
@@ -253,3 +505,191 @@ pub async fn patch_movie_by_id(
         Err(err) => Err(err),
     }
 }
+
+/// Patch several movie fields by id
+#[utoipa::path(
+    path = "/api/v1/movies/patchMany/{id}",
+    responses(
+        (status = 200, description = "Patched", body = String, content_type = "application/json", example = json!(HashMap::from([("message".to_string(), "Movie fields (director, title) with id: '1234' were successfully patched".to_string())]))),
+        (status = 404, description = "Not Exists", body = AppError, example = json!(AppError::NotExists.to_string())),
+        (status = 400, description = "Field not allowed, Cannot parse ObjectId, Wrong ImdbId or ImdbId in use", body = AppError, examples(
+            ("Field not allowed" = (value = json!(AppError::FieldNotAllowed.to_string()))),
+            ("Cannot parse ObjectId" = (value = json!(AppError::CannotParseObjId.to_string()))),
+            ("Wrong ImdbId" = (value = json!(AppError::WrongImdbId.to_string()))),
+            ("ImdbId in use" = (value = json!(AppError::ImdbIdInUse.to_string())))
+        )),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
+    ),
+    params(
+        ("id", description = "Unique ObjectId of Movie")
+    ),
+    request_body(content = Object, description = "Field/value pairs to set, all applied in a single update", example = json!({"title": "Casino", "director": "Martin Scorsese", "genres": "[\"Crime\", \"Drama\"]"})),
+    tag = "Movies"
+)]
+#[patch("/patchMany/{id}")]
+pub async fn patch_movie_many_by_id(
+    db: Data<Database>,
+    path: Path<String>,
+    fields: Json<HashMap<String, String>>,
+) -> Result<HttpResponse, AppError> {
+    let id = path.into_inner();
+
+    match db.patch_movie_many(id.as_str(), fields.into_inner()).await {
+        Ok(res) => Ok(HttpResponse::Ok().json(res)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Replace movie genres by id
+#[utoipa::path(
+    path = "/api/v1/movies/{id}/genres",
+    responses(
+        (status = 200, description = "Genres replaced", body = String, content_type = "application/json", example = json!(HashMap::from([("message".to_string(), "Movie genres with id: '1234' were successfully updated".to_string())]))),
+        (status = 404, description = "Not Exists", body = AppError, example = json!(AppError::NotExists.to_string())),
+        (status = 400, description = "Validation Error or Cannot parse ObjectId", body = AppError, examples(
+            ("ValidationError" = (value = json!([{"field": "genres", "messages": ["These genres are duplicated: drama"]}]))),
+            ("Cannot parse ObjectId" = (value = json!(AppError::CannotParseObjId.to_string())))
+        )),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
+    ),
+    params(
+        ("id", description = "Unique ObjectId of Movie")
+    ),
+    request_body = GenresRequest,
+    tag = "Movies"
+)]
+#[put("/{id}/genres")]
+pub async fn put_movie_genres(
+    db: Data<Database>,
+    path: Path<String>,
+    request: Json<GenresRequest>,
+) -> Result<HttpResponse, AppError> {
+    let id = path.into_inner();
+
+    request.validate()?;
+    match db
+        .set_movie_genres(id.as_str(), request.into_inner().genres)
+        .await
+    {
+        Ok(res) => Ok(HttpResponse::Ok().json(res)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Create or update movie by imdbId
+#[utoipa::path(
+    path = "/api/v1/movies/byImdbId/{imdbId}",
+    responses(
+        (status = 200, description = "Updated", body = String, content_type = "application/json", example = json!({"created": false, "id": "1234"})),
+        (status = 201, description = "Created", body = String, content_type = "application/json", example = json!({"created": true, "id": "1234"})),
+        (status = 400, description = "Validation Error, Wrong ImdbId or ImdbId in use", body = AppError, examples(
+            ("ValidationError" = (value = json!([{"field": "title", "messages": ["The movie title cannot be empty"]}]))),
+            ("Wrong ImdbId" = (value = json!(AppError::WrongImdbId.to_string()))),
+            ("ImdbId in use" = (value = json!(AppError::ImdbIdInUse.to_string())))
+        )),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
+    ),
+    params(
+        ("imdbId", description = "Unique imdbId of Movie")
+    ),
+    request_body = MovieRequest,
+    tag = "Movies"
+)]
+#[put("/byImdbId/{imdbId}")]
+pub async fn upsert_movie_by_imdb_id(
+    db: Data<Database>,
+    path: Path<String>,
+    movie: Json<MovieRequest>,
+) -> Result<HttpResponse, AppError> {
+    let imdb_id = path.into_inner();
+
+    movie.validate()?;
+    if movie.imdb_id != imdb_id {
+        return Err(AppError::ValidationAppError(
+            "imdbId: The imdbId of the body must match the one in the path".to_string(),
+        ));
+    }
+    match db.upsert_movie_by_imdb_id(imdb_id.as_str(), movie.0).await {
+        Ok(res) if res["created"] == true => Ok(HttpResponse::Created().json(res)),
+        Ok(res) => Ok(HttpResponse::Ok().json(res)),
+        Err(err) => Err(err),
+    }
+}
+
+/// List patchable fields of Movie
+#[utoipa::path(
+    path = "/api/v1/movies/patchableFields",
+    responses(
+        (status = 200, description = "List the fields accepted by the Movie patch endpoint and their expected types", body = [PatchableField]),
+    ),
+    tag = "Movies"
+)]
+#[get("/patchableFields")]
+pub async fn get_movie_patchable_fields() -> HttpResponse {
+    HttpResponse::Ok().json(MOVIE_PATCHABLE_FIELDS)
+}
+
+/// List the genres in use by movies
+#[utoipa::path(
+    path = "/api/v1/movies/genres",
+    responses(
+        (status = 200, description = "Sorted list of the distinct movie genres", body = [String], example = json!(["Action", "Crime", "Drama"])),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
+    ),
+    tag = "Movies"
+)]
+#[get("/genres")]
+pub async fn get_movie_genres(db: Data<Database>) -> Result<HttpResponse, AppError> {
+    match db.distinct_genres().await {
+        Ok(genres) => Ok(HttpResponse::Ok().json(genres)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Find the release date range of movies
+#[utoipa::path(
+    path = "/api/v1/movies/dateRange",
+    responses(
+        (status = 200, description = "Earliest and latest movie release dates (null when there are no movies)", body = Object, content_type = "application/json", example = json!({"earliest": "1941-09-05", "latest": "2024-03-01"})),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
+    ),
+    tag = "Movies"
+)]
+#[get("/dateRange")]
+pub async fn get_movie_date_range(db: Data<Database>) -> Result<HttpResponse, AppError> {
+    match db.find_release_date_range().await {
+        Ok(res) => Ok(HttpResponse::Ok().json(res)),
+        Err(err) => Err(err),
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ImdbIdParams {
+    /// 1-based page number, the first page when missing or 0
+    page: Option<u32>,
+    size: Option<u32>,
+}
+
+/// List movie imdbIds
+#[utoipa::path(
+    path = "/api/v1/movies/imdbIds",
+    responses(
+        (status = 200, description = "List only the imdbIds of the movies with pagination, for catalog syncs", body = Object, content_type = "application/json", example = json!({"imdbIds": ["tt0068646", "tt0071562"], "currentPage": 1, "pageSize": 10, "totalItems": 2, "totalPages": 1, "hasPrev": false, "hasNext": false})),
+        (status = 204, description = "Empty List", body = AppError, example = json!(AppError::Empty.to_string())),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
+    ),
+    params(
+        ImdbIdParams
+    ),
+    tag = "Movies"
+)]
+#[get("/imdbIds")]
+pub async fn get_movie_imdb_ids(
+    db: Data<Database>,
+    params: Query<ImdbIdParams>,
+) -> Result<HttpResponse, AppError> {
+    match db.list_movie_imdb_ids(params.page, params.size).await {
+        Ok(res) => Ok(HttpResponse::Ok().json(res)),
+        Err(err) => Err(err),
+    }
+}
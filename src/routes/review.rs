@@ -2,23 +2,45 @@ use std::collections::HashMap;
 
 use actix_web::{
     delete, get, patch, post, put,
-    web::{Data, Json, Path, Query},
-    HttpResponse,
+    web::{Bytes, Data, Json, Path, Query},
+    HttpRequest, HttpResponse,
 };
+use futures_util::StreamExt;
 use serde::Deserialize;
+use serde_json::{Map, Value};
 use utoipa::{IntoParams, ToSchema};
 use validator::Validate;
 
 use crate::{
-    error::AppError,
-    models::review::{Review, ReviewRequest, ReviewUpdate},
-    services::{db::Database, review_repo::ReviewRepository},
+    error::{internal_error, AppError},
+    models::bulk::BulkResult,
+    models::fields::REVIEW_PATCHABLE_FIELDS,
+    models::review::{Review, ReviewReassign, ReviewRequest, ReviewUpdate},
+    routes::etag::conditional_list_response,
+    services::{
+        db::Database,
+        pagination::non_empty_page,
+        review_repo::{to_ndjson_line, ReviewRepository},
+    },
 };
 
 #[derive(Debug, Deserialize, IntoParams)]
 pub struct Params {
+    /// Term searched (case-insensitive) in the review title and body
+    q: Option<String>,
+    /// Only reviews rated at least this much (0 to 5)
+    #[serde(rename = "minRating")]
+    min_rating: Option<f32>,
+    /// `ratingAsc`, `ratingDesc`, `newest` or `oldest` (insertion order when missing)
+    sort: Option<String>,
+    /// Opaque token returned as `nextCursor`, replaces `page` when present (default sort only)
+    cursor: Option<String>,
+    /// 1-based page number, the first page when missing or 0
     page: Option<u32>,
     size: Option<u32>,
+    /// `true` answers an empty page with 200 and an empty list instead of 204
+    #[serde(rename = "emptyAsOk")]
+    empty_as_ok: Option<bool>,
 }
 
 /// Find all reviews
@@ -26,7 +48,13 @@ pub struct Params {
     path = "/api/v1/reviews/findAll",
     responses(
         (status = 200, description = "List all reviews with pagination", body = [ReviewResponseDoc]),
-        (status = 204, description = "Empty List", body = AppError, example = json!(AppError::Empty.to_string())),
+        (status = 204, description = "Empty List, unless emptyAsOk=true", body = AppError, example = json!(AppError::Empty.to_string())),
+        (status = 304, description = "Not Modified, the page still matches If-None-Match"),
+        (status = 400, description = "Malformed cursor, invalid sort or minRating out of range", body = AppError, examples(
+            ("Malformed cursor" = (value = json!(AppError::ValidationAppError("Malformed cursor: 'abc'".to_string()).to_string()))),
+            ("Invalid sort" = (value = json!(AppError::ValidationAppError("sort: 'rating' is not valid, use 'ratingAsc', 'ratingDesc', 'newest' or 'oldest'".to_string()).to_string()))),
+            ("Invalid minRating" = (value = json!(AppError::ValidationAppError("minRating: '7' is not valid, it must be between 0 and 5".to_string()).to_string())))
+        )),
         (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string())),
     ),
     params(
@@ -36,11 +64,56 @@ pub struct Params {
 )]
 #[get("/findAll")]
 pub async fn get_reviews(
+    req: HttpRequest,
     db: Data<Database>,
     params: Query<Params>,
 ) -> Result<HttpResponse, AppError> {
     match db
-        .find_all_reviews(params.page.clone(), params.size.clone())
+        .find_all_reviews(
+            params.q.clone(),
+            params.min_rating,
+            params.sort.clone(),
+            params.cursor.clone(),
+            params.page,
+            params.size,
+        )
+        .await
+    {
+        Ok(res) => {
+            let res = non_empty_page(res, "reviews", params.empty_as_ok.unwrap_or(false))?;
+            Ok(conditional_list_response(&req, &res, "reviews"))
+        }
+        Err(err) => Err(err),
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ParentParams {
+    /// 1-based page number, the first page when missing or 0
+    page: Option<u32>,
+    size: Option<u32>,
+}
+
+/// Find all reviews with their parent
+#[utoipa::path(
+    path = "/api/v1/reviews/findAllWithParents",
+    responses(
+        (status = 200, description = "List all reviews with pagination, each one with the imdbId, title and type of its movie or series", body = Object, example = json!({"reviews": [{"_id": "66a3a6c2f1a1b2c3d4e5f601", "title": "Una obra maestra", "rating": 5, "body": "Impresionante", "createdAt": "2024-05-07T11:56:05.792+00:00", "updatedAt": "2024-05-07T11:56:05.792+00:00", "bodyPreview": "Impresionante", "parent": {"imdbId": "tt12345", "title": "El padrino", "type": "movie"}}], "currentPage": 1, "pageSize": 10, "totalItems": 1, "totalPages": 1, "hasPrev": false, "hasNext": false})),
+        (status = 204, description = "Empty List", body = AppError, example = json!(AppError::Empty.to_string())),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string())),
+    ),
+    params(
+        ParentParams
+    ),
+    tag = "Reviews"
+)]
+#[get("/findAllWithParents")]
+pub async fn get_reviews_with_parents(
+    db: Data<Database>,
+    params: Query<ParentParams>,
+) -> Result<HttpResponse, AppError> {
+    match db
+        .find_all_reviews_with_parents(params.page, params.size)
         .await
     {
         Ok(res) => Ok(HttpResponse::Ok().json(res)),
@@ -48,18 +121,26 @@ pub async fn get_reviews(
     }
 }
 
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct PageParams {
+    /// 1-based page number, the first page when missing or 0
+    page: Option<u32>,
+    size: Option<u32>,
+}
+
 /// Find all reviews by imdbId
 #[utoipa::path(
     path = "/api/v1/reviews/findAllByImdbId/{imdbId}",
     responses(
-        (status = 200, description = "List all reviews by imdbId with pagination", body = [ReviewResponseDoc]),
+        (status = 200, description = "List all reviews by imdbId with pagination", body = Object, content_type = "application/json", example = json!({"reviews": [{"_id": "66a3a6c2f1a1b2c3d4e5f601", "title": "Una obra maestra", "rating": 5, "body": "Impresionante", "createdAt": "2024-05-07T11:56:05.792+00:00", "updatedAt": "2024-05-07T11:56:05.792+00:00"}], "currentPage": 1, "pageSize": 10, "totalItems": 1, "totalPages": 1, "hasPrev": false, "hasNext": false})),
         (status = 204, description = "Empty List", body = AppError, example = json!(AppError::Empty.to_string())),
         (status = 400, description = "Wrong ImdbId passed", body = AppError, example = json!(AppError::WrongImdbId.to_string())),
         (status = 404, description = "Not Exists", body = AppError, example = json!(AppError::NotExists.to_string())),
         (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string())),
     ),
     params(
-        ("imdbId", description = "Unique imdbId of Movie or Series")
+        ("imdbId", description = "Unique imdbId of Movie or Series"),
+        PageParams
     ),
     tag = "Reviews"
 )]
@@ -67,9 +148,60 @@ pub async fn get_reviews(
 pub async fn get_reviews_by_imdb_id(
     db: Data<Database>,
     path: Path<String>,
+    params: Query<PageParams>,
 ) -> Result<HttpResponse, AppError> {
     let imdb_id = path.into_inner();
-    match db.find_all_reviews_by_imdb_id(imdb_id.as_str()).await {
+    match db
+        .find_all_reviews_by_imdb_id(imdb_id.as_str(), params.page, params.size)
+        .await
+    {
+        Ok(review_list) => Ok(HttpResponse::Ok().json(review_list)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Export all reviews as NDJSON
+#[utoipa::path(
+    path = "/api/v1/reviews/export.ndjson",
+    responses(
+        (status = 200, description = "Stream every review, one ReviewResponse JSON per line", body = String, content_type = "application/x-ndjson", example = json!("{\"_id\":\"66a3a6c2f1a1b2c3d4e5f601\",\"title\":\"Una obra maestra\",\"rating\":5,\"body\":\"Impresionante\",\"createdAt\":\"2024-05-07T11:56:05.792+00:00\",\"updatedAt\":\"2024-05-07T11:56:05.792+00:00\"}\n")),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string())),
+    ),
+    tag = "Reviews"
+)]
+#[get("/export.ndjson")]
+pub async fn export_reviews_ndjson(db: Data<Database>) -> Result<HttpResponse, AppError> {
+    let cursor = db.export_reviews_cursor().await?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/x-ndjson")
+        .streaming(cursor.map(|result| to_ndjson_line(result).map(Bytes::from))))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct LatestParams {
+    /// Number of reviews, 5 when missing and never more than 50
+    limit: Option<u32>,
+}
+
+/// Find the latest reviews
+#[utoipa::path(
+    path = "/api/v1/reviews/latest",
+    responses(
+        (status = 200, description = "List the most recently created reviews of any movie or series, newest first", body = [ReviewResponseDoc]),
+        (status = 204, description = "Empty List", body = AppError, example = json!(AppError::Empty.to_string())),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string())),
+    ),
+    params(
+        LatestParams
+    ),
+    tag = "Reviews"
+)]
+#[get("/latest")]
+pub async fn get_latest_reviews(
+    db: Data<Database>,
+    params: Query<LatestParams>,
+) -> Result<HttpResponse, AppError> {
+    match db.find_latest_reviews(params.limit.unwrap_or(5)).await {
         Ok(review_list) => Ok(HttpResponse::Ok().json(review_list)),
         Err(err) => Err(err),
     }
@@ -105,8 +237,8 @@ pub async fn get_review_by_id(
 #[utoipa::path(
     path = "/api/v1/reviews/new",
     responses(
-        (status = 201, description = "Created", body = String, content_type = "application/json", example = json!(HashMap::from([("message".to_string(), "Review was successfully created. (id: '1234')".to_string())]))),
-        (status = 400, description = "ValidationError", body = AppError, example = json!(AppError::ValidationAppError("title: The review title cannot be empty".to_string()).to_string())),
+        (status = 201, description = "Created", body = Object, content_type = "application/json", example = json!({"id": "66a3a6c2f1a1b2c3d4e5f601", "message": "Review was successfully created. (id: '66a3a6c2f1a1b2c3d4e5f601')", "imdbId": "tt12345", "parentType": "movie", "newReviewCount": 3})),
+        (status = 400, description = "ValidationError", body = AppError, example = json!([{"field": "title", "messages": ["The review title cannot be empty"]}])),
         (status = 404, description = "Not Exists", body = AppError, example = json!(AppError::NotExists.to_string())),
         (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
     ),
@@ -121,13 +253,8 @@ pub async fn create_review(
     request.validate()?;
     match db
         .create_review(
-            Review::try_from(ReviewRequest {
-                title: request.title.clone(),
-                rating: request.rating.clone(),
-                body: request.title.clone(),
-                imdb_id: request.imdb_id.clone(),
-            })
-            .expect("Error converting request to Review"),
+            Review::try_from(&*request)
+                .map_err(internal_error("Error converting request to Review"))?,
             request.imdb_id.as_str(),
         )
         .await
@@ -137,13 +264,53 @@ pub async fn create_review(
     }
 }
 
+/// Create several reviews
+#[utoipa::path(
+    path = "/api/v1/reviews/bulk",
+    responses(
+        (status = 201, description = "Every review was created", body = Object, content_type = "application/json", example = json!({"succeeded": 1, "failed": 0, "results": [{"index": 0, "success": true, "data": {"id": "66a3a6c2f1a1b2c3d4e5f601", "imdbId": "tt12345", "parentType": "movie", "newReviewCount": 3}}]})),
+        (status = 207, description = "Some reviews were created and some failed, see each result", body = Object, content_type = "application/json", example = json!({"succeeded": 1, "failed": 1, "results": [{"index": 0, "success": true, "data": {"id": "66a3a6c2f1a1b2c3d4e5f601", "imdbId": "tt12345", "parentType": "movie", "newReviewCount": 3}}, {"index": 1, "success": false, "error": AppError::NotExists.to_string()}]})),
+        (status = 400, description = "No review was created or the list is empty", body = AppError, example = json!(AppError::ValidationAppError("The bulk request cannot be empty".to_string()).to_string())),
+    ),
+    request_body = Vec<ReviewRequest>,
+    tag = "Reviews"
+)]
+#[post("/bulk")]
+pub async fn create_reviews_bulk(
+    db: Data<Database>,
+    request: Json<Vec<ReviewRequest>>,
+) -> Result<HttpResponse, AppError> {
+    if request.is_empty() {
+        return Err(AppError::ValidationAppError(
+            "The bulk request cannot be empty".to_string(),
+        ));
+    }
+    let mut results = Vec::with_capacity(request.len());
+    for item in request.into_inner() {
+        results.push(create_bulk_item(&db, item).await);
+    }
+    let bulk = BulkResult::from_results(results);
+    Ok(HttpResponse::build(bulk.status_code()).json(bulk))
+}
+
+async fn create_bulk_item(
+    db: &Database,
+    item: ReviewRequest,
+) -> Result<Map<String, Value>, AppError> {
+    item.validate()?;
+    let imdb_id = item.imdb_id.clone();
+    let review =
+        Review::try_from(item).map_err(|err| AppError::ValidationAppError(err.to_string()))?;
+    db.create_review(review, imdb_id.as_str()).await
+}
+
 /// Delete review by id
 #[utoipa::path(
     path = "/api/v1/reviews/delete/{id}",
     responses(
         (status = 200, description = "Deleted", body = String, content_type = "application/json", example = json!(HashMap::from([("message".to_string(), "Review with id: '1234' was successfully deleted".to_string())]))),
         (status = 400, description = "Cannot parse ObjectId", body = AppError, example = json!(AppError::CannotParseObjId.to_string())),
-        (status = 404, description = "Not Exists", body = AppError, example = json!(AppError::NotExists.to_string())),
+        (status = 404, description = "Not Found", body = AppError, example = json!(AppError::NotFound.to_string())),
         (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
     ),
     params(
@@ -167,10 +334,10 @@ pub async fn delete_review_by_id(
 #[utoipa::path(
     path = "/api/v1/reviews/update/{id}",
     responses(
-        (status = 200, description = "Updated", body = String, content_type = "application/json", example = json!(HashMap::from([("message".to_string(), "Review with id: '1234' was successfully updated".to_string())]))),
+        (status = 200, description = "Updated", body = String, content_type = "application/json", example = json!({"message": "Review with id: '1234' was successfully updated", "createdAt": "2024-05-12T10:15:00+00:00", "updatedAt": "2024-06-01T18:30:00+00:00"})),
         (status = 400, description = "Cannot parse ObjectId or Validation Error", body = AppError, examples(
             ("Cannot parse ObjectId" = (value = json!(AppError::CannotParseObjId.to_string()))),
-            ("ValidationError" = (value = json!(AppError::ValidationAppError("title: The review title cannot be empty".to_string()).to_string())))
+            ("ValidationError" = (value = json!([{"field": "title", "messages": ["The review title cannot be empty"]}])))
         )),
         (status = 404, description = "Not Exists", body = AppError, example = json!(AppError::NotExists.to_string())),
         (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
@@ -206,7 +373,7 @@ pub struct PatchParams {
 #[utoipa::path(
     path = "/api/v1/reviews/patch/{id}",
     responses(
-        (status = 200, description = "Patched", body = String, content_type = "application/json", example = json!(HashMap::from([("message".to_string(), "Review rating with id: '1234' was successfully patched".to_string())]))),
+        (status = 200, description = "Patched", body = String, content_type = "application/json", example = json!({"message": "Review rating with id: '1234' was successfully patched", "createdAt": "2024-05-12T10:15:00+00:00", "updatedAt": "2024-06-01T18:30:00+00:00"})),
         (status = 404, description = "Not Exists", body = AppError, example = json!(AppError::NotExists.to_string())),
         (status = 400, description = "Cannot parse ObjectId or Field not allowed", body = AppError, examples(
             ("Cannot parse ObjectId" = (value = json!(AppError::CannotParseObjId.to_string()))),
@@ -240,3 +407,53 @@ pub async fn patch_review_by_id(
         Err(err) => Err(err),
     }
 }
+
+/// Move a review to another movie or series
+#[utoipa::path(
+    path = "/api/v1/reviews/{id}/reassign",
+    responses(
+        (status = 200, description = "Reassigned", body = String, content_type = "application/json", example = json!({"message": "Review with id: '1234' was successfully reassigned to imdbId: 'tt0068646'", "parentType": "movie"})),
+        (status = 400, description = "Cannot parse ObjectId, Validation Error or no movie or series with that imdbId", body = AppError, examples(
+            ("Cannot parse ObjectId" = (value = json!(AppError::CannotParseObjId.to_string()))),
+            ("ValidationError" = (value = json!([{"field": "imdbId", "messages": ["The imdbId must match the following format: 'tt0000'"]}]))),
+            ("Not Exists" = (value = json!(AppError::NotExists.to_string())))
+        )),
+        (status = 404, description = "Not Found", body = AppError, example = json!(AppError::NotFound.to_string())),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
+    ),
+    params(
+        ("id", description = "Unique ObjectId of Review")
+    ),
+    request_body = ReviewReassign,
+    tag = "Reviews"
+)]
+#[patch("/{id}/reassign")]
+pub async fn reassign_review_by_id(
+    db: Data<Database>,
+    path: Path<String>,
+    body: Json<ReviewReassign>,
+) -> Result<HttpResponse, AppError> {
+    let id = path.into_inner();
+
+    body.validate()?;
+    match db
+        .reassign_review(id.as_str(), body.0.imdb_id.as_str())
+        .await
+    {
+        Ok(res) => Ok(HttpResponse::Ok().json(res)),
+        Err(err) => Err(err),
+    }
+}
+
+/// List patchable fields of Review
+#[utoipa::path(
+    path = "/api/v1/reviews/patchableFields",
+    responses(
+        (status = 200, description = "List the fields accepted by the Review patch endpoint and their expected types", body = [PatchableField]),
+    ),
+    tag = "Reviews"
+)]
+#[get("/patchableFields")]
+pub async fn get_review_patchable_fields() -> HttpResponse {
+    HttpResponse::Ok().json(REVIEW_PATCHABLE_FIELDS)
+}
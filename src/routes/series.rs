@@ -3,23 +3,30 @@ use std::collections::HashMap;
 use actix_web::{
     delete, get, patch, post, put,
     web::{Data, Json, Path, Query},
-    HttpResponse,
+    HttpRequest, HttpResponse,
 };
+use log::error;
 use serde::Deserialize;
 use utoipa::{IntoParams, ToSchema};
 use validator::Validate;
 
 use crate::{
-    error::AppError,
-    models::series::{Series, SeriesRequest},
-    services::{db::Database, series_repo::SeriesRepository},
+    error::{internal_error, AppError},
+    models::fields::SERIES_PATCHABLE_FIELDS,
+    models::series::{Episode, SeasonListRequest, Series, SeriesDoc, SeriesRequest},
+    routes::etag::conditional_json_response,
+    services::{db::Database, pagination::non_empty_page, series_repo::SeriesRepository},
 };
 
 #[derive(Debug, Deserialize, IntoParams)]
 pub struct Params {
     title: Option<String>,
+    /// 1-based page number, the first page when missing or 0
     page: Option<u32>,
     size: Option<u32>,
+    /// `true` answers an empty page with 200 and an empty list instead of 204
+    #[serde(rename = "emptyAsOk")]
+    empty_as_ok: Option<bool>,
 }
 
 /// Find all series
@@ -27,7 +34,7 @@ pub struct Params {
     path = "/api/v1/series/findAll",
     responses(
         (status = 200, description = "List all series with pagination", body = [SeriesResponse]),
-        (status = 204, description = "Empty List", body = AppError, example = json!(AppError::Empty.to_string())),
+        (status = 204, description = "Empty List, unless emptyAsOk=true", body = AppError, example = json!(AppError::Empty.to_string())),
         (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string())),
     ),
     params(
@@ -41,40 +48,272 @@ pub async fn get_series(
     params: Query<Params>,
 ) -> Result<HttpResponse, AppError> {
     match db
-        .find_all_series(
-            params.title.clone(),
-            params.page.clone(),
-            params.size.clone(),
-        )
+        .find_all_series(params.title.clone(), params.page, params.size)
         .await
     {
-        Ok(res) => Ok(HttpResponse::Ok().json(res)),
+        Ok(res) => Ok(HttpResponse::Ok().json(non_empty_page(
+            res,
+            "series",
+            params.empty_as_ok.unwrap_or(false),
+        )?)),
         Err(err) => Err(err),
     }
 }
 
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct DetailParams {
+    /// Embeds the series reviews as `reviews` when `true`
+    #[serde(rename = "includeReviews")]
+    include_reviews: Option<bool>,
+}
+
 /// Find series by id
 #[utoipa::path(
     path = "/api/v1/series/findById/{id}",
     responses(
-        (status = 200, description = "Fetch Series by id", body = SeriesDoc),
+        (status = 200, description = "Fetch Series by id, with a weak ETag", body = SeriesDoc),
+        (status = 304, description = "Not Modified, If-None-Match holds the current ETag"),
         (status = 400, description = "Cannot parse ObjectId", body = AppError, example = json!(AppError::CannotParseObjId.to_string())),
         (status = 404, description = "Not Found", body = AppError, example = json!(AppError::NotFound.to_string())),
         (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
     ),
     params(
-        ("id", description = "Unique ObjectId of Series")
+        ("id", description = "Unique ObjectId of Series"),
+        DetailParams
     ),
     tag = "Series"
 )]
 #[get("/findById/{id}")]
 pub async fn get_series_by_id(
+    req: HttpRequest,
     db: Data<Database>,
     path: Path<String>,
+    params: Query<DetailParams>,
 ) -> Result<HttpResponse, AppError> {
     let id = path.into_inner();
     match db.find_series_by_id(id.as_str()).await {
-        Ok(series) => Ok(HttpResponse::Ok().json(series)),
+        Ok(series) => {
+            let average_rating = db.average_rating(&series.review_ids).await?;
+            let reviews = if params.include_reviews.unwrap_or(false) {
+                Some(db.embedded_reviews(&series.review_ids).await?)
+            } else {
+                None
+            };
+            let mut series_doc = to_series_doc(series)?;
+            series_doc.average_rating = average_rating;
+            series_doc.reviews = reviews;
+            Ok(conditional_json_response(&req, &series_doc))
+        }
+        Err(err) => Err(err),
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct CountParams {
+    title: Option<String>,
+}
+
+/// Count series
+#[utoipa::path(
+    path = "/api/v1/series/count",
+    responses(
+        (status = 200, description = "Number of series matching the same title filter as findAll", body = String, content_type = "application/json", example = json!({"count": 12})),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
+    ),
+    params(
+        CountParams
+    ),
+    tag = "Series"
+)]
+#[get("/count")]
+pub async fn get_series_count(
+    db: Data<Database>,
+    params: Query<CountParams>,
+) -> Result<HttpResponse, AppError> {
+    match db.count_series(params.into_inner().title).await {
+        Ok(res) => Ok(HttpResponse::Ok().json(res)),
+        Err(err) => Err(err),
+    }
+}
+
+/// List the genres in use by series
+#[utoipa::path(
+    path = "/api/v1/series/genres",
+    responses(
+        (status = 200, description = "Sorted list of the distinct series genres", body = [String], example = json!(["Comedy", "Crime", "Drama"])),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
+    ),
+    tag = "Series"
+)]
+#[get("/genres")]
+pub async fn get_series_genres(db: Data<Database>) -> Result<HttpResponse, AppError> {
+    match db.distinct_genres().await {
+        Ok(genres) => Ok(HttpResponse::Ok().json(genres)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Find series total runtime by id
+#[utoipa::path(
+    path = "/api/v1/series/findById/{id}/totalRuntime",
+    responses(
+        (status = 200, description = "Sum of every episode duration of the Series", body = String, content_type = "application/json", example = json!({"totalMinutes": 231, "formatted": "3h 51m"})),
+        (status = 400, description = "Cannot parse ObjectId", body = AppError, example = json!(AppError::CannotParseObjId.to_string())),
+        (status = 404, description = "Not Found", body = AppError, example = json!(AppError::NotFound.to_string())),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
+    ),
+    params(
+        ("id", description = "Unique ObjectId of Series")
+    ),
+    tag = "Series"
+)]
+#[get("/findById/{id}/totalRuntime")]
+pub async fn get_series_total_runtime(
+    db: Data<Database>,
+    path: Path<String>,
+) -> Result<HttpResponse, AppError> {
+    let id = path.into_inner();
+    match db.total_runtime(id.as_str()).await {
+        Ok(res) => Ok(HttpResponse::Ok().json(res)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Find a season of a series
+#[utoipa::path(
+    path = "/api/v1/series/{id}/seasons/{index}",
+    responses(
+        (status = 200, description = "Fetch the Season at the given 1-based index", body = crate::models::series::Season),
+        (status = 400, description = "Cannot parse ObjectId", body = AppError, example = json!(AppError::CannotParseObjId.to_string())),
+        (status = 404, description = "Not Found, the series does not exist or has no season at that index", body = AppError, example = json!(AppError::NotFound.to_string())),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
+    ),
+    params(
+        ("id", description = "Unique ObjectId of Series"),
+        ("index", description = "1-based position of the season")
+    ),
+    tag = "Series"
+)]
+#[get("/{id}/seasons/{index}")]
+pub async fn get_series_season(
+    db: Data<Database>,
+    path: Path<(String, usize)>,
+) -> Result<HttpResponse, AppError> {
+    let (id, index) = path.into_inner();
+    match db.find_series_season(id.as_str(), index).await {
+        Ok(season) => Ok(HttpResponse::Ok().json(season)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Add an episode to a season of a series
+#[utoipa::path(
+    path = "/api/v1/series/{id}/seasons/{index}/episodes",
+    responses(
+        (status = 201, description = "Created", body = String, content_type = "application/json", example = json!({"message": "Episode was successfully added to season 1 of series with id: '1234'"})),
+        (status = 400, description = "Cannot parse ObjectId or Validation Error", body = AppError, examples(
+            ("CannotParseObjId" = (value = json!(AppError::CannotParseObjId.to_string()))),
+            ("ValidationError" = (value = json!([{"field": "title", "messages": ["The episode title cannot be empty"]}])))
+        )),
+        (status = 404, description = "Not Found, the series does not exist or has no season at that index", body = AppError, example = json!(AppError::NotFound.to_string())),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
+    ),
+    params(
+        ("id", description = "Unique ObjectId of Series"),
+        ("index", description = "1-based position of the season")
+    ),
+    request_body = Episode,
+    tag = "Series"
+)]
+#[post("/{id}/seasons/{index}/episodes")]
+pub async fn add_series_season_episode(
+    db: Data<Database>,
+    path: Path<(String, usize)>,
+    request: Json<Episode>,
+) -> Result<HttpResponse, AppError> {
+    request.validate()?;
+    let (id, index) = path.into_inner();
+    match db
+        .add_season_episode(id.as_str(), index, request.into_inner())
+        .await
+    {
+        Ok(res) => Ok(HttpResponse::Created().json(res)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Replace the whole season list of a series
+///
+/// `numberOfSeasons` is set to the length of the new list.
+#[utoipa::path(
+    path = "/api/v1/series/{id}/seasons",
+    responses(
+        (status = 200, description = "Replaced", body = String, content_type = "application/json", example = json!({"message": "Seasons of series with id: '1234' were successfully replaced", "numberOfSeasons": 2})),
+        (status = 400, description = "Cannot parse ObjectId or Validation Error", body = AppError, examples(
+            ("CannotParseObjId" = (value = json!(AppError::CannotParseObjId.to_string()))),
+            ("ValidationError" = (value = json!([{"field": "seasonList[0].episodeList", "messages": ["The season has to have at least one episode"]}])))
+        )),
+        (status = 404, description = "Not Found", body = AppError, example = json!(AppError::NotFound.to_string())),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
+    ),
+    params(
+        ("id", description = "Unique ObjectId of Series")
+    ),
+    request_body = [crate::models::series::Season],
+    tag = "Series"
+)]
+#[put("/{id}/seasons")]
+pub async fn replace_series_seasons(
+    db: Data<Database>,
+    path: Path<String>,
+    request: Json<SeasonListRequest>,
+) -> Result<HttpResponse, AppError> {
+    request.validate()?;
+    let id = path.into_inner();
+    match db
+        .replace_season_list(id.as_str(), request.into_inner().season_list)
+        .await
+    {
+        Ok(res) => Ok(HttpResponse::Ok().json(res)),
+        Err(err) => Err(err),
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct EpisodeParams {
+    /// 1-based page number, the first page when missing or 0
+    page: Option<u32>,
+    size: Option<u32>,
+}
+
+/// Find all episodes of a series as a flat list
+#[utoipa::path(
+    path = "/api/v1/series/findById/{id}/episodes/all",
+    responses(
+        (status = 200, description = "Every episode across seasons in order, with 1-based seasonIndex/episodeIndex", body = [FlatEpisode]),
+        (status = 204, description = "Empty List", body = AppError, example = json!(AppError::Empty.to_string())),
+        (status = 400, description = "Cannot parse ObjectId", body = AppError, example = json!(AppError::CannotParseObjId.to_string())),
+        (status = 404, description = "Not Found", body = AppError, example = json!(AppError::NotFound.to_string())),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
+    ),
+    params(
+        ("id", description = "Unique ObjectId of Series"),
+        EpisodeParams
+    ),
+    tag = "Series"
+)]
+#[get("/findById/{id}/episodes/all")]
+pub async fn get_series_flattened_episodes(
+    db: Data<Database>,
+    path: Path<String>,
+    params: Query<EpisodeParams>,
+) -> Result<HttpResponse, AppError> {
+    let id = path.into_inner();
+    match db
+        .find_flattened_episodes(id.as_str(), params.page, params.size)
+        .await
+    {
+        Ok(res) => Ok(HttpResponse::Ok().json(res)),
         Err(err) => Err(err),
     }
 }
@@ -100,19 +339,26 @@ pub async fn get_series_by_imdb_id(
 ) -> Result<HttpResponse, AppError> {
     let imdb_id = path.into_inner();
     match db.find_series_by_imdb_id(imdb_id.as_str()).await {
-        Ok(series) => Ok(HttpResponse::Ok().json(series)),
+        Ok(series) => Ok(HttpResponse::Ok().json(to_series_doc(series)?)),
         Err(err) => Err(err),
     }
 }
 
+fn to_series_doc(series: Series) -> Result<SeriesDoc, AppError> {
+    SeriesDoc::try_from(series).map_err(|err| {
+        error!("Error converting Series to SeriesDoc [{}]", err);
+        AppError::InternalServerError
+    })
+}
+
 /// Create new series
 #[utoipa::path(
     path = "/api/v1/series/new",
     responses(
-        (status = 201, description = "Created", body = String, content_type = "application/json", example = json!(HashMap::from([("message".to_string(), "Series was successfully created. (id: '1234')".to_string())]))),
+        (status = 201, description = "Created", body = String, content_type = "application/json", example = json!({"id": "1234", "message": "Series was successfully created. (id: '1234')"})),
         (status = 400, description = "Already Exists or Validation Error", body = AppError, examples(
             ("AlreadyExists" = (value = json!(AppError::AlreadyExists.to_string()))),
-            ("ValidationError" = (value = json!(AppError::ValidationAppError("title: The series title cannot be empty".to_string()).to_string())))
+            ("ValidationError" = (value = json!([{"field": "title", "messages": ["The series title cannot be empty"]}])))
         )),
         (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
     ),
@@ -131,7 +377,7 @@ pub async fn create_series(
                 imdb_id: request.imdb_id.clone(),
                 title: request.title.clone(),
                 overview: request.overview.clone(),
-                number_of_seasons: request.number_of_seasons.clone(),
+                number_of_seasons: request.number_of_seasons,
                 creator: request.creator.clone(),
                 release_date: request.release_date.clone(),
                 trailer_link: request.trailer_link.clone(),
@@ -140,7 +386,7 @@ pub async fn create_series(
                 poster: request.poster.clone(),
                 backdrop: request.backdrop.clone(),
             })
-            .expect("Error converting request to Series"),
+            .map_err(internal_error("Error converting request to Series"))?,
         )
         .await
     {
@@ -155,7 +401,7 @@ pub async fn create_series(
     responses(
         (status = 200, description = "Deleted", body = String, content_type = "application/json", example = json!(HashMap::from([("message".to_string(), "Series with id: '1234' was successfully deleted".to_string())]))),
         (status = 400, description = "Cannot parse ObjectId", body = AppError, example = json!(AppError::CannotParseObjId.to_string())),
-        (status = 404, description = "Not Exists", body = AppError, example = json!(AppError::NotExists.to_string())),
+        (status = 404, description = "Not Found", body = AppError, example = json!(AppError::NotFound.to_string())),
         (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
     ),
     params(
@@ -182,7 +428,7 @@ pub async fn delete_series_by_id(
         (status = 200, description = "Updated", body = String, content_type = "application/json", example = json!(HashMap::from([("message".to_string(), "Series with id: '1234' was successfully updated".to_string())]))),
         (status = 404, description = "Not Exists", body = AppError, example = json!(AppError::NotExists.to_string())),
         (status = 400, description = "Validation Error, Cannot parse ObjectId or ImdbId in use", body = AppError, examples(
-            ("ValidationError" = (value = json!(AppError::ValidationAppError("title: The series title cannot be empty".to_string()).to_string()))),
+            ("ValidationError" = (value = json!([{"field": "title", "messages": ["The series title cannot be empty"]}]))),
             ("Cannot parse ObjectId" = (value = json!(AppError::CannotParseObjId.to_string()))),
             ("ImdbId in use" = (value = json!(AppError::ImdbIdInUse.to_string())))
         )),
@@ -255,3 +501,16 @@ pub async fn patch_series_by_id(
         Err(err) => Err(err),
     }
 }
+
+/// List patchable fields of Series
+#[utoipa::path(
+    path = "/api/v1/series/patchableFields",
+    responses(
+        (status = 200, description = "List the fields accepted by the Series patch endpoint and their expected types", body = [PatchableField]),
+    ),
+    tag = "Series"
+)]
+#[get("/patchableFields")]
+pub async fn get_series_patchable_fields() -> HttpResponse {
+    HttpResponse::Ok().json(SERIES_PATCHABLE_FIELDS)
+}
@@ -0,0 +1,25 @@
+use actix_web::{get, web::Data, HttpResponse};
+
+use crate::{
+    error::AppError,
+    services::{admin_repo::AdminRepository, db::Database},
+};
+
+/// Find global stats
+///
+/// Totals of movies, series and reviews plus the mean rating of every review, for dashboards.
+#[utoipa::path(
+    path = "/api/v1/stats",
+    responses(
+        (status = 200, description = "Totals of every collection and the overall average review rating", body = GlobalStats),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
+    ),
+    tag = "Stats"
+)]
+#[get("/stats")]
+pub async fn get_global_stats(db: Data<Database>) -> Result<HttpResponse, AppError> {
+    match db.global_stats().await {
+        Ok(stats) => Ok(HttpResponse::Ok().json(stats)),
+        Err(err) => Err(err),
+    }
+}
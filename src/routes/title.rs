@@ -0,0 +1,171 @@
+use actix_web::{
+    get, post,
+    web::{Data, Json, Path, Query},
+    HttpResponse,
+};
+use serde::Deserialize;
+use utoipa::IntoParams;
+use validator::Validate;
+
+use crate::{
+    error::AppError,
+    models::title::MissingTitlesRequest,
+    services::{db::Database, title_repo::TitleRepository},
+};
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct SearchParams {
+    /// Term searched (case-insensitive) in the title of movies and series
+    query: String,
+    /// 1-based page number, the first page when missing or 0
+    page: Option<u32>,
+    size: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct PopularParams {
+    limit: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct MostReviewedParams {
+    /// Genre the titles must include, e.g. `Drama`
+    genre: String,
+    limit: Option<u32>,
+}
+
+/// Find popular titles
+///
+/// Movies and series ranked by a Bayesian average of their reviews:
+/// `score = (v / (v + m)) * R + (m / (v + m)) * C`, with `v` the review count, `R` the title's
+/// average rating, `C` the average rating of all reviews and `m = 5`.
+#[utoipa::path(
+    path = "/api/v1/titles/popular",
+    responses(
+        (status = 200, description = "List the most popular movies and series", body = [PopularTitle]),
+        (status = 204, description = "Empty List", body = AppError, example = json!(AppError::Empty.to_string())),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string())),
+    ),
+    params(
+        PopularParams
+    ),
+    tag = "Titles"
+)]
+#[get("/popular")]
+pub async fn get_popular_titles(
+    db: Data<Database>,
+    params: Query<PopularParams>,
+) -> Result<HttpResponse, AppError> {
+    match db.find_popular_titles(params.limit).await {
+        Ok(res) => Ok(HttpResponse::Ok().json(res)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Find the most reviewed titles of a genre
+#[utoipa::path(
+    path = "/api/v1/titles/mostReviewed",
+    responses(
+        (status = 200, description = "Movies and series of the genre sorted by review count (descending), each tagged with its type", body = Object, content_type = "application/json", example = json!({"genre": "Drama", "titles": [{"type": "series", "imdbId": "tt0903747", "title": "Breaking Bad", "poster": "https://image.tmdb.org/t/p/original/ggFHVNu6YYI5L9pCfOacjizRGt.jpg", "reviewCount": 45}]})),
+        (status = 204, description = "Empty List", body = AppError, example = json!(AppError::Empty.to_string())),
+        (status = 400, description = "Empty genre", body = AppError, example = json!(AppError::ValidationAppError("genre: The genre cannot be empty".to_string()).to_string())),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string())),
+    ),
+    params(
+        MostReviewedParams
+    ),
+    tag = "Titles"
+)]
+#[get("/mostReviewed")]
+pub async fn get_most_reviewed_titles(
+    db: Data<Database>,
+    params: Query<MostReviewedParams>,
+) -> Result<HttpResponse, AppError> {
+    match db
+        .find_most_reviewed_titles(params.genre.as_str(), params.limit)
+        .await
+    {
+        Ok(res) => Ok(HttpResponse::Ok().json(res)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Find missing imdbIds
+#[utoipa::path(
+    path = "/api/v1/titles/missing",
+    responses(
+        (status = 200, description = "List the requested imdbIds that are neither a movie nor a series", body = Object, content_type = "application/json", example = json!({"missingCount": 1, "missingImdbIds": ["tt0071562"]})),
+        (status = 400, description = "ValidationError", body = AppError, example = json!([{"field": "imdbIds", "messages": ["These imdbIds do not match the format 'tt0000': 12345"]}])),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string())),
+    ),
+    request_body = MissingTitlesRequest,
+    tag = "Titles"
+)]
+#[post("/missing")]
+pub async fn post_missing_titles(
+    db: Data<Database>,
+    request: Json<MissingTitlesRequest>,
+) -> Result<HttpResponse, AppError> {
+    request.validate()?;
+    match db
+        .find_missing_imdb_ids(request.into_inner().imdb_ids)
+        .await
+    {
+        Ok(res) => Ok(HttpResponse::Ok().json(res)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Search movies and series
+#[utoipa::path(
+    path = "/api/v1/search",
+    responses(
+        (status = 200, description = "Movies and series whose title matches the query, sorted by title and paginated, each tagged with its type", body = Object, content_type = "application/json", example = json!({"results": [{"type": "series", "imdbId": "tt0903747", "title": "Breaking Bad", "releaseDate": "2008-01-20", "poster": "https://image.tmdb.org/t/p/original/ggFHVNu6YYI5L9pCfOacjizRGt.jpg"}], "currentPage": 1, "pageSize": 10, "totalItems": 1, "totalPages": 1, "hasPrev": false, "hasNext": false})),
+        (status = 204, description = "Empty List", body = AppError, example = json!(AppError::Empty.to_string())),
+        (status = 400, description = "Empty query", body = AppError, example = json!(AppError::ValidationAppError("query: The search query cannot be empty".to_string()).to_string())),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string())),
+    ),
+    params(
+        SearchParams
+    ),
+    tag = "Search"
+)]
+#[get("/search")]
+pub async fn search_titles(
+    db: Data<Database>,
+    params: Query<SearchParams>,
+) -> Result<HttpResponse, AppError> {
+    match db
+        .search_all(params.query.as_str(), params.page, params.size)
+        .await
+    {
+        Ok(res) => Ok(HttpResponse::Ok().json(res)),
+        Err(err) => Err(err),
+    }
+}
+
+/// Find a movie or series by imdbId
+#[utoipa::path(
+    path = "/api/v1/findByImdbId/{imdbId}",
+    responses(
+        (status = 200, description = "Fetch the Movie or Series with that imdbId, tagged with its type", body = Object, content_type = "application/json", example = json!({"type": "series", "_id": "6640ce2b8d0a4a4e3c5a7d31", "imdbId": "tt0903747", "title": "Breaking Bad"})),
+        (status = 400, description = "Wrong ImdbId passed", body = AppError, example = json!(AppError::WrongImdbId.to_string())),
+        (status = 404, description = "Not Found", body = AppError, example = json!(AppError::NotFound.to_string())),
+        (status = 500, description = "Internal Server Error", body = AppError, example = json!(AppError::InternalServerError.to_string()))
+    ),
+    params(
+        ("imdbId", description = "Unique imdbId of Movie or Series")
+    ),
+    tag = "Search"
+)]
+#[get("/findByImdbId/{imdbId}")]
+pub async fn get_title_by_imdb_id(
+    db: Data<Database>,
+    path: Path<String>,
+) -> Result<HttpResponse, AppError> {
+    let imdb_id = path.into_inner();
+    match db.find_title_by_imdb_id(imdb_id.as_str()).await {
+        Ok(res) => Ok(HttpResponse::Ok().json(res)),
+        Err(err) => Err(err),
+    }
+}
@@ -0,0 +1,805 @@
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use futures_util::TryStreamExt;
+use log::{error, info, warn};
+use mongodb::{
+    bson::{doc, from_document, oid::ObjectId, Bson, Document},
+    options::FindOptions,
+    ClientSession, Collection,
+};
+use serde_json::{Map, Value};
+
+use crate::{
+    error::{internal_error, AppError},
+    models::{
+        admin::{DuplicateGroup, GenreStats, GlobalStats, IncompleteMedia},
+        validation::{IMAGE_URL_PATTERN, RE_REMOTE_IMAGES},
+    },
+};
+
+use super::{db::Database, review_repo::to_average_rating};
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait AdminRepository {
+    async fn find_raw_movie_by_id(&self, id: &str) -> Result<Map<String, Value>, AppError>;
+    async fn find_incomplete_media(&self) -> Result<Map<String, Value>, AppError>;
+    async fn find_duplicate_movies(&self) -> Result<Vec<DuplicateGroup>, AppError>;
+    async fn merge_movies(
+        &self,
+        keep_id: &str,
+        remove_id: &str,
+    ) -> Result<Map<String, Value>, AppError>;
+    async fn find_genre_stats(&self) -> Result<Vec<GenreStats>, AppError>;
+    async fn global_stats(&self) -> Result<GlobalStats, AppError>;
+}
+
+#[async_trait]
+impl AdminRepository for Database {
+    async fn find_raw_movie_by_id(&self, id: &str) -> Result<Map<String, Value>, AppError> {
+        info!("GET admin /movies/raw with id: '{}' executed", id);
+        let obj_id = ObjectId::from_str(id)?;
+        let raw_doc: Document = match self
+            .movies
+            .clone_with_type::<Document>()
+            .find_one(doc! {"_id": obj_id}, None)
+            .await
+        {
+            Ok(Some(raw_doc)) => raw_doc,
+            Ok(None) => {
+                warn!(
+                    "Warn in admin /movies/raw with id: '{}' [{}]",
+                    id,
+                    AppError::NotFound
+                );
+                return Err(AppError::NotFound);
+            }
+            Err(_) => {
+                error!(
+                    "Error in admin /movies/raw with id: '{}' [{}]",
+                    id,
+                    AppError::InternalServerError
+                );
+                return Err(AppError::InternalServerError);
+            }
+        };
+        Ok(document_to_json(raw_doc))
+    }
+
+    async fn find_incomplete_media(&self) -> Result<Map<String, Value>, AppError> {
+        info!("GET admin /incompleteMedia executed");
+        let mut media_list =
+            find_incomplete_in(self.movies.clone_with_type::<Document>(), "movie").await?;
+        media_list
+            .extend(find_incomplete_in(self.series.clone_with_type::<Document>(), "series").await?);
+
+        let mut result_map: Map<String, Value> = Map::new();
+        result_map.insert(
+            "totalItems".to_string(),
+            serde_json::to_value(media_list.len()).unwrap(),
+        );
+        result_map.insert(
+            "media".to_string(),
+            serde_json::to_value(media_list).unwrap(),
+        );
+        Ok(result_map)
+    }
+
+    async fn find_duplicate_movies(&self) -> Result<Vec<DuplicateGroup>, AppError> {
+        info!("GET admin /duplicates executed");
+        let cursor = match self
+            .movies
+            .clone_with_type::<Document>()
+            .aggregate(duplicates_pipeline(), None)
+            .await
+        {
+            Ok(cursor) => cursor,
+            Err(_) => {
+                error!(
+                    "Error in admin /duplicates aggregating movies [{}]",
+                    AppError::InternalServerError
+                );
+                return Err(AppError::InternalServerError);
+            }
+        };
+        let group_docs: Vec<Document> = match cursor.try_collect().await {
+            Ok(group_docs) => group_docs,
+            Err(_) => {
+                error!(
+                    "Error in admin /duplicates collecting groups [{}]",
+                    AppError::InternalServerError
+                );
+                return Err(AppError::InternalServerError);
+            }
+        };
+        Ok(group_docs.iter().filter_map(to_duplicate_group).collect())
+    }
+
+    async fn merge_movies(
+        &self,
+        keep_id: &str,
+        remove_id: &str,
+    ) -> Result<Map<String, Value>, AppError> {
+        info!(
+            "POST admin /movies/merge keeping: '{}' removing: '{}' executed",
+            keep_id, remove_id
+        );
+        let keep_oid = ObjectId::from_str(keep_id)?;
+        let remove_oid = ObjectId::from_str(remove_id)?;
+        if keep_oid == remove_oid {
+            let err = AppError::ValidationAppError(
+                "keepId and removeId must be different movies".to_string(),
+            );
+            warn!("Warn in admin /movies/merge [{}]", err);
+            return Err(err);
+        }
+
+        let mut session = match self.client.start_session(None).await {
+            Ok(session) => session,
+            Err(_) => {
+                error!(
+                    "Error in admin /movies/merge starting session [{}]",
+                    AppError::InternalServerError
+                );
+                return Err(AppError::InternalServerError);
+            }
+        };
+        if session.start_transaction(None).await.is_err() {
+            error!(
+                "Error in admin /movies/merge starting transaction [{}]",
+                AppError::InternalServerError
+            );
+            return Err(AppError::InternalServerError);
+        }
+
+        let review_count = match self
+            .merge_in_session(&mut session, keep_oid, remove_oid)
+            .await
+        {
+            Ok(review_count) => review_count,
+            Err(err) => {
+                let _ = session.abort_transaction().await;
+                return Err(err);
+            }
+        };
+        if session.commit_transaction().await.is_err() {
+            error!(
+                "Error in admin /movies/merge committing transaction [{}]",
+                AppError::InternalServerError
+            );
+            return Err(AppError::InternalServerError);
+        }
+        self.movie_cache
+            .purge(&[keep_oid.to_hex(), remove_oid.to_hex()]);
+
+        let mut result_map: Map<String, Value> = Map::new();
+        result_map.insert("id".to_string(), Value::String(keep_oid.to_hex()));
+        result_map.insert(
+            "reviewCount".to_string(),
+            serde_json::to_value(review_count).unwrap(),
+        );
+        Ok(result_map)
+    }
+
+    async fn find_genre_stats(&self) -> Result<Vec<GenreStats>, AppError> {
+        info!("GET admin /genreStats executed");
+        let pipeline = genre_stats_pipeline(self.reviews.name());
+        let cursor = match self
+            .movies
+            .clone_with_type::<Document>()
+            .aggregate(pipeline, None)
+            .await
+        {
+            Ok(cursor) => cursor,
+            Err(_) => {
+                error!(
+                    "Error in admin /genreStats aggregating movies [{}]",
+                    AppError::InternalServerError
+                );
+                return Err(AppError::InternalServerError);
+            }
+        };
+        let stats_docs: Vec<Document> = match cursor.try_collect().await {
+            Ok(stats_docs) => stats_docs,
+            Err(_) => {
+                error!(
+                    "Error in admin /genreStats collecting stats [{}]",
+                    AppError::InternalServerError
+                );
+                return Err(AppError::InternalServerError);
+            }
+        };
+        Ok(stats_docs.into_iter().filter_map(to_genre_stats).collect())
+    }
+
+    async fn global_stats(&self) -> Result<GlobalStats, AppError> {
+        info!("GET /stats executed");
+        let total_movies = self
+            .movies
+            .count_documents(None, None)
+            .await
+            .map_err(internal_error("Error in /stats counting movies"))?;
+        let total_series = self
+            .series
+            .count_documents(None, None)
+            .await
+            .map_err(internal_error("Error in /stats counting series"))?;
+        let total_reviews = self
+            .reviews
+            .count_documents(None, None)
+            .await
+            .map_err(internal_error("Error in /stats counting reviews"))?;
+        let mut cursor = self
+            .reviews
+            .aggregate(global_rating_pipeline(), None)
+            .await
+            .map_err(internal_error("Error in /stats averaging review ratings"))?;
+        let group_doc = cursor
+            .try_next()
+            .await
+            .map_err(internal_error("Error in /stats reading the average rating"))?;
+        Ok(GlobalStats {
+            total_movies,
+            total_series,
+            total_reviews,
+            average_rating: to_average_rating(group_doc),
+        })
+    }
+}
+
+impl Database {
+    /// Moves the review ids of `remove_oid` onto `keep_oid`, points those reviews' `parentId` at
+    /// `keep_oid` and deletes `remove_oid`, returning the merged review count. Runs inside the
+    /// caller's transaction.
+    async fn merge_in_session(
+        &self,
+        session: &mut ClientSession,
+        keep_oid: ObjectId,
+        remove_oid: ObjectId,
+    ) -> Result<usize, AppError> {
+        let mut movies = Vec::with_capacity(2);
+        for oid in [keep_oid, remove_oid] {
+            match self
+                .movies
+                .find_one_with_session(doc! {"_id": oid}, None, session)
+                .await
+            {
+                Ok(Some(movie)) => movies.push(movie),
+                Ok(None) => {
+                    warn!(
+                        "Warn in admin /movies/merge with id: '{}' [{}]",
+                        oid,
+                        AppError::NotFound
+                    );
+                    return Err(AppError::NotFound);
+                }
+                Err(_) => {
+                    error!(
+                        "Error in admin /movies/merge with id: '{}' [{}]",
+                        oid,
+                        AppError::InternalServerError
+                    );
+                    return Err(AppError::InternalServerError);
+                }
+            }
+        }
+        let (kept, removed) = (&movies[0], &movies[1]);
+
+        let update = merge_review_ids_update(&removed.review_ids);
+        if self
+            .movies
+            .update_one_with_session(doc! {"_id": keep_oid}, update, None, session)
+            .await
+            .is_err()
+        {
+            error!(
+                "Error in admin /movies/merge updating id: '{}' [{}]",
+                keep_oid,
+                AppError::InternalServerError
+            );
+            return Err(AppError::InternalServerError);
+        }
+        if !removed.review_ids.is_empty() {
+            self.reviews
+                .update_many_with_session(
+                    doc! {"_id": {"$in": removed.review_ids.clone()}},
+                    reparent_reviews_update(keep_oid),
+                    None,
+                    session,
+                )
+                .await
+                .map_err(internal_error(format!(
+                    "Error in admin /movies/merge moving the reviews to id: '{}'",
+                    keep_oid
+                )))?;
+        }
+        if self
+            .movies
+            .delete_one_with_session(doc! {"_id": remove_oid}, None, session)
+            .await
+            .is_err()
+        {
+            error!(
+                "Error in admin /movies/merge deleting id: '{}' [{}]",
+                remove_oid,
+                AppError::InternalServerError
+            );
+            return Err(AppError::InternalServerError);
+        }
+        Ok(merged_review_count(&kept.review_ids, &removed.review_ids))
+    }
+}
+
+/// Single group over every review; no group at all when there are no reviews.
+fn global_rating_pipeline() -> Vec<Document> {
+    vec![doc! { "$group": { "_id": null, "avg": { "$avg": "$rating" } } }]
+}
+
+/// One row per genre: movies tagged with it, their reviews and the mean rating of those
+/// reviews (0 when none). Sorted by movie count, then genre name.
+fn genre_stats_pipeline(reviews_coll: &str) -> Vec<Document> {
+    vec![
+        doc! { "$unwind": "$genres" },
+        doc! { "$lookup": {
+            "from": reviews_coll,
+            "localField": "reviewIds",
+            "foreignField": "_id",
+            "as": "reviews",
+        } },
+        doc! { "$group": {
+            "_id": "$genres",
+            "movieCount": { "$sum": 1 },
+            "totalReviews": { "$sum": { "$size": "$reviews" } },
+            "ratingSum": { "$sum": { "$sum": "$reviews.rating" } },
+        } },
+        doc! { "$project": {
+            "_id": 0,
+            "genre": "$_id",
+            "movieCount": 1,
+            "totalReviews": 1,
+            "avg": { "$cond": [
+                { "$gt": ["$totalReviews", 0] },
+                { "$divide": ["$ratingSum", "$totalReviews"] },
+                null,
+            ] },
+        } },
+        doc! { "$sort": { "movieCount": -1, "genre": 1 } },
+    ]
+}
+
+/// Rounds the `avg` of the `genre_stats_pipeline` output like the other averages, so a genre
+/// without reviews gets a `null` averageRating.
+fn to_genre_stats(stats_doc: Document) -> Option<GenreStats> {
+    let average_rating = to_average_rating(Some(stats_doc.clone()));
+    let stats = from_document::<GenreStats>(stats_doc).ok()?;
+    Some(GenreStats {
+        average_rating,
+        ..stats
+    })
+}
+
+fn merge_review_ids_update(review_ids: &[ObjectId]) -> Document {
+    doc! { "$addToSet": { "reviewIds": { "$each": review_ids } } }
+}
+
+/// The moved reviews now belong to the kept movie, so `delete_review` pulls them from it.
+fn reparent_reviews_update(keep_oid: ObjectId) -> Document {
+    doc! { "$set": { "parentId": keep_oid, "parentType": "movie" } }
+}
+
+/// Mirrors `$addToSet`: ids already on the kept movie are not counted twice.
+fn merged_review_count(kept: &[ObjectId], removed: &[ObjectId]) -> usize {
+    let mut merged = kept.to_vec();
+    for review_id in removed {
+        if !merged.contains(review_id) {
+            merged.push(*review_id);
+        }
+    }
+    merged.len()
+}
+
+/// Groups movies by lowercased, trimmed title plus release year (`"casino|1995"`) and keeps
+/// only the keys shared by more than one movie.
+fn duplicates_pipeline() -> Vec<Document> {
+    vec![
+        doc! { "$group": {
+            "_id": { "$concat": [
+                { "$toLower": { "$trim": { "input": "$title" } } },
+                "|",
+                { "$substrCP": ["$releaseDate", 0, 4] },
+            ] },
+            "ids": { "$push": "$_id" },
+        } },
+        doc! { "$match": { "ids.1": { "$exists": true } } },
+        doc! { "$sort": { "_id": 1 } },
+    ]
+}
+
+fn to_duplicate_group(group_doc: &Document) -> Option<DuplicateGroup> {
+    let ids: Vec<String> = group_doc
+        .get_array("ids")
+        .ok()?
+        .iter()
+        .filter_map(|id| id.as_object_id().map(|oid| oid.to_hex()))
+        .collect();
+    if ids.len() < 2 {
+        return None;
+    }
+    Some(DuplicateGroup {
+        key: group_doc.get_str("_id").ok()?.to_string(),
+        ids,
+    })
+}
+
+/// Candidates are narrowed in the database (empty or non image-like values), then re-checked
+/// here with the same regex the request validators use.
+async fn find_incomplete_in(
+    collection: Collection<Document>,
+    media_type: &str,
+) -> Result<Vec<IncompleteMedia>, AppError> {
+    let not_image = doc! { "$not": { "$regex": IMAGE_URL_PATTERN } };
+    let filter = doc! {
+        "$or": [
+            { "poster": "" },
+            { "backdrop": "" },
+            { "poster": not_image.clone() },
+            { "backdrop": not_image },
+        ]
+    };
+    let options = FindOptions::builder()
+        .projection(doc! { "imdbId": 1, "title": 1, "poster": 1, "backdrop": 1 })
+        .build();
+    let cursor = match collection.find(filter, options).await {
+        Ok(cursor) => cursor,
+        Err(_) => {
+            error!(
+                "Error in admin /incompleteMedia finding {} [{}]",
+                media_type,
+                AppError::InternalServerError
+            );
+            return Err(AppError::InternalServerError);
+        }
+    };
+    let docs: Vec<Document> = match cursor.try_collect().await {
+        Ok(docs) => docs,
+        Err(_) => {
+            error!(
+                "Error in admin /incompleteMedia collecting {} [{}]",
+                media_type,
+                AppError::InternalServerError
+            );
+            return Err(AppError::InternalServerError);
+        }
+    };
+    Ok(docs
+        .iter()
+        .filter_map(|media_doc| to_incomplete_media(media_doc, media_type))
+        .collect())
+}
+
+fn missing_media_fields(media_doc: &Document) -> Vec<String> {
+    ["poster", "backdrop"]
+        .into_iter()
+        .filter(|field| !RE_REMOTE_IMAGES.is_match(media_doc.get_str(field).unwrap_or_default()))
+        .map(|field| field.to_string())
+        .collect()
+}
+
+fn to_incomplete_media(media_doc: &Document, media_type: &str) -> Option<IncompleteMedia> {
+    let missing_fields = missing_media_fields(media_doc);
+    if missing_fields.is_empty() {
+        return None;
+    }
+    Some(IncompleteMedia {
+        media_type: media_type.to_string(),
+        _id: media_doc
+            .get_object_id("_id")
+            .map(|oid| oid.to_hex())
+            .unwrap_or_default(),
+        imdb_id: media_doc.get_str("imdbId").unwrap_or_default().to_string(),
+        title: media_doc.get_str("title").unwrap_or_default().to_string(),
+        missing_fields,
+    })
+}
+
+/// Converts a stored BSON document into JSON (relaxed Extended JSON), keeping every field as is.
+fn document_to_json(raw_doc: Document) -> Map<String, Value> {
+    match Bson::Document(raw_doc).into_relaxed_extjson() {
+        Value::Object(map) => map,
+        _ => Map::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Unit Tests
+
+    #[test]
+    fn test_document_to_json_keeps_internal_fields() {
+        let oid = ObjectId::new();
+        let review_oid = ObjectId::new();
+        let raw_doc = doc! {
+            "_id": oid,
+            "imdbId": "tt12345",
+            "title": "Casino",
+            "reviewIds": [review_oid],
+            "legacyField": "not in any DTO",
+        };
+
+        let map = document_to_json(raw_doc);
+        assert_eq!(map["_id"]["$oid"], oid.to_hex());
+        assert_eq!(map["reviewIds"][0]["$oid"], review_oid.to_hex());
+        assert_eq!(map["legacyField"], "not in any DTO");
+    }
+
+    #[test]
+    fn test_to_incomplete_media_complete_and_incomplete_movie() {
+        let complete = doc! {
+            "_id": ObjectId::new(),
+            "imdbId": "tt12345",
+            "title": "Casino",
+            "poster": "https://moviedb.com/casino/poster.jpg",
+            "backdrop": "https://moviedb.com/casino/backdrop.webp",
+        };
+        let incomplete = doc! {
+            "_id": ObjectId::new(),
+            "imdbId": "tt54321",
+            "title": "Uno de los nuestros",
+            "poster": "",
+            "backdrop": "https://moviedb.com/goodfellas/backdrop.txt",
+        };
+
+        assert!(to_incomplete_media(&complete, "movie").is_none());
+
+        let media = to_incomplete_media(&incomplete, "movie").unwrap();
+        assert_eq!(media.media_type, "movie");
+        assert_eq!(media.imdb_id, "tt54321");
+        assert_eq!(media.missing_fields, vec!["poster", "backdrop"]);
+    }
+
+    #[test]
+    fn test_to_duplicate_group_keeps_only_shared_keys() {
+        let (first, second, unique) = (ObjectId::new(), ObjectId::new(), ObjectId::new());
+        let group_docs = [
+            doc! { "_id": "casino|1995", "ids": [first, second] },
+            doc! { "_id": "uno de los nuestros|1990", "ids": [unique] },
+        ];
+
+        let groups: Vec<DuplicateGroup> =
+            group_docs.iter().filter_map(to_duplicate_group).collect();
+        assert_eq!(
+            groups,
+            vec![DuplicateGroup {
+                key: "casino|1995".to_string(),
+                ids: vec![first.to_hex(), second.to_hex()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_merge_review_ids_update_uses_add_to_set_each() {
+        let review_ids = vec![ObjectId::new(), ObjectId::new()];
+        let update = merge_review_ids_update(&review_ids);
+        let each = update
+            .get_document("$addToSet")
+            .unwrap()
+            .get_document("reviewIds")
+            .unwrap()
+            .get_array("$each")
+            .unwrap();
+        assert_eq!(each.len(), 2);
+        assert_eq!(each[0].as_object_id(), Some(review_ids[0]));
+    }
+
+    #[test]
+    fn test_reparent_reviews_update_points_at_kept_movie() {
+        let keep_oid = ObjectId::new();
+        let set = reparent_reviews_update(keep_oid)
+            .get_document("$set")
+            .unwrap()
+            .clone();
+        assert_eq!(set.get_object_id("parentId").unwrap(), keep_oid);
+        assert_eq!(set.get_str("parentType").unwrap(), "movie");
+    }
+
+    #[test]
+    fn test_merged_review_count_skips_shared_reviews() {
+        let shared = ObjectId::new();
+        let kept = vec![shared, ObjectId::new()];
+        let removed = vec![shared, ObjectId::new(), ObjectId::new()];
+        assert_eq!(merged_review_count(&kept, &removed), 4);
+        assert_eq!(merged_review_count(&kept, &[]), 2);
+    }
+
+    #[actix_web::test]
+    async fn test_merge_movies_ok() {
+        let mut mock = MockAdminRepository::new();
+        let keep_oid = ObjectId::new();
+
+        mock.expect_merge_movies().returning(move |keep, _| {
+            let mut result_map = Map::new();
+            result_map.insert("id".to_string(), Value::String(keep.to_string()));
+            result_map.insert("reviewCount".to_string(), Value::from(3));
+            Ok(result_map)
+        });
+
+        let result = mock
+            .merge_movies(
+                keep_oid.to_hex().as_str(),
+                ObjectId::new().to_hex().as_str(),
+            )
+            .await;
+        assert!(result.is_ok_and(|map| map["id"] == keep_oid.to_hex() && map["reviewCount"] == 3));
+    }
+
+    #[actix_web::test]
+    async fn test_merge_movies_removed_not_found() {
+        let mut mock = MockAdminRepository::new();
+
+        mock.expect_merge_movies()
+            .returning(|_, _| Err(AppError::NotFound));
+
+        let result = mock
+            .merge_movies(
+                ObjectId::new().to_hex().as_str(),
+                ObjectId::new().to_hex().as_str(),
+            )
+            .await;
+        assert!(result.is_err_and(|err| err == AppError::NotFound));
+    }
+
+    #[test]
+    fn test_genre_stats_pipeline_groups_unwound_genres() {
+        let pipeline = genre_stats_pipeline("reviews");
+        assert_eq!(pipeline[0], doc! { "$unwind": "$genres" });
+        assert_eq!(
+            pipeline[1]
+                .get_document("$lookup")
+                .unwrap()
+                .get_str("from")
+                .unwrap(),
+            "reviews"
+        );
+        assert_eq!(
+            pipeline[2]
+                .get_document("$group")
+                .unwrap()
+                .get_str("_id")
+                .unwrap(),
+            "$genres"
+        );
+        let sort = pipeline[4].get_document("$sort").unwrap();
+        assert_eq!(sort.get_i32("movieCount").unwrap(), -1);
+
+        // Shape produced by the $project stage
+        let stats = to_genre_stats(doc! {
+            "genre": "Drama",
+            "movieCount": 2,
+            "totalReviews": 3,
+            "avg": 3.8333,
+        })
+        .unwrap();
+        assert_eq!(stats.movie_count, 2);
+        assert_eq!(stats.total_reviews, 3);
+        assert_eq!(stats.average_rating, Some(3.83));
+
+        let unreviewed = to_genre_stats(doc! {
+            "genre": "Crimen",
+            "movieCount": 1,
+            "totalReviews": 0,
+            "avg": null,
+        })
+        .unwrap();
+        assert_eq!(unreviewed.average_rating, None);
+        assert!(serde_json::to_value(unreviewed).unwrap()["averageRating"].is_null());
+    }
+
+    #[actix_web::test]
+    async fn test_find_genre_stats_two_genres() {
+        let mut mock = MockAdminRepository::new();
+
+        mock.expect_find_genre_stats().returning(|| {
+            Ok(vec![
+                GenreStats {
+                    genre: "Drama".to_string(),
+                    movie_count: 2,
+                    average_rating: Some(4.5),
+                    total_reviews: 4,
+                },
+                GenreStats {
+                    genre: "Crimen".to_string(),
+                    movie_count: 1,
+                    average_rating: None,
+                    total_reviews: 0,
+                },
+            ])
+        });
+
+        let stats = mock.find_genre_stats().await.unwrap();
+        assert_eq!(stats.len(), 2);
+        assert!(stats[0].movie_count >= stats[1].movie_count);
+        assert_eq!(stats[0].genre, "Drama");
+        assert_eq!(stats[1].average_rating, None);
+    }
+
+    #[test]
+    fn test_global_rating_pipeline_groups_all_reviews() {
+        let pipeline = global_rating_pipeline();
+        assert_eq!(pipeline.len(), 1);
+        let group = pipeline[0].get_document("$group").unwrap();
+        assert_eq!(group.get("_id"), Some(&Bson::Null));
+        assert_eq!(
+            group.get_document("avg").unwrap(),
+            &doc! { "$avg": "$rating" }
+        );
+
+        assert_eq!(to_average_rating(None), None);
+        assert_eq!(
+            to_average_rating(Some(doc! { "_id": null, "avg": 3.876 })),
+            Some(3.88)
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_global_stats_json_shape() {
+        let mut mock = MockAdminRepository::new();
+
+        mock.expect_global_stats().returning(|| {
+            Ok(GlobalStats {
+                total_movies: 120,
+                total_series: 35,
+                total_reviews: 980,
+                average_rating: to_average_rating(Some(doc! { "_id": null, "avg": 3.871 })),
+            })
+        });
+
+        let stats = mock.global_stats().await.unwrap();
+        assert_eq!(
+            serde_json::to_value(stats).unwrap(),
+            serde_json::json!({
+                "totalMovies": 120,
+                "totalSeries": 35,
+                "totalReviews": 980,
+                "averageRating": 3.87,
+            })
+        );
+
+        let empty = GlobalStats {
+            total_movies: 0,
+            total_series: 0,
+            total_reviews: 0,
+            average_rating: to_average_rating(None),
+        };
+        assert!(serde_json::to_value(empty).unwrap()["averageRating"].is_null());
+    }
+
+    #[actix_web::test]
+    async fn test_find_raw_movie_by_id_ok() {
+        let mut mock = MockAdminRepository::new();
+        let oid = ObjectId::new();
+
+        mock.expect_find_raw_movie_by_id().returning(move |_| {
+            Ok(document_to_json(
+                doc! { "_id": oid, "reviewIds": [ObjectId::new()] },
+            ))
+        });
+
+        let result = mock.find_raw_movie_by_id(oid.to_string().as_str()).await;
+        assert!(result.is_ok_and(|map| map.contains_key("reviewIds")));
+    }
+
+    #[actix_web::test]
+    async fn test_find_raw_movie_by_id_not_found() {
+        let mut mock = MockAdminRepository::new();
+        let oid = ObjectId::new();
+
+        mock.expect_find_raw_movie_by_id()
+            .returning(|_| Err(AppError::NotFound));
+
+        let result = mock.find_raw_movie_by_id(oid.to_string().as_str()).await;
+        assert!(result.is_err_and(|err| err == AppError::NotFound));
+    }
+}
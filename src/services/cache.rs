@@ -0,0 +1,166 @@
+use std::{
+    collections::HashMap,
+    env,
+    future::Future,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use log::warn;
+
+use crate::error::AppError;
+
+const DEFAULT_CACHE_TTL_SECS: u64 = 60;
+
+/// In-memory cache of `/findById` results keyed by hex ObjectId. Writes through the API evict
+/// their entry; changes made straight in MongoDB need `POST /admin/cache/purge` or the TTL.
+pub struct FindByIdCache<T> {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (Instant, T)>>,
+}
+
+impl<T: Clone> FindByIdCache<T> {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Reads `FIND_BY_ID_CACHE_TTL_SECS` (60 by default, 0 disables the cache).
+    pub fn from_env() -> Self {
+        let ttl_secs = match env::var("FIND_BY_ID_CACHE_TTL_SECS") {
+            Ok(raw) => raw.trim().parse().unwrap_or_else(|_| {
+                warn!(
+                    "Invalid FIND_BY_ID_CACHE_TTL_SECS '{}', using default {}",
+                    raw, DEFAULT_CACHE_TTL_SECS
+                );
+                DEFAULT_CACHE_TTL_SECS
+            }),
+            Err(_) => DEFAULT_CACHE_TTL_SECS,
+        };
+        Self::new(Duration::from_secs(ttl_secs))
+    }
+
+    pub fn get(&self, id: &str) -> Option<T> {
+        let mut entries = self.entries.lock().unwrap();
+        let (stored_at, value) = entries.get(id)?;
+        if stored_at.elapsed() < self.ttl {
+            return Some(value.clone());
+        }
+        entries.remove(id);
+        None
+    }
+
+    pub fn insert(&self, id: &str, value: T) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(id.to_string(), (Instant::now(), value));
+    }
+
+    /// Cached value for `id`, or the result of `load` (cached only when it succeeds).
+    pub async fn get_or_load<F, Fut>(&self, id: &str, load: F) -> Result<T, AppError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, AppError>>,
+    {
+        if let Some(value) = self.get(id) {
+            return Ok(value);
+        }
+        let value = load().await?;
+        self.insert(id, value.clone());
+        Ok(value)
+    }
+
+    /// Evicts the given ids, returning how many were cached.
+    pub fn purge(&self, ids: &[String]) -> usize {
+        let mut entries = self.entries.lock().unwrap();
+        ids.iter()
+            .filter(|id| entries.remove(*id).is_some())
+            .count()
+    }
+
+    pub fn purge_all(&self) -> usize {
+        let mut entries = self.entries.lock().unwrap();
+        let purged = entries.len();
+        entries.clear();
+        purged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    // Auxiliary Functions
+
+    async fn load_counted(
+        cache: &FindByIdCache<String>,
+        id: &str,
+        db_hits: &AtomicUsize,
+    ) -> Result<String, AppError> {
+        cache
+            .get_or_load(id, || async {
+                db_hits.fetch_add(1, Ordering::SeqCst);
+                Ok(format!("movie {}", id))
+            })
+            .await
+    }
+
+    // Unit Tests
+
+    #[actix_web::test]
+    async fn test_purge_evicts_cached_entry() {
+        let cache = FindByIdCache::new(Duration::from_secs(60));
+        let db_hits = AtomicUsize::new(0);
+
+        load_counted(&cache, "6640ce2b8d0a4a4e3c5a7d31", &db_hits)
+            .await
+            .unwrap();
+        load_counted(&cache, "6640ce2b8d0a4a4e3c5a7d31", &db_hits)
+            .await
+            .unwrap();
+        assert_eq!(db_hits.load(Ordering::SeqCst), 1);
+
+        let purged = cache.purge(&[
+            "6640ce2b8d0a4a4e3c5a7d31".to_string(),
+            "6640ce2b8d0a4a4e3c5a7d32".to_string(),
+        ]);
+        assert_eq!(purged, 1);
+
+        let movie = load_counted(&cache, "6640ce2b8d0a4a4e3c5a7d31", &db_hits)
+            .await
+            .unwrap();
+        assert_eq!(movie, "movie 6640ce2b8d0a4a4e3c5a7d31");
+        assert_eq!(db_hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[actix_web::test]
+    async fn test_purge_all_and_failed_loads() {
+        let cache = FindByIdCache::new(Duration::from_secs(60));
+        let db_hits = AtomicUsize::new(0);
+        load_counted(&cache, "a", &db_hits).await.unwrap();
+        load_counted(&cache, "b", &db_hits).await.unwrap();
+
+        let not_found = cache
+            .get_or_load("c", || async { Err(AppError::NotFound) })
+            .await;
+        assert!(not_found.is_err_and(|err| err == AppError::NotFound));
+
+        assert_eq!(cache.purge_all(), 2);
+        assert!(cache.get("a").is_none());
+    }
+
+    #[test]
+    fn test_zero_ttl_disables_cache() {
+        let cache = FindByIdCache::new(Duration::ZERO);
+        cache.insert("a", "movie a".to_string());
+        assert!(cache.get("a").is_none());
+    }
+}
@@ -0,0 +1,69 @@
+use std::str::FromStr;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use log::warn;
+use mongodb::bson::oid::ObjectId;
+
+use crate::error::AppError;
+
+// Bumped whenever the token layout changes so old cursors are rejected instead of misread
+const CURSOR_VERSION: &str = "v1";
+
+/// Opaque pagination token pointing after `last_id`. Clients must not rely on its content.
+pub fn encode_cursor(last_id: &ObjectId) -> String {
+    URL_SAFE_NO_PAD.encode(format!("{}:{}", CURSOR_VERSION, last_id.to_hex()))
+}
+
+pub fn decode_cursor(cursor: &str) -> Result<ObjectId, AppError> {
+    let decoded = URL_SAFE_NO_PAD
+        .decode(cursor)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .and_then(|raw| {
+            raw.strip_prefix(CURSOR_VERSION)
+                .and_then(|rest| rest.strip_prefix(':'))
+                .and_then(|hex| ObjectId::from_str(hex).ok())
+        });
+    match decoded {
+        Some(oid) => Ok(oid),
+        None => {
+            let err = AppError::ValidationAppError(format!("Malformed cursor: '{}'", cursor));
+            warn!("Warn decoding cursor [{}]", err);
+            Err(err)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Unit Tests
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let oid = ObjectId::new();
+        let cursor = encode_cursor(&oid);
+        assert!(!cursor.contains(&oid.to_hex()));
+        assert_eq!(decode_cursor(&cursor).unwrap(), oid);
+    }
+
+    #[test]
+    fn test_decode_malformed_cursor() {
+        let oid = ObjectId::new();
+        let raw_oid = URL_SAFE_NO_PAD.encode(oid.to_hex());
+        let other_version = URL_SAFE_NO_PAD.encode(format!("v0:{}", oid.to_hex()));
+        for cursor in [
+            "",
+            "not base64!",
+            raw_oid.as_str(),
+            other_version.as_str(),
+            oid.to_hex().as_str(),
+        ] {
+            assert!(
+                matches!(decode_cursor(cursor), Err(AppError::ValidationAppError(_))),
+                "{cursor}"
+            );
+        }
+    }
+}
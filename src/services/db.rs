@@ -1,30 +1,132 @@
 use std::env;
 
-use crate::models::{movie::Movie, review::Review, series::Series};
+use crate::{
+    error::{internal_error, AppError},
+    models::{movie::Movie, review::Review, series::Series},
+};
+use async_trait::async_trait;
 use dotenv::dotenv;
-use mongodb::{Client, Collection};
+use log::{error, info, warn};
+use mongodb::{bson::doc, Client, Collection};
+
+use super::{cache::FindByIdCache, maintenance, seed};
+
+const DEFAULT_DB_NAME: &str = "cinema-rust-db";
 
 pub struct Database {
+    pub client: Client,
     pub movies: Collection<Movie>,
     pub series: Collection<Series>,
     pub reviews: Collection<Review>,
+    pub movie_cache: FindByIdCache<Movie>,
+    pub series_cache: FindByIdCache<Series>,
 }
 
 impl Database {
     pub async fn init() -> Self {
         dotenv().ok();
-        let uri = match env::var("MONGO_URI") {
-            Ok(v) => v.to_string(),
-            Err(_) => format!("An error ocurred trying to connect with MongoDB URI"),
-        };
+        let uri = resolve_mongo_uri(env::var("MONGO_URI").ok()).unwrap_or_else(|| {
+            error!("MONGO_URI is not set, add it to the environment or to the .env file");
+            panic!("MONGO_URI is not set");
+        });
+        let db_name = resolve_db_name(env::var("MONGO_DB_NAME").ok());
+        info!("Using MongoDB database '{}'", db_name);
 
         let client = Client::with_uri_str(uri).await.unwrap();
-        let db = client.database("cinema-rust-db");
+        let db = client.database(&db_name);
 
-        Database {
+        let database = Database {
+            client: client.clone(),
             movies: db.collection("movies"),
             series: db.collection("series"),
             reviews: db.collection("reviews"),
+            movie_cache: FindByIdCache::from_env(),
+            series_cache: FindByIdCache::from_env(),
+        };
+        database.create_imdb_id_indexes().await;
+        seed::seed_from_env(&database).await;
+        database
+    }
+
+    /// Unique `imdbId` index on movies and series, the only guard against two concurrent
+    /// creates of the same title. Runs `reindex`, so a non-unique `imdbId_1` left by older
+    /// versions is replaced. Existing duplicates make it fail, which is logged, not fatal.
+    async fn create_imdb_id_indexes(&self) {
+        match maintenance::reindex(self, false).await {
+            Ok(report) if report.changes.is_empty() => info!("Unique imdbId indexes ready"),
+            Ok(report) => info!("Unique imdbId indexes ready: {}", report.changes.join(", ")),
+            Err(err) => warn!(
+                "Could not create the unique imdbId indexes, remove the duplicated imdbIds listed by GET /admin/duplicates and call POST /admin/maintenance/reindex [{}]",
+                err
+            ),
         }
     }
+
+    /// Evicts `ids` from both findById caches, or every entry when `ids` is `None`.
+    pub fn purge_find_by_id_cache(&self, ids: Option<&[String]>) -> usize {
+        match ids {
+            Some(ids) => self.movie_cache.purge(ids) + self.series_cache.purge(ids),
+            None => self.movie_cache.purge_all() + self.series_cache.purge_all(),
+        }
+    }
+}
+
+/// `MONGO_URI`, `None` when it is missing or blank.
+fn resolve_mongo_uri(raw: Option<String>) -> Option<String> {
+    raw.map(|uri| uri.trim().to_string())
+        .filter(|uri| !uri.is_empty())
+}
+
+/// `MONGO_DB_NAME`, so several environments can share a cluster; `cinema-rust-db` when it is
+/// missing or blank.
+fn resolve_db_name(raw: Option<String>) -> String {
+    raw.map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| DEFAULT_DB_NAME.to_string())
+}
+
+/// Connectivity probe behind `/health`, a trait so the handler can be tested without MongoDB.
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait HealthCheck {
+    async fn ping(&self) -> Result<(), AppError>;
+}
+
+#[async_trait]
+impl HealthCheck for Database {
+    async fn ping(&self) -> Result<(), AppError> {
+        self.client
+            .database(&self.movies.namespace().db)
+            .run_command(doc! { "ping": 1 }, None)
+            .await
+            .map_err(internal_error("Error in /health pinging MongoDB"))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Unit Tests
+
+    #[test]
+    fn test_resolve_db_name() {
+        assert_eq!(resolve_db_name(None), DEFAULT_DB_NAME);
+        assert_eq!(resolve_db_name(Some("  ".to_string())), DEFAULT_DB_NAME);
+        assert_eq!(
+            resolve_db_name(Some(" cinema-rust-db-staging ".to_string())),
+            "cinema-rust-db-staging"
+        );
+    }
+
+    #[test]
+    fn test_resolve_mongo_uri_missing() {
+        assert_eq!(resolve_mongo_uri(None), None);
+        assert_eq!(resolve_mongo_uri(Some(String::new())), None);
+        assert_eq!(
+            resolve_mongo_uri(Some("mongodb://localhost:27017".to_string())),
+            Some("mongodb://localhost:27017".to_string())
+        );
+    }
 }
@@ -0,0 +1,618 @@
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+use futures_util::TryStreamExt;
+use log::info;
+use mongodb::{
+    bson::{doc, oid::ObjectId, Document},
+    options::{FindOptions, IndexOptions},
+    Collection, IndexModel,
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{
+    error::{internal_error, AppError},
+    models::duration::RuntimeDuration,
+};
+
+use super::db::Database;
+
+/// Field indexed by `reindex` on both the movies and series collections.
+const IMDB_ID_INDEX: &str = "imdbId_1";
+
+/// State of the `imdbId_1` index. Older databases have it without the unique flag, which makes
+/// creating the unique one fail with an index options conflict.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImdbIdIndex {
+    Missing,
+    NonUnique,
+    Unique,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceReport {
+    #[schema(example = "normalizeDurations")]
+    pub task: String,
+    #[schema(example = true)]
+    pub dry_run: bool,
+    #[schema(example = 1)]
+    pub changed_count: usize,
+    #[schema(example = json!(["movies 6640ce2b8d0a4a4e3c5a7d31: '2h' -> '2h 0m'"]))]
+    pub changes: Vec<String>,
+}
+
+impl MaintenanceReport {
+    fn new(task: &str, dry_run: bool, changes: Vec<String>) -> Self {
+        Self {
+            task: task.to_string(),
+            dry_run,
+            changed_count: changes.len(),
+            changes,
+        }
+    }
+}
+
+/// Low level reads/writes used by the maintenance tasks, so dry runs can be checked without a database.
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait MaintenanceStore {
+    async fn list_movie_durations(&self) -> Result<Vec<(ObjectId, String)>, AppError>;
+    async fn set_movie_duration(&self, id: ObjectId, duration: String) -> Result<(), AppError>;
+    async fn list_review_refs(
+        &self,
+        collection: &str,
+    ) -> Result<Vec<(ObjectId, Vec<ObjectId>)>, AppError>;
+    async fn list_review_ids(&self) -> Result<Vec<ObjectId>, AppError>;
+    async fn set_review_refs(
+        &self,
+        collection: &str,
+        id: ObjectId,
+        review_ids: Vec<ObjectId>,
+    ) -> Result<(), AppError>;
+    async fn list_reviews_without_parent(&self) -> Result<Vec<ObjectId>, AppError>;
+    async fn set_review_parent(
+        &self,
+        review_ids: Vec<ObjectId>,
+        parent_id: ObjectId,
+        parent_type: &str,
+    ) -> Result<(), AppError>;
+    async fn find_imdb_id_index(&self, collection: &str) -> Result<ImdbIdIndex, AppError>;
+    async fn drop_imdb_id_index(&self, collection: &str) -> Result<(), AppError>;
+    async fn create_imdb_id_index(&self, collection: &str) -> Result<(), AppError>;
+}
+
+impl Database {
+    fn raw_collection(&self, collection: &str) -> Collection<Document> {
+        match collection {
+            "series" => self.series.clone_with_type::<Document>(),
+            "reviews" => self.reviews.clone_with_type::<Document>(),
+            _ => self.movies.clone_with_type::<Document>(),
+        }
+    }
+}
+
+/// Unique `imdbId` index, created at startup and by `reindex` when it is missing.
+pub fn imdb_id_index() -> IndexModel {
+    IndexModel::builder()
+        .keys(doc! { "imdbId": 1 })
+        .options(
+            IndexOptions::builder()
+                .name(IMDB_ID_INDEX.to_string())
+                .unique(true)
+                .build(),
+        )
+        .build()
+}
+
+async fn find_all_raw(
+    collection: Collection<Document>,
+    projection: Document,
+    task: &str,
+) -> Result<Vec<Document>, AppError> {
+    let options = FindOptions::builder().projection(projection).build();
+    let cursor = collection
+        .find(None, options)
+        .await
+        .map_err(internal_error(format!(
+            "Error in admin /maintenance/{}",
+            task
+        )))?;
+    cursor.try_collect().await.map_err(internal_error(format!(
+        "Error in admin /maintenance/{}",
+        task
+    )))
+}
+
+#[async_trait]
+impl MaintenanceStore for Database {
+    async fn list_movie_durations(&self) -> Result<Vec<(ObjectId, String)>, AppError> {
+        let docs = find_all_raw(
+            self.raw_collection("movies"),
+            doc! { "duration": 1 },
+            "normalizeDurations",
+        )
+        .await?;
+        Ok(docs
+            .iter()
+            .filter_map(|movie_doc| {
+                Some((
+                    movie_doc.get_object_id("_id").ok()?,
+                    movie_doc.get_str("duration").ok()?.to_string(),
+                ))
+            })
+            .collect())
+    }
+
+    async fn set_movie_duration(&self, id: ObjectId, duration: String) -> Result<(), AppError> {
+        self.raw_collection("movies")
+            .update_one(
+                doc! { "_id": id },
+                doc! { "$set": { "duration": duration } },
+                None,
+            )
+            .await
+            .map_err(internal_error(
+                "Error in admin /maintenance/normalizeDurations",
+            ))?;
+        self.movie_cache.purge(&[id.to_hex()]);
+        Ok(())
+    }
+
+    async fn list_review_refs(
+        &self,
+        collection: &str,
+    ) -> Result<Vec<(ObjectId, Vec<ObjectId>)>, AppError> {
+        let docs = find_all_raw(
+            self.raw_collection(collection),
+            doc! { "reviewIds": 1 },
+            "recountReviews",
+        )
+        .await?;
+        Ok(docs
+            .iter()
+            .filter_map(|entity_doc| {
+                let review_ids = entity_doc
+                    .get_array("reviewIds")
+                    .map(|ids| ids.iter().filter_map(|id| id.as_object_id()).collect())
+                    .unwrap_or_default();
+                Some((entity_doc.get_object_id("_id").ok()?, review_ids))
+            })
+            .collect())
+    }
+
+    async fn list_review_ids(&self) -> Result<Vec<ObjectId>, AppError> {
+        let docs = find_all_raw(
+            self.raw_collection("reviews"),
+            doc! { "_id": 1 },
+            "recountReviews",
+        )
+        .await?;
+        Ok(docs
+            .iter()
+            .filter_map(|review_doc| review_doc.get_object_id("_id").ok())
+            .collect())
+    }
+
+    async fn set_review_refs(
+        &self,
+        collection: &str,
+        id: ObjectId,
+        review_ids: Vec<ObjectId>,
+    ) -> Result<(), AppError> {
+        self.raw_collection(collection)
+            .update_one(
+                doc! { "_id": id },
+                doc! { "$set": { "reviewIds": review_ids } },
+                None,
+            )
+            .await
+            .map_err(internal_error("Error in admin /maintenance/recountReviews"))?;
+        self.purge_find_by_id_cache(Some(&[id.to_hex()]));
+        Ok(())
+    }
+
+    async fn list_reviews_without_parent(&self) -> Result<Vec<ObjectId>, AppError> {
+        let options = FindOptions::builder().projection(doc! { "_id": 1 }).build();
+        let cursor = self
+            .raw_collection("reviews")
+            .find(doc! { "parentId": { "$exists": false } }, options)
+            .await
+            .map_err(internal_error(
+                "Error in admin /maintenance/backfillReviewParents",
+            ))?;
+        let docs: Vec<Document> = cursor.try_collect().await.map_err(internal_error(
+            "Error in admin /maintenance/backfillReviewParents",
+        ))?;
+        Ok(docs
+            .iter()
+            .filter_map(|review_doc| review_doc.get_object_id("_id").ok())
+            .collect())
+    }
+
+    async fn set_review_parent(
+        &self,
+        review_ids: Vec<ObjectId>,
+        parent_id: ObjectId,
+        parent_type: &str,
+    ) -> Result<(), AppError> {
+        self.raw_collection("reviews")
+            .update_many(
+                doc! { "_id": { "$in": review_ids } },
+                doc! { "$set": { "parentId": parent_id, "parentType": parent_type } },
+                None,
+            )
+            .await
+            .map_err(internal_error(
+                "Error in admin /maintenance/backfillReviewParents",
+            ))?;
+        self.purge_find_by_id_cache(Some(&[parent_id.to_hex()]));
+        Ok(())
+    }
+
+    async fn find_imdb_id_index(&self, collection: &str) -> Result<ImdbIdIndex, AppError> {
+        let indexes: Vec<IndexModel> = self
+            .raw_collection(collection)
+            .list_indexes(None)
+            .await
+            .map_err(internal_error("Error in admin /maintenance/reindex"))?
+            .try_collect()
+            .await
+            .map_err(internal_error("Error in admin /maintenance/reindex"))?;
+        let options = indexes.into_iter().find_map(|index| {
+            index
+                .options
+                .filter(|options| options.name.as_deref() == Some(IMDB_ID_INDEX))
+        });
+        Ok(match options {
+            None => ImdbIdIndex::Missing,
+            Some(options) if options.unique == Some(true) => ImdbIdIndex::Unique,
+            Some(_) => ImdbIdIndex::NonUnique,
+        })
+    }
+
+    async fn drop_imdb_id_index(&self, collection: &str) -> Result<(), AppError> {
+        self.raw_collection(collection)
+            .drop_index(IMDB_ID_INDEX, None)
+            .await
+            .map_err(internal_error("Error in admin /maintenance/reindex"))
+    }
+
+    async fn create_imdb_id_index(&self, collection: &str) -> Result<(), AppError> {
+        self.raw_collection(collection)
+            .create_index(imdb_id_index(), None)
+            .await
+            .map(|_| ())
+            .map_err(internal_error("Error in admin /maintenance/reindex"))
+    }
+}
+
+/// Rewrites every movie duration in the canonical "Xh Ym" form.
+pub async fn normalize_durations<S: MaintenanceStore + Sync>(
+    store: &S,
+    dry_run: bool,
+) -> Result<MaintenanceReport, AppError> {
+    info!("POST admin /maintenance/normalizeDurations executed (dryRun: {dry_run})");
+    let mut changes: Vec<String> = Vec::new();
+    for (id, raw) in store.list_movie_durations().await? {
+        let Ok(duration) = raw.parse::<RuntimeDuration>() else {
+            continue;
+        };
+        let canonical = duration.to_string();
+        if canonical == raw {
+            continue;
+        }
+        changes.push(format!("movies {}: '{}' -> '{}'", id, raw, canonical));
+        if !dry_run {
+            store.set_movie_duration(id, canonical).await?;
+        }
+    }
+    Ok(MaintenanceReport::new(
+        "normalizeDurations",
+        dry_run,
+        changes,
+    ))
+}
+
+/// Removes duplicated review ids and ids pointing to reviews that no longer exist.
+pub async fn recount_reviews<S: MaintenanceStore + Sync>(
+    store: &S,
+    dry_run: bool,
+) -> Result<MaintenanceReport, AppError> {
+    info!("POST admin /maintenance/recountReviews executed (dryRun: {dry_run})");
+    let existing: HashSet<ObjectId> = store.list_review_ids().await?.into_iter().collect();
+    let mut changes: Vec<String> = Vec::new();
+    for collection in ["movies", "series"] {
+        for (id, review_ids) in store.list_review_refs(collection).await? {
+            let mut seen: HashSet<ObjectId> = HashSet::new();
+            let cleaned: Vec<ObjectId> = review_ids
+                .iter()
+                .filter(|review_id| existing.contains(review_id) && seen.insert(**review_id))
+                .copied()
+                .collect();
+            if cleaned.len() == review_ids.len() {
+                continue;
+            }
+            changes.push(format!(
+                "{} {}: {} -> {} reviews",
+                collection,
+                id,
+                review_ids.len(),
+                cleaned.len()
+            ));
+            if !dry_run {
+                store.set_review_refs(collection, id, cleaned).await?;
+            }
+        }
+    }
+    Ok(MaintenanceReport::new("recountReviews", dry_run, changes))
+}
+
+/// Removes only the repeated review ids, keeping the first occurrence of each one.
+pub async fn dedupe_review_ids<S: MaintenanceStore + Sync>(
+    store: &S,
+    dry_run: bool,
+) -> Result<MaintenanceReport, AppError> {
+    info!("POST admin /maintenance/dedupeReviewIds executed (dryRun: {dry_run})");
+    let mut changes: Vec<String> = Vec::new();
+    for collection in ["movies", "series"] {
+        for (id, review_ids) in store.list_review_refs(collection).await? {
+            let mut seen: HashSet<ObjectId> = HashSet::new();
+            let deduped: Vec<ObjectId> = review_ids
+                .iter()
+                .filter(|review_id| seen.insert(**review_id))
+                .copied()
+                .collect();
+            if deduped.len() == review_ids.len() {
+                continue;
+            }
+            changes.push(format!(
+                "{} {}: {} duplicated review ids removed",
+                collection,
+                id,
+                review_ids.len() - deduped.len()
+            ));
+            if !dry_run {
+                store.set_review_refs(collection, id, deduped).await?;
+            }
+        }
+    }
+    Ok(MaintenanceReport::new("dedupeReviewIds", dry_run, changes))
+}
+
+/// Sets `parentId`/`parentType` on reviews stored before they existed, from the `reviewIds` of
+/// movies and series. A review referenced by both keeps the movie, as in `/findAllWithParents`.
+pub async fn backfill_review_parents<S: MaintenanceStore + Sync>(
+    store: &S,
+    dry_run: bool,
+) -> Result<MaintenanceReport, AppError> {
+    info!("POST admin /maintenance/backfillReviewParents executed (dryRun: {dry_run})");
+    let mut orphans: HashSet<ObjectId> = store
+        .list_reviews_without_parent()
+        .await?
+        .into_iter()
+        .collect();
+    let mut changes: Vec<String> = Vec::new();
+    for (collection, parent_type) in [("movies", "movie"), ("series", "series")] {
+        for (id, review_ids) in store.list_review_refs(collection).await? {
+            let linked: Vec<ObjectId> = review_ids
+                .into_iter()
+                .filter(|review_id| orphans.remove(review_id))
+                .collect();
+            if linked.is_empty() {
+                continue;
+            }
+            changes.push(format!(
+                "{} {}: {} reviews linked",
+                collection,
+                id,
+                linked.len()
+            ));
+            if !dry_run {
+                store.set_review_parent(linked, id, parent_type).await?;
+            }
+        }
+    }
+    Ok(MaintenanceReport::new(
+        "backfillReviewParents",
+        dry_run,
+        changes,
+    ))
+}
+
+/// Creates the unique imdbId index on movies and series when it is missing, and replaces the
+/// non-unique one older databases have.
+pub async fn reindex<S: MaintenanceStore + Sync>(
+    store: &S,
+    dry_run: bool,
+) -> Result<MaintenanceReport, AppError> {
+    info!("POST admin /maintenance/reindex executed (dryRun: {dry_run})");
+    let mut changes: Vec<String> = Vec::new();
+    for collection in ["movies", "series"] {
+        let state = store.find_imdb_id_index(collection).await?;
+        match state {
+            ImdbIdIndex::Unique => continue,
+            ImdbIdIndex::Missing => {
+                changes.push(format!("{}: create index '{}'", collection, IMDB_ID_INDEX))
+            }
+            ImdbIdIndex::NonUnique => changes.push(format!(
+                "{}: replace non-unique index '{}' with a unique one",
+                collection, IMDB_ID_INDEX
+            )),
+        }
+        if dry_run {
+            continue;
+        }
+        if state == ImdbIdIndex::NonUnique {
+            store.drop_imdb_id_index(collection).await?;
+        }
+        store.create_imdb_id_index(collection).await?;
+    }
+    Ok(MaintenanceReport::new("reindex", dry_run, changes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Unit Tests
+
+    #[actix_web::test]
+    async fn test_normalize_durations_dry_run_does_not_write() {
+        let mut mock = MockMaintenanceStore::new();
+        let oid = ObjectId::new();
+
+        mock.expect_list_movie_durations().returning(move || {
+            Ok(vec![
+                (oid, "2h".to_string()),
+                (ObjectId::new(), "1h 30m".to_string()),
+            ])
+        });
+        mock.expect_set_movie_duration().times(0);
+
+        let report = normalize_durations(&mock, true).await.unwrap();
+        assert!(report.dry_run);
+        assert_eq!(report.changed_count, 1);
+        assert_eq!(
+            report.changes[0],
+            format!("movies {}: '2h' -> '2h 0m'", oid)
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_normalize_durations_writes_changes() {
+        let mut mock = MockMaintenanceStore::new();
+
+        mock.expect_list_movie_durations()
+            .returning(|| Ok(vec![(ObjectId::new(), "95m".to_string())]));
+        mock.expect_set_movie_duration()
+            .withf(|_, duration| duration == "1h 35m")
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let report = normalize_durations(&mock, false).await.unwrap();
+        assert!(!report.dry_run);
+        assert_eq!(report.changed_count, 1);
+    }
+
+    #[actix_web::test]
+    async fn test_recount_reviews_dry_run_does_not_write() {
+        let mut mock = MockMaintenanceStore::new();
+        let review_oid = ObjectId::new();
+
+        mock.expect_list_review_ids()
+            .returning(move || Ok(vec![review_oid]));
+        mock.expect_list_review_refs().returning(move |collection| {
+            Ok(match collection {
+                "movies" => vec![(
+                    ObjectId::new(),
+                    vec![review_oid, review_oid, ObjectId::new()],
+                )],
+                _ => vec![(ObjectId::new(), vec![])],
+            })
+        });
+        mock.expect_set_review_refs().times(0);
+
+        let report = recount_reviews(&mock, true).await.unwrap();
+        assert_eq!(report.changed_count, 1);
+        assert!(report.changes[0].ends_with("3 -> 1 reviews"));
+    }
+
+    #[actix_web::test]
+    async fn test_dedupe_review_ids_writes_unique_ids() {
+        let mut mock = MockMaintenanceStore::new();
+        let review_oid = ObjectId::new();
+        let other_oid = ObjectId::new();
+
+        mock.expect_list_review_refs().returning(move |collection| {
+            Ok(match collection {
+                "series" => vec![(ObjectId::new(), vec![review_oid, other_oid, review_oid])],
+                _ => vec![(ObjectId::new(), vec![other_oid])],
+            })
+        });
+        mock.expect_set_review_refs()
+            .withf(move |collection, _, ids| {
+                collection == "series" && ids == &vec![review_oid, other_oid]
+            })
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+
+        let report = dedupe_review_ids(&mock, false).await.unwrap();
+        assert_eq!(report.changed_count, 1);
+    }
+
+    #[actix_web::test]
+    async fn test_backfill_review_parents_links_orphans_only() {
+        let mut mock = MockMaintenanceStore::new();
+        let movie_oid = ObjectId::new();
+        let series_oid = ObjectId::new();
+        let orphan_oid = ObjectId::new();
+        let shared_oid = ObjectId::new();
+
+        mock.expect_list_reviews_without_parent()
+            .returning(move || Ok(vec![orphan_oid, shared_oid]));
+        mock.expect_list_review_refs().returning(move |collection| {
+            Ok(match collection {
+                "movies" => vec![(movie_oid, vec![orphan_oid, shared_oid, ObjectId::new()])],
+                _ => vec![(series_oid, vec![shared_oid])],
+            })
+        });
+        mock.expect_set_review_parent()
+            .withf(move |ids, parent_id, parent_type| {
+                ids == &vec![orphan_oid, shared_oid]
+                    && *parent_id == movie_oid
+                    && parent_type == "movie"
+            })
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+
+        let report = backfill_review_parents(&mock, false).await.unwrap();
+        assert_eq!(
+            report.changes,
+            vec![format!("movies {}: 2 reviews linked", movie_oid)]
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_reindex_dry_run_does_not_write() {
+        let mut mock = MockMaintenanceStore::new();
+
+        mock.expect_find_imdb_id_index().returning(|collection| {
+            Ok(match collection {
+                "movies" => ImdbIdIndex::Unique,
+                _ => ImdbIdIndex::Missing,
+            })
+        });
+        mock.expect_create_imdb_id_index().times(0);
+
+        let report = reindex(&mock, true).await.unwrap();
+        assert_eq!(report.changes, vec!["series: create index 'imdbId_1'"]);
+    }
+
+    #[actix_web::test]
+    async fn test_reindex_replaces_non_unique_index() {
+        let mut mock = MockMaintenanceStore::new();
+
+        mock.expect_find_imdb_id_index().returning(|collection| {
+            Ok(match collection {
+                "movies" => ImdbIdIndex::NonUnique,
+                _ => ImdbIdIndex::Unique,
+            })
+        });
+        mock.expect_drop_imdb_id_index()
+            .withf(|collection| collection == "movies")
+            .times(1)
+            .returning(|_| Ok(()));
+        mock.expect_create_imdb_id_index()
+            .withf(|collection| collection == "movies")
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let report = reindex(&mock, false).await.unwrap();
+        assert_eq!(
+            report.changes,
+            vec!["movies: replace non-unique index 'imdbId_1' with a unique one"]
+        );
+    }
+}
@@ -1,4 +1,11 @@
+pub mod admin_repo;
+pub mod cache;
+pub mod cursor;
 pub mod db;
+pub mod maintenance;
 pub mod movie_repo;
+pub mod pagination;
 pub mod review_repo;
+pub mod seed;
 pub mod series_repo;
+pub mod title_repo;
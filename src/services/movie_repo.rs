@@ -1,24 +1,45 @@
-use std::str::FromStr;
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    str::FromStr,
+};
 
 use async_trait::async_trait;
-use futures_util::{StreamExt, TryStreamExt};
-use lazy_static::lazy_static;
+use futures_util::TryStreamExt;
 use log::{error, info, warn};
 use mongodb::{
-    bson::{doc, oid::ObjectId, Regex},
-    options::{CountOptions, FindOptions},
+    bson::{doc, from_document, oid::ObjectId, Bson, DateTime, Document, Regex},
+    error::{BulkWriteFailure, ErrorKind},
+    options::{
+        CountOptions, FindOneAndUpdateOptions, FindOneOptions, FindOptions, InsertManyOptions,
+        ReturnDocument,
+    },
+    Collection,
 };
 use serde_json::{Map, Value};
 
 use crate::{
-    error::AppError,
-    models::movie::{Movie, MovieRequest, MovieResponse},
+    error::{insert_error, inserted_object_id, internal_error, AppError, DUPLICATE_KEY_CODE},
+    models::{
+        duration::RuntimeDuration,
+        fields::{is_patchable, MOVIE_PATCHABLE_FIELDS},
+        movie::{Movie, MovieRequest, MovieResponse},
+        validation::{normalize_imdb_id, RE_MOVIE_IMDB_ID},
+    },
 };
 
-use super::{db::Database, series_repo::SeriesRepository};
+use super::{
+    db::Database,
+    pagination::{build_pagination_map, current_page, page_skip},
+    series_repo::SeriesRepository,
+};
 
-lazy_static! {
-    static ref RE_IMDB_ID: regex::Regex = regex::Regex::new(r"^tt\d+$").unwrap();
+/// `/findAll` filters, each one optional and ANDed with the others.
+#[derive(Debug, Default)]
+pub struct MovieFilter {
+    pub title: Option<String>,
+    pub genre: Option<String>,
+    /// Four digit release year, matched against the start of `releaseDate`
+    pub year: Option<String>,
 }
 
 #[cfg_attr(test, mockall::automock)]
@@ -26,13 +47,27 @@ lazy_static! {
 pub trait MovieRepository {
     async fn find_all_movies(
         &self,
-        title: Option<String>,
+        filter: MovieFilter,
+        sort: Option<String>,
+        order: Option<String>,
         page: Option<u32>,
         size: Option<u32>,
     ) -> Result<Map<String, Value>, AppError>;
     async fn find_movie_by_id(&self, id: &str) -> Result<Movie, AppError>;
+    async fn find_movie_projection_by_id(
+        &self,
+        id: &str,
+        projection: Document,
+    ) -> Result<Document, AppError>;
     async fn find_movie_by_imdb_id(&self, imdb_id: &str) -> Result<Movie, AppError>;
+    async fn find_random_movie(&self) -> Result<Movie, AppError>;
+    async fn count_movies(&self, filter: MovieFilter) -> Result<Map<String, Value>, AppError>;
+    async fn distinct_genres(&self) -> Result<Vec<String>, AppError>;
     async fn create_movie(&self, movie: Movie) -> Result<Map<String, Value>, AppError>;
+    async fn create_movies(
+        &self,
+        movies: Vec<Movie>,
+    ) -> Result<Vec<Result<ObjectId, AppError>>, AppError>;
     async fn delete_movie(&self, id: &str) -> Result<Map<String, Value>, AppError>;
     async fn movie_exists_by_imdb_id(&self, imdb_id: &str) -> Result<bool, AppError>;
     async fn update_movie(
@@ -46,130 +81,170 @@ pub trait MovieRepository {
         field: &str,
         val: &str,
     ) -> Result<Map<String, Value>, AppError>;
+    async fn patch_movie_many(
+        &self,
+        id: &str,
+        fields: HashMap<String, String>,
+    ) -> Result<Map<String, Value>, AppError>;
+    async fn set_movie_genres(
+        &self,
+        id: &str,
+        genres: Vec<String>,
+    ) -> Result<Map<String, Value>, AppError>;
+    async fn upsert_movie_by_imdb_id(
+        &self,
+        imdb_id: &str,
+        movie: MovieRequest,
+    ) -> Result<Map<String, Value>, AppError>;
+    async fn find_release_date_range(&self) -> Result<Map<String, Value>, AppError>;
+    async fn list_movie_imdb_ids(
+        &self,
+        page: Option<u32>,
+        size: Option<u32>,
+    ) -> Result<Map<String, Value>, AppError>;
 }
 
 #[async_trait]
 impl MovieRepository for Database {
     async fn find_all_movies(
         &self,
-        title: Option<String>,
+        filter: MovieFilter,
+        sort: Option<String>,
+        order: Option<String>,
         page: Option<u32>,
         size: Option<u32>,
     ) -> Result<Map<String, Value>, AppError> {
         info!("GET movies /findAll executed");
         let mut result_map: Map<String, Value> = Map::new();
 
-        let page_num = match page {
-            None => 0,
-            Some(page) => {
-                if page > 0 {
-                    page
-                } else {
-                    0
-                }
-            }
-        };
+        let page_num = current_page(page);
         let page_size = match size {
-            None => 10,
-            Some(size) => {
-                if size > 0 {
-                    size
-                } else {
-                    10
-                }
-            }
-        };
-        let filter = match title {
-            None => None,
-            Some(title) => {
-                let regex = Regex {
-                    pattern: format!("{}", title),
-                    options: String::new(),
-                };
-                doc! {"title": { "$regex": regex, "$options": "i" }}.into()
-            }
+            Some(size) if size > 0 => size,
+            _ => 10,
         };
+        validate_release_year(filter.year.as_deref())?;
+        let filter = build_movie_filter(filter);
+
+        let movie_sort = parse_movie_sort(sort.as_deref(), order.as_deref())?;
 
         let total_items = self
             .movies
             .count_documents(filter.clone(), CountOptions::default())
             .await
-            .ok()
-            .expect("Error counting total of movies");
-        let total_pages = (total_items as f64 / page_size as f64).ceil() as u64;
-
-        let options = FindOptions::builder()
-            .skip((page_num * page_size) as u64)
-            .limit(page_size as i64)
-            .build();
-
-        let cursor = self
-            .movies
-            .find(filter, options)
-            .await
-            .ok()
-            .expect("Error finding all movies");
-
-        let movie_list: Vec<MovieResponse> = cursor
-            .map(|movie| MovieResponse::try_from(movie.unwrap()))
-            .try_collect()
-            .await
-            .ok()
-            .expect("Error collecting movies");
+            .map_err(internal_error("Error counting total of movies"))?;
 
-        if movie_list.is_empty() {
-            warn!("Warn in movies /findAll [{}]", AppError::Empty.to_string());
-            return Err(AppError::Empty);
-        }
+        let skip = page_skip(page_num - 1, page_size)?;
+        let movie_list: Vec<MovieResponse> = match movie_sort {
+            MovieSort::ReviewCount(direction) => {
+                let pipeline = review_count_pipeline(filter, direction, skip, page_size as i64);
+                aggregate_movies(&self.movies, pipeline).await?
+            }
+            MovieSort::Duration(direction) => {
+                let pipeline = duration_pipeline(filter, direction, skip, page_size as i64);
+                aggregate_movies(&self.movies, pipeline).await?
+            }
+            MovieSort::Find(sort_doc) => {
+                let options = FindOptions::builder()
+                    .sort(sort_doc)
+                    .skip(skip)
+                    .limit(page_size as i64)
+                    .build();
+
+                let cursor = self
+                    .movies
+                    .find(filter, options)
+                    .await
+                    .map_err(internal_error("Error finding all movies"))?;
+
+                let movies: Vec<Movie> = cursor
+                    .try_collect()
+                    .await
+                    .map_err(internal_error("Error collecting movies"))?;
+                movies
+                    .into_iter()
+                    .map(MovieResponse::try_from)
+                    .collect::<Result<_, _>>()
+                    .map_err(internal_error("Error converting movies"))?
+            }
+        };
 
+        // An empty page still gets its envelope, the route decides whether it is a 204
         result_map.insert(
             "movies".to_string(),
             serde_json::to_value(movie_list).unwrap(),
         );
-        result_map.insert(
-            "currentPage".to_string(),
-            serde_json::to_value(page_num).unwrap(),
-        );
-        result_map.insert(
-            "totalItems".to_string(),
-            serde_json::to_value(total_items).unwrap(),
-        );
-        result_map.insert(
-            "totalPages".to_string(),
-            serde_json::to_value(total_pages).unwrap(),
-        );
-
-        Ok(result_map)
+        Ok(build_pagination_map(
+            result_map,
+            page_num,
+            page_size,
+            total_items,
+        ))
     }
 
     async fn find_movie_by_id(&self, id: &str) -> Result<Movie, AppError> {
         info!("GET movies /findById with id: '{}' executed", id);
         let obj_id = ObjectId::from_str(id)?;
-        let movie: Movie = match self.movies.find_one(doc! {"_id": obj_id}, None).await {
-            Ok(Some(movie)) => movie,
-            Ok(None) => {
+        self.movie_cache
+            .get_or_load(&obj_id.to_hex(), || async {
+                match self.movies.find_one(doc! {"_id": obj_id}, None).await {
+                    Ok(Some(movie)) => Ok(movie),
+                    Ok(None) => {
+                        warn!(
+                            "Warn in movies /findById with id: '{}' [{}]",
+                            id,
+                            AppError::NotFound.to_string()
+                        );
+                        Err(AppError::NotFound)
+                    }
+                    Err(_) => {
+                        error!(
+                            "Error in movies /findById with id: '{}' [{}]",
+                            id,
+                            AppError::InternalServerError.to_string()
+                        );
+                        Err(AppError::InternalServerError)
+                    }
+                }
+            })
+            .await
+    }
+
+    async fn find_movie_projection_by_id(
+        &self,
+        id: &str,
+        projection: Document,
+    ) -> Result<Document, AppError> {
+        info!(
+            "GET movies /findById with id: '{}' and projection executed",
+            id
+        );
+        let obj_id = ObjectId::from_str(id)?;
+        let options = FindOneOptions::builder().projection(projection).build();
+        match self
+            .movies
+            .clone_with_type::<Document>()
+            .find_one(doc! {"_id": obj_id}, options)
+            .await
+            .map_err(internal_error(format!(
+                "Error in movies /findById with id: '{}' and projection",
+                id
+            )))? {
+            Some(movie_doc) => Ok(movie_doc),
+            None => {
                 warn!(
                     "Warn in movies /findById with id: '{}' [{}]",
                     id,
-                    AppError::NotFound.to_string()
-                );
-                return Err(AppError::NotFound);
-            }
-            Err(_) => {
-                error!(
-                    "Error in movies /findById with id: '{}' [{}]",
-                    id,
-                    AppError::InternalServerError.to_string()
+                    AppError::NotFound
                 );
-                return Err(AppError::InternalServerError);
+                Err(AppError::NotFound)
             }
-        };
-        Ok(movie)
+        }
     }
 
     async fn find_movie_by_imdb_id(&self, imdb_id: &str) -> Result<Movie, AppError> {
+        let imdb_id = &normalize_imdb_id(imdb_id);
         info!("GET movies /findByImdbId with id: '{}' executed", imdb_id);
-        if !RE_IMDB_ID.is_match(imdb_id) {
+        if !RE_MOVIE_IMDB_ID.is_match(imdb_id) {
             error!(
                 "Error in movies /findByImdbId with imdbId: '{}' [{}]",
                 imdb_id,
@@ -200,6 +275,57 @@ impl MovieRepository for Database {
         Ok(movie)
     }
 
+    async fn find_random_movie(&self) -> Result<Movie, AppError> {
+        info!("GET movies /random executed");
+        let mut cursor = self
+            .movies
+            .aggregate(vec![doc! { "$sample": { "size": 1 } }], None)
+            .await
+            .map_err(internal_error("Error in movies /random sampling a movie"))?;
+        let movie_doc = match cursor.try_next().await {
+            Ok(Some(movie_doc)) => movie_doc,
+            Ok(None) => {
+                warn!("Warn in movies /random [{}]", AppError::Empty.to_string());
+                return Err(AppError::Empty);
+            }
+            Err(err) => {
+                error!(
+                    "Error in movies /random reading the sampled movie [{}]",
+                    err
+                );
+                return Err(AppError::InternalServerError);
+            }
+        };
+        from_document(movie_doc).map_err(internal_error(
+            "Error in movies /random deserializing the sampled movie",
+        ))
+    }
+
+    async fn count_movies(&self, filter: MovieFilter) -> Result<Map<String, Value>, AppError> {
+        info!("GET movies /count executed");
+        validate_release_year(filter.year.as_deref())?;
+        let count = self
+            .movies
+            .count_documents(build_movie_filter(filter), CountOptions::default())
+            .await
+            .map_err(internal_error("Error in movies /count counting movies"))?;
+        let mut map_result: Map<String, Value> = Map::new();
+        map_result.insert("count".to_string(), serde_json::to_value(count).unwrap());
+        Ok(map_result)
+    }
+
+    async fn distinct_genres(&self) -> Result<Vec<String>, AppError> {
+        info!("GET movies /genres executed");
+        let genres = self
+            .movies
+            .distinct("genres", None, None)
+            .await
+            .map_err(internal_error(
+                "Error in movies /genres finding distinct genres",
+            ))?;
+        Ok(sorted_genres(genres))
+    }
+
     async fn create_movie(&self, movie: Movie) -> Result<Map<String, Value>, AppError> {
         info!("POST movies /new executed");
         if self
@@ -226,21 +352,73 @@ impl MovieRepository for Database {
             .movies
             .insert_one(&movie, None)
             .await
-            .ok()
-            .expect(format!("Error creating movie with imdbId: '{}'", movie.imdb_id).as_str());
+            .map_err(insert_error(format!(
+                "Error creating movie with imdbId: '{}'",
+                movie.imdb_id
+            )))?;
+
+        Ok(created_movie_result(inserted_object_id(
+            &result.inserted_id,
+        )?))
+    }
 
-        let mut map_result: Map<String, Value> = Map::new();
-        map_result.insert(
-            "message".to_string(),
-            Value::String(
-                format!(
-                    "Movie was successfully created. (id: '{}')",
-                    result.inserted_id.as_object_id().unwrap().to_string()
-                )
-                .to_string(),
-            ),
-        );
-        Ok(map_result)
+    async fn create_movies(
+        &self,
+        movies: Vec<Movie>,
+    ) -> Result<Vec<Result<ObjectId, AppError>>, AppError> {
+        info!("POST movies /bulk executed");
+        if movies.is_empty() {
+            return Ok(Vec::new());
+        }
+        let imdb_ids: Vec<String> = movies.iter().map(|movie| movie.imdb_id.clone()).collect();
+        let filter = doc! { "imdbId": { "$in": imdb_ids.as_slice() } };
+        let mut taken = self
+            .movies
+            .distinct("imdbId", filter.clone(), None)
+            .await
+            .map_err(internal_error(
+                "Error finding taken imdbIds in movies /bulk",
+            ))?;
+        taken.extend(self.series.distinct("imdbId", filter, None).await.map_err(
+            internal_error("Error finding taken imdbIds in movies /bulk"),
+        )?);
+        let taken: HashSet<String> = taken
+            .iter()
+            .filter_map(|imdb_id| imdb_id.as_str().map(str::to_string))
+            .collect();
+
+        let insertable = plan_bulk_insert(&imdb_ids, &taken);
+        let ids: Vec<ObjectId> = movies.iter().map(|movie| movie._id).collect();
+        let (to_insert, positions): (Vec<Movie>, Vec<usize>) = movies
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| insertable[*index])
+            .map(|(index, movie)| (movie, index))
+            .unzip();
+        if to_insert.is_empty() {
+            return Ok(bulk_outcomes(&ids, &insertable, &HashMap::new()));
+        }
+
+        // Unordered, so one failed movie does not stop the rest of the batch
+        let options = InsertManyOptions::builder().ordered(false).build();
+        let failed_codes: HashMap<usize, i32> =
+            match self.movies.insert_many(to_insert, options).await {
+                Ok(_) => HashMap::new(),
+                Err(err) => match err.kind.as_ref() {
+                    ErrorKind::BulkWrite(BulkWriteFailure {
+                        write_errors: Some(write_errors),
+                        ..
+                    }) => write_errors
+                        .iter()
+                        .map(|write_error| (positions[write_error.index], write_error.code))
+                        .collect(),
+                    _ => {
+                        error!("Error in movies /bulk inserting movies [{}]", err);
+                        return Err(AppError::InternalServerError);
+                    }
+                },
+            };
+        Ok(bulk_outcomes(&ids, &insertable, &failed_codes))
     }
 
     async fn delete_movie(&self, id: &str) -> Result<Map<String, Value>, AppError> {
@@ -257,6 +435,7 @@ impl MovieRepository for Database {
                 return Err(AppError::InternalServerError);
             }
         };
+        self.movie_cache.purge(&[obj_id.to_hex()]);
         let mut map_result: Map<String, Value> = Map::new();
         if del_result.deleted_count > 0 {
             map_result.insert(
@@ -269,9 +448,9 @@ impl MovieRepository for Database {
             warn!(
                 "Warn in movies /delete with id: '{}' [{}]",
                 obj_id,
-                AppError::NotExists.to_string()
+                AppError::NotFound.to_string()
             );
-            return Err(AppError::NotExists);
+            return Err(AppError::NotFound);
         }
         Ok(map_result)
     }
@@ -299,6 +478,7 @@ impl MovieRepository for Database {
     ) -> Result<Map<String, Value>, AppError> {
         info!("PUT movies /update with id: '{}' executed", id);
         let obj_id = ObjectId::from_str(id)?;
+        check_movie_imdb_id(&movie.imdb_id, "/update")?;
         let movie_founded: Movie = match self.movies.find_one(doc! { "_id": obj_id }, None).await {
             Ok(Some(movie)) => movie,
             Ok(None) => {
@@ -333,23 +513,15 @@ impl MovieRepository for Database {
             .movies
             .update_one(
                 doc! { "_id": obj_id },
-                doc! {
-                "$set": doc! {
-                    "imdbId": movie.imdb_id,
-                    "title": movie.title,
-                    "overview": movie.overview,
-                    "duration": movie.duration,
-                    "releaseDate": movie.release_date,
-                    "trailerLink": movie.trailer_link,
-                    "genres": movie.genres,
-                    "poster": movie.poster,
-                    "backdrop": movie.backdrop
-                }},
+                doc! { "$set": build_update_set(movie)? },
                 None,
             )
             .await
-            .ok()
-            .expect(format!("Error updating movie with id: '{}'", id).as_str());
+            .map_err(internal_error(format!(
+                "Error updating movie with id: '{}'",
+                id
+            )))?;
+        self.movie_cache.purge(&[obj_id.to_hex()]);
         let mut map_result: Map<String, Value> = Map::new();
         map_result.insert(
             "message".to_string(),
@@ -369,20 +541,8 @@ impl MovieRepository for Database {
         val: &str,
     ) -> Result<Map<String, Value>, AppError> {
         info!("PATCH movies /patch with id: '{}' executed", id);
-        let fields_vec: Vec<&str> = vec![
-            "imdbId",
-            "title",
-            "overview",
-            "duration",
-            "director",
-            "releaseDate",
-            "trailerLink",
-            "genres",
-            "poster",
-            "backdrop",
-        ];
         let obj_id = ObjectId::from_str(id)?;
-        if !fields_vec.contains(&field) {
+        if !is_patchable(MOVIE_PATCHABLE_FIELDS, field) {
             warn!(
                 "Warn in movies /patch with id: '{}' [{}]",
                 obj_id,
@@ -390,6 +550,7 @@ impl MovieRepository for Database {
             );
             return Err(AppError::FieldNotAllowed);
         }
+        let patched_val = patch_value(field, val)?;
         let movie_founded: Movie = match self.movies.find_one(doc! { "_id": obj_id }, None).await {
             Ok(Some(movie)) => movie,
             Ok(None) => {
@@ -410,14 +571,7 @@ impl MovieRepository for Database {
             }
         };
         if field == "imdbId" {
-            if !RE_IMDB_ID.is_match(val) {
-                error!(
-                    "Error in movies /patch with id: '{}' [{}]",
-                    id,
-                    AppError::WrongImdbId.to_string()
-                );
-                return Err(AppError::WrongImdbId);
-            }
+            check_movie_imdb_id(val, "/patch")?;
             let exists_imdb_id_movie: bool = self.movie_exists_by_imdb_id(val).await?;
             let exists_imdb_id_series: bool = self.series_exists_by_imdb_id(val).await?;
             if (exists_imdb_id_movie || exists_imdb_id_series) && movie_founded.imdb_id != val {
@@ -435,13 +589,17 @@ impl MovieRepository for Database {
                 doc! { "_id": obj_id },
                 doc! {
                 "$set": doc! {
-                    field: val
+                    field: patched_val,
+                    "updatedAt": DateTime::now(),
                 }},
                 None,
             )
             .await
-            .ok()
-            .expect(format!("Error patching movie with id: '{}'", id).as_str());
+            .map_err(internal_error(format!(
+                "Error patching movie with id: '{}'",
+                id
+            )))?;
+        self.movie_cache.purge(&[obj_id.to_hex()]);
         let mut map_result: Map<String, Value> = Map::new();
         map_result.insert(
             "message".to_string(),
@@ -453,98 +611,1141 @@ impl MovieRepository for Database {
         );
         Ok(map_result)
     }
+
+    async fn patch_movie_many(
+        &self,
+        id: &str,
+        fields: HashMap<String, String>,
+    ) -> Result<Map<String, Value>, AppError> {
+        info!("PATCH movies /patchMany with id: '{}' executed", id);
+        let obj_id = ObjectId::from_str(id)?;
+        if fields.is_empty() {
+            return Err(AppError::ValidationAppError(
+                "fields: At least one field is required".to_string(),
+            ));
+        }
+        if let Some(field) = first_not_patchable(&fields) {
+            warn!(
+                "Warn in movies /patchMany with id: '{}' and field: '{}' [{}]",
+                obj_id,
+                field,
+                AppError::FieldNotAllowed
+            );
+            return Err(AppError::FieldNotAllowed);
+        }
+        let movie_founded: Movie = match self.movies.find_one(doc! { "_id": obj_id }, None).await {
+            Ok(Some(movie)) => movie,
+            Ok(None) => {
+                warn!(
+                    "Warn in movies /patchMany with id: '{}' [{}]",
+                    obj_id,
+                    AppError::NotExists
+                );
+                return Err(AppError::NotExists);
+            }
+            Err(_) => {
+                error!(
+                    "Error in movies /patchMany with id: '{}' [{}]",
+                    obj_id,
+                    AppError::InternalServerError
+                );
+                return Err(AppError::InternalServerError);
+            }
+        };
+        if let Some(imdb_id) = fields.get("imdbId") {
+            check_movie_imdb_id(imdb_id, "/patchMany")?;
+            let exists_imdb_id_movie: bool = self.movie_exists_by_imdb_id(imdb_id).await?;
+            let exists_imdb_id_series: bool = self.series_exists_by_imdb_id(imdb_id).await?;
+            if (exists_imdb_id_movie || exists_imdb_id_series) && movie_founded.imdb_id != *imdb_id
+            {
+                warn!(
+                    "Warn in movies /patchMany with id: '{}' [{}]",
+                    obj_id,
+                    AppError::ImdbIdInUse
+                );
+                return Err(AppError::ImdbIdInUse);
+            }
+        }
+        let patch_set = build_patch_set(&fields)?;
+        let result = self
+            .movies
+            .update_one(doc! { "_id": obj_id }, doc! { "$set": patch_set }, None)
+            .await
+            .map_err(internal_error(format!(
+                "Error patching movie with id: '{}'",
+                id
+            )))?;
+        self.movie_cache.purge(&[obj_id.to_hex()]);
+        let mut map_result: Map<String, Value> = Map::new();
+        map_result.insert(
+            "message".to_string(),
+            Value::String(if result.modified_count != 0 {
+                format!(
+                    "Movie fields ({}) with id: '{}' were successfully patched",
+                    sorted_keys(&fields).join(", "),
+                    id
+                )
+            } else {
+                "Fields have the same value, no patch was performed".to_string()
+            }),
+        );
+        Ok(map_result)
+    }
+
+    async fn set_movie_genres(
+        &self,
+        id: &str,
+        genres: Vec<String>,
+    ) -> Result<Map<String, Value>, AppError> {
+        info!("PUT movies /{{id}}/genres with id: '{}' executed", id);
+        let obj_id = ObjectId::from_str(id)?;
+        let result = self
+            .movies
+            .update_one(
+                doc! { "_id": obj_id },
+                doc! { "$set": { "genres": genres, "updatedAt": DateTime::now() } },
+                None,
+            )
+            .await
+            .map_err(internal_error(format!(
+                "Error setting genres of movie with id: '{}'",
+                id
+            )))?;
+        if result.matched_count == 0 {
+            warn!(
+                "Warn in movies /{{id}}/genres with id: '{}' [{}]",
+                obj_id,
+                AppError::NotExists
+            );
+            return Err(AppError::NotExists);
+        }
+        self.movie_cache.purge(&[obj_id.to_hex()]);
+        let mut map_result: Map<String, Value> = Map::new();
+        map_result.insert(
+            "message".to_string(),
+            Value::String(if result.modified_count != 0 {
+                format!("Movie genres with id: '{}' were successfully updated", id)
+            } else {
+                "Fields have the same value, no update was performed".to_string()
+            }),
+        );
+        Ok(map_result)
+    }
+
+    async fn upsert_movie_by_imdb_id(
+        &self,
+        imdb_id: &str,
+        movie: MovieRequest,
+    ) -> Result<Map<String, Value>, AppError> {
+        info!("PUT movies /byImdbId with imdbId: '{}' executed", imdb_id);
+        if !RE_MOVIE_IMDB_ID.is_match(imdb_id) {
+            error!(
+                "Error in movies /byImdbId with imdbId: '{}' [{}]",
+                imdb_id,
+                AppError::WrongImdbId
+            );
+            return Err(AppError::WrongImdbId);
+        }
+        if self.series_exists_by_imdb_id(imdb_id).await? {
+            error!(
+                "Error in movies /byImdbId with imdbId: '{}' [{}]",
+                imdb_id,
+                AppError::ImdbIdInUse
+            );
+            return Err(AppError::ImdbIdInUse);
+        }
+        let new_id = ObjectId::new();
+        let update = build_upsert_update(movie, new_id)?;
+        let options = FindOneAndUpdateOptions::builder()
+            .upsert(true)
+            .return_document(ReturnDocument::Before)
+            .projection(doc! { "_id": 1 })
+            .build();
+        // Single atomic round trip: no document before the write means it was inserted
+        let movie_before = self
+            .movies
+            .clone_with_type::<Document>()
+            .find_one_and_update(doc! { "imdbId": imdb_id }, update, options)
+            .await
+            .map_err(internal_error(format!(
+                "Error in movies /byImdbId upserting imdbId: '{}'",
+                imdb_id
+            )))?;
+        let (created, id) = match movie_before.and_then(|before| before.get_object_id("_id").ok()) {
+            Some(id) => (false, id),
+            None => (true, new_id),
+        };
+        self.movie_cache.purge(&[id.to_hex()]);
+        let mut map_result: Map<String, Value> = Map::new();
+        map_result.insert("created".to_string(), Value::Bool(created));
+        map_result.insert("id".to_string(), Value::String(id.to_hex()));
+        Ok(map_result)
+    }
+
+    async fn find_release_date_range(&self) -> Result<Map<String, Value>, AppError> {
+        info!("GET movies /dateRange executed");
+        let range_doc = self
+            .movies
+            .clone_with_type::<Document>()
+            .aggregate(release_date_range_pipeline(), None)
+            .await
+            .map_err(internal_error("Error in movies /dateRange"))?
+            .try_next()
+            .await
+            .map_err(internal_error(
+                "Error in movies /dateRange reading the range",
+            ))?;
+        Ok(to_date_range(range_doc))
+    }
+
+    async fn list_movie_imdb_ids(
+        &self,
+        page: Option<u32>,
+        size: Option<u32>,
+    ) -> Result<Map<String, Value>, AppError> {
+        info!("GET movies /imdbIds executed");
+        let page_num = current_page(page);
+        let page_size = match size {
+            Some(size) if size > 0 => size,
+            _ => 10,
+        };
+        let movies = self.movies.clone_with_type::<Document>();
+
+        let total_items = movies
+            .count_documents(None, CountOptions::default())
+            .await
+            .map_err(internal_error("Error counting total of movies"))?;
+
+        let options = FindOptions::builder()
+            .projection(doc! { "_id": 0, "imdbId": 1 })
+            .sort(doc! { "_id": 1 })
+            .skip(page_skip(page_num - 1, page_size)?)
+            .limit(page_size as i64)
+            .build();
+        let cursor = movies
+            .find(None, options)
+            .await
+            .map_err(internal_error("Error finding movie imdbIds"))?;
+        let imdb_id_docs: Vec<Document> = cursor
+            .try_collect()
+            .await
+            .map_err(internal_error("Error collecting movie imdbIds"))?;
+
+        let imdb_id_list = to_imdb_id_list(imdb_id_docs);
+        if imdb_id_list.is_empty() {
+            warn!("Warn in movies /imdbIds [{}]", AppError::Empty);
+            return Err(AppError::Empty);
+        }
+
+        let mut result_map: Map<String, Value> = Map::new();
+        result_map.insert(
+            "imdbIds".to_string(),
+            serde_json::to_value(imdb_id_list).unwrap(),
+        );
+        Ok(build_pagination_map(
+            result_map,
+            page_num,
+            page_size,
+            total_items,
+        ))
+    }
+}
+
+fn to_imdb_id_list(imdb_id_docs: Vec<Document>) -> Vec<String> {
+    imdb_id_docs
+        .iter()
+        .filter_map(|imdb_id_doc| imdb_id_doc.get_str("imdbId").ok())
+        .map(|imdb_id| imdb_id.to_string())
+        .collect()
+}
+
+/// Genres from a `distinct("genres")` call, trimmed, deduplicated and sorted; blanks and
+/// non-string values are dropped.
+pub fn sorted_genres(genres: Vec<Bson>) -> Vec<String> {
+    genres
+        .iter()
+        .filter_map(Bson::as_str)
+        .map(str::trim)
+        .filter(|genre| !genre.is_empty())
+        .map(str::to_string)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// Release dates are `YYYY-M-D` strings whose month and day may be unpadded (`1972-3-24`), so
+/// they are compared as dates and only formatted back to `YYYY-MM-DD` for the bounds. Values
+/// that are not dates are left out.
+fn release_date_range_pipeline() -> Vec<Document> {
+    let part = |index: i32| {
+        doc! { "$toInt": { "$arrayElemAt": [{ "$split": ["$releaseDate", "-"] }, index] } }
+    };
+    let as_day = |bound: &str| doc! { "$dateToString": { "format": "%Y-%m-%d", "date": bound } };
+    vec![
+        doc! { "$match": { "releaseDate": { "$regex": r"^\d{4}-\d{1,2}-\d{1,2}$" } } },
+        doc! { "$group": {
+            "_id": Bson::Null,
+            "earliest": { "$min": { "$dateFromParts": {
+                "year": part(0), "month": part(1), "day": part(2),
+            } } },
+            "latest": { "$max": { "$dateFromParts": {
+                "year": part(0), "month": part(1), "day": part(2),
+            } } },
+        } },
+        doc! { "$project": {
+            "_id": 0,
+            "earliest": as_day("$earliest"),
+            "latest": as_day("$latest"),
+        } },
+    ]
+}
+
+/// No movies (no group emitted) gives `null` bounds.
+fn to_date_range(range_doc: Option<Document>) -> Map<String, Value> {
+    let bound = |key: &str| {
+        range_doc
+            .as_ref()
+            .and_then(|range_doc| range_doc.get_str(key).ok())
+            .map_or(Value::Null, |date| Value::String(date.to_string()))
+    };
+    let mut map_result: Map<String, Value> = Map::new();
+    map_result.insert("earliest".to_string(), bound("earliest"));
+    map_result.insert("latest".to_string(), bound("latest"));
+    map_result
+}
+
+/// Body returned for each created movie, by `/new` and for every created item of `/bulk`.
+pub fn created_movie_result(inserted_id: ObjectId) -> Map<String, Value> {
+    let mut map_result: Map<String, Value> = Map::new();
+    map_result.insert("id".to_string(), Value::String(inserted_id.to_hex()));
+    map_result.insert(
+        "message".to_string(),
+        Value::String(format!(
+            "Movie was successfully created. (id: '{}')",
+            inserted_id
+        )),
+    );
+    map_result
+}
+
+/// Whether each movie of a bulk create should be inserted: not when its imdbId is already
+/// taken by a movie or series, nor when an earlier movie of the same batch uses it.
+fn plan_bulk_insert(imdb_ids: &[String], taken: &HashSet<String>) -> Vec<bool> {
+    let mut seen: HashSet<&str> = HashSet::new();
+    imdb_ids
+        .iter()
+        .map(|imdb_id| !taken.contains(imdb_id) && seen.insert(imdb_id.as_str()))
+        .collect()
+}
+
+/// Outcome of each movie of a bulk create, in request order. `failed_codes` holds the write
+/// error code `insert_many` reported for a movie; a duplicate key lost a race on the index.
+fn bulk_outcomes(
+    ids: &[ObjectId],
+    insertable: &[bool],
+    failed_codes: &HashMap<usize, i32>,
+) -> Vec<Result<ObjectId, AppError>> {
+    ids.iter()
+        .enumerate()
+        .map(|(index, id)| {
+            if !insertable[index] {
+                warn!(
+                    "Warn in movies /bulk with index: {} [{}]",
+                    index,
+                    AppError::AlreadyExists
+                );
+                return Err(AppError::AlreadyExists);
+            }
+            match failed_codes.get(&index) {
+                None => Ok(*id),
+                Some(&DUPLICATE_KEY_CODE) => Err(AppError::AlreadyExists),
+                Some(code) => {
+                    error!(
+                        "Error in movies /bulk with index: {} [write error code {}]",
+                        index, code
+                    );
+                    Err(AppError::InternalServerError)
+                }
+            }
+        })
+        .collect()
+}
+
+fn sorted_keys(fields: &HashMap<String, String>) -> Vec<&str> {
+    let mut keys: Vec<&str> = fields.keys().map(|key| key.as_str()).collect();
+    keys.sort_unstable();
+    keys
+}
+
+/// First key (alphabetically, so the error is stable) that is not a patchable movie field.
+fn first_not_patchable(fields: &HashMap<String, String>) -> Option<&str> {
+    sorted_keys(fields)
+        .into_iter()
+        .find(|key| !is_patchable(MOVIE_PATCHABLE_FIELDS, key))
+}
+
+/// Upsert of `/byImdbId`: the request fields are always set, while `_id` (`new_id`), empty
+/// `reviewIds` and `createdAt` only on insert. The duration is stored in its normalized form.
+fn build_upsert_update(movie: MovieRequest, new_id: ObjectId) -> Result<Document, AppError> {
+    let duration = normalize_duration(&movie.duration)?;
+    let now = DateTime::now();
+    Ok(doc! {
+        "$set": {
+            "title": movie.title,
+            "overview": movie.overview,
+            "duration": duration,
+            "director": movie.director,
+            "releaseDate": movie.release_date,
+            "trailerLink": movie.trailer_link,
+            "genres": movie.genres,
+            "poster": movie.poster,
+            "backdrop": movie.backdrop,
+            "updatedAt": now,
+        },
+        "$setOnInsert": {
+            "_id": new_id,
+            "reviewIds": [],
+            "createdAt": now,
+        },
+    })
+}
+
+/// `$set` of `/update`, refreshing `updatedAt` (`createdAt` is kept).
+fn build_update_set(movie: MovieRequest) -> Result<Document, AppError> {
+    let duration = normalize_duration(&movie.duration)?;
+    Ok(doc! {
+        "imdbId": movie.imdb_id,
+        "title": movie.title,
+        "overview": movie.overview,
+        "duration": duration,
+        "director": movie.director,
+        "releaseDate": movie.release_date,
+        "trailerLink": movie.trailer_link,
+        "genres": movie.genres,
+        "poster": movie.poster,
+        "backdrop": movie.backdrop,
+        "updatedAt": DateTime::now(),
+    })
+}
+
+/// Single `$set` for every pair plus `updatedAt`, each value parsed by `patch_value` so
+/// `/patchMany` stores (and rejects) exactly what `/patch` does.
+fn build_patch_set(fields: &HashMap<String, String>) -> Result<Document, AppError> {
+    let mut set_doc = Document::new();
+    for key in sorted_keys(fields) {
+        set_doc.insert(key, patch_value(key, &fields[key])?);
+    }
+    set_doc.insert("updatedAt", DateTime::now());
+    Ok(set_doc)
+}
+
+/// The movie imdbId format, shared by every write that sets `imdbId`.
+fn check_movie_imdb_id(imdb_id: &str, endpoint: &str) -> Result<(), AppError> {
+    if RE_MOVIE_IMDB_ID.is_match(imdb_id) {
+        return Ok(());
+    }
+    warn!(
+        "Warn in movies {} with imdbId: '{}' [{}]",
+        endpoint,
+        imdb_id,
+        AppError::WrongImdbId
+    );
+    Err(AppError::WrongImdbId)
+}
+
+/// Every duration write stores the normalized "Xh Ym" form, so `Movie` can always read it back
+/// and the duration sort sees a single format.
+fn normalize_duration(duration: &str) -> Result<String, AppError> {
+    duration
+        .parse::<RuntimeDuration>()
+        .map(|duration| duration.to_string())
+        .map_err(|err| {
+            let err = AppError::ValidationAppError(format!("duration: {}", err));
+            warn!("Warn in movies write [{}]", err);
+            err
+        })
+}
+
+/// `val` as stored for `field`: `genres` is a JSON array of strings, `duration` its normalized
+/// form, the rest is text.
+fn patch_value(field: &str, val: &str) -> Result<Bson, AppError> {
+    match field {
+        "genres" => {}
+        "duration" => return normalize_duration(val).map(Bson::String),
+        _ => return Ok(Bson::String(val.to_string())),
+    }
+    match serde_json::from_str::<Vec<String>>(val) {
+        Ok(genres) => Ok(Bson::from(genres)),
+        Err(err) => {
+            let err = AppError::ValidationAppError(format!(
+                "genres: '{}' is not valid, use a JSON array of strings like '[\"Drama\"]' ({})",
+                val, err
+            ));
+            warn!("Warn in movies /patch [{}]", err);
+            Err(err)
+        }
+    }
+}
+
+/// `/findAll` filter: title regex, genre (an element of `genres`) and release year, ANDed.
+fn build_movie_filter(movie_filter: MovieFilter) -> Option<Document> {
+    let mut filter = Document::new();
+    if let Some(title) = movie_filter.title {
+        let regex = Regex {
+            pattern: title,
+            options: String::new(),
+        };
+        filter.insert("title", doc! { "$regex": regex, "$options": "i" });
+    }
+    if let Some(genre) = movie_filter.genre {
+        filter.insert("genres", genre);
+    }
+    if let Some(year) = movie_filter.year {
+        filter.insert("releaseDate", doc! { "$regex": format!("^{}", year) });
+    }
+    (!filter.is_empty()).then_some(filter)
+}
+
+/// The year goes into a regex, so anything but four digits is rejected.
+fn validate_release_year(year: Option<&str>) -> Result<(), AppError> {
+    match year {
+        Some(year) if year.len() != 4 || !year.chars().all(|c| c.is_ascii_digit()) => {
+            let err = AppError::ValidationAppError(format!(
+                "year: '{}' is not valid, use a four-digit year like '2014'",
+                year
+            ));
+            warn!("Warn in movies /findAll [{}]", err);
+            Err(err)
+        }
+        _ => Ok(()),
+    }
+}
+
+async fn aggregate_movies(
+    movies: &Collection<Movie>,
+    pipeline: Vec<Document>,
+) -> Result<Vec<MovieResponse>, AppError> {
+    let cursor = movies
+        .clone_with_type::<Document>()
+        .aggregate(pipeline, None)
+        .await
+        .map_err(internal_error("Error aggregating sorted movies"))?;
+    let movie_docs: Vec<Document> = cursor
+        .try_collect()
+        .await
+        .map_err(internal_error("Error collecting movies"))?;
+
+    movie_docs
+        .into_iter()
+        .map(|movie_doc| {
+            let movie = from_document::<Movie>(movie_doc)
+                .map_err(internal_error("Error reading aggregated movie"))?;
+            MovieResponse::try_from(movie).map_err(internal_error("Error converting movies"))
+        })
+        .collect()
+}
+
+const SCALAR_SORT_FIELDS: [&str; 2] = ["title", "releaseDate"];
+
+#[derive(Debug, PartialEq)]
+enum MovieSort {
+    /// Plain field sort (or none), served by a regular find
+    Find(Option<Document>),
+    /// Sort by the size of `reviewIds`, which needs an aggregation
+    ReviewCount(i32),
+    /// Sort by runtime in minutes; the stored "Xh Ym" string does not sort numerically
+    Duration(i32),
+}
+
+fn parse_movie_sort(sort: Option<&str>, order: Option<&str>) -> Result<MovieSort, AppError> {
+    let direction = match order {
+        None | Some("asc") => 1,
+        Some("desc") => -1,
+        Some(order) => {
+            let err = AppError::ValidationAppError(format!(
+                "order: '{}' is not valid, use 'asc' or 'desc'",
+                order
+            ));
+            warn!("Warn in movies /findAll [{}]", err);
+            return Err(err);
+        }
+    };
+    match sort {
+        None => Ok(MovieSort::Find(None)),
+        Some("reviewCount") => Ok(MovieSort::ReviewCount(direction)),
+        Some("duration") => Ok(MovieSort::Duration(direction)),
+        Some(field) if SCALAR_SORT_FIELDS.contains(&field) => {
+            Ok(MovieSort::Find(Some(doc! { field: direction })))
+        }
+        Some(field) => {
+            warn!(
+                "Warn in movies /findAll with sort: '{}' [{}]",
+                field,
+                AppError::FieldNotAllowed
+            );
+            Err(AppError::FieldNotAllowed)
+        }
+    }
+}
+
+fn review_count_pipeline(
+    filter: Option<Document>,
+    direction: i32,
+    skip: u64,
+    limit: i64,
+) -> Vec<Document> {
+    let review_count = doc! { "$size": { "$ifNull": ["$reviewIds", []] } };
+    computed_sort_pipeline(filter, ("rc", review_count), direction, skip, limit)
+}
+
+/// Runtime in minutes from the stored duration, read as "Xh Ym", "Xh" or "Ym" (older documents
+/// may predate the normalized form), unreadable parts counting as 0.
+fn duration_pipeline(
+    filter: Option<Document>,
+    direction: i32,
+    skip: u64,
+    limit: i64,
+) -> Vec<Document> {
+    let to_int = |input: Document| {
+        doc! { "$convert": { "input": input, "to": "int", "onError": 0, "onNull": 0 } }
+    };
+    // Split on "h": the hours are before it (when there is one), the minutes after it
+    let hours = doc! { "$cond": [
+        { "$gte": [{ "$indexOfCP": ["$duration", "h"] }, 0] },
+        to_int(doc! { "$arrayElemAt": [{ "$split": ["$duration", "h"] }, 0] }),
+        0,
+    ] };
+    let mins = to_int(doc! { "$replaceAll": {
+        "input": { "$trim": { "input": { "$arrayElemAt": [{ "$split": ["$duration", "h"] }, -1] } } },
+        "find": "m",
+        "replacement": "",
+    } });
+    let minutes = doc! { "$add": [{ "$multiply": [hours, 60] }, mins] };
+    computed_sort_pipeline(filter, ("runtime", minutes), direction, skip, limit)
+}
+
+/// Sorts on a temporary `key` field computed per movie, removed again before returning.
+fn computed_sort_pipeline(
+    filter: Option<Document>,
+    (key, expr): (&str, Document),
+    direction: i32,
+    skip: u64,
+    limit: i64,
+) -> Vec<Document> {
+    let mut pipeline = Vec::new();
+    if let Some(filter) = filter {
+        pipeline.push(doc! { "$match": filter });
+    }
+    pipeline.extend([
+        doc! { "$addFields": { key: expr } },
+        // _id keeps the order stable between pages when values tie
+        doc! { "$sort": { key: direction, "_id": 1 } },
+        doc! { "$skip": skip as i64 },
+        doc! { "$limit": limit },
+        doc! { "$project": { key: 0 } },
+    ]);
+    pipeline
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    // Auxiliar Functions
+    // Auxiliar Functions
+
+    fn build_movie_mock(oid: ObjectId) -> Movie {
+        Movie {
+            _id: oid,
+            imdb_id: "tt12345".to_string(),
+            title: "El lobo de Wall Street".to_string(),
+            director: "Martin Scorsese".to_string(),
+            overview: "Testing movies...".to_string(),
+            release_date: "2002-12-4".to_string(),
+            duration: "2h 54m".parse().unwrap(),
+            trailer_link: "https://youtube.com/dasDsdXsDS".to_string(),
+            genres: vec![
+                "Crimen".to_string(),
+                "Drama".to_string(),
+                "Ciencia Ficción".to_string(),
+            ],
+            poster: "https://moviedb.com/lobo/lobo_poster.jpg".to_string(),
+            backdrop: "https://moviedb.com/lobo/lobo_backdrop.jpg".to_string(),
+            review_ids: vec![ObjectId::new()],
+            created_at: DateTime::now(),
+            updated_at: DateTime::now(),
+        }
+    }
+
+    fn build_movie_req_mock() -> MovieRequest {
+        MovieRequest {
+            imdb_id: "tt12345".to_string(),
+            title: "Casino".to_string(),
+            overview: "Película que trata de la mafia de los casinos de Las Vegas".to_string(),
+            director: "Martin Scorsese".to_string(),
+            duration: "2h 54m".to_string(),
+            release_date: "1990-3-4".to_string(),
+            genres: vec!["Crímen".to_string(), "Drama".to_string()],
+            trailer_link: "https://youtube.com/video/ds1281o3l1h".to_string(),
+            poster: "https://moviedb.com/casino/poster.jpg".to_string(),
+            backdrop: "https://moviedb.com/casino/poster.jpg".to_string(),
+        }
+    }
+
+    // Unit Tests
+
+    #[actix_web::test]
+    async fn test_find_all_movies_ok() {
+        let mut mock = MockMovieRepository::new();
+
+        mock.expect_find_all_movies().returning(|_, _, _, _, _| {
+            let mut result_map = serde_json::Map::new();
+            let movie = MovieResponse {
+                imdb_id: "tt12345".to_string(),
+                title: "Casino".to_string(),
+                director: "Martin Scorsese".to_string(),
+                duration: "2h 54m".to_string(),
+                release_date: "1990-3-4".to_string(),
+                poster: "https://moviedb.com/casino/poster.jpg".to_string(),
+                review_count: 0,
+            };
+            let reviewed_movie = MovieResponse::try_from(Movie {
+                review_ids: vec![ObjectId::new(), ObjectId::new(), ObjectId::new()],
+                ..build_movie_mock(ObjectId::new())
+            })
+            .unwrap();
+            result_map.insert(
+                "movies".to_string(),
+                serde_json::to_value(vec![movie, reviewed_movie]).unwrap(),
+            );
+            result_map.insert("currentPage".to_string(), serde_json::to_value(1).unwrap());
+            result_map.insert("totalItems".to_string(), serde_json::to_value(2).unwrap());
+            result_map.insert("totalPages".to_string(), serde_json::to_value(1).unwrap());
+            Ok(result_map)
+        });
+
+        let result = mock
+            .find_all_movies(
+                MovieFilter {
+                    title: Some("Casino".to_string()),
+                    ..MovieFilter::default()
+                },
+                None,
+                None,
+                Some(1),
+                Some(10),
+            )
+            .await;
+
+        let map = result.unwrap();
+        assert_eq!(map.get("currentPage").unwrap(), 1);
+        assert_eq!(map.get("totalItems").unwrap(), 2);
+        assert_eq!(map.get("totalPages").unwrap(), 1);
+
+        let movie_list = map.get("movies").unwrap().as_array().unwrap();
+        assert_eq!(movie_list.len(), 2);
+        assert_eq!(movie_list[0].get("title").unwrap(), "Casino");
+        assert_eq!(movie_list[0].get("director").unwrap(), "Martin Scorsese");
+        assert_eq!(movie_list[0].get("reviewCount").unwrap(), 0);
+        assert_eq!(movie_list[1].get("reviewCount").unwrap(), 3);
+    }
+
+    #[test]
+    fn test_build_movie_filter_genre_only() {
+        let filter = build_movie_filter(MovieFilter {
+            genre: Some("Drama".to_string()),
+            ..MovieFilter::default()
+        })
+        .unwrap();
+        assert_eq!(filter, doc! { "genres": "Drama" });
+    }
+
+    #[test]
+    fn test_build_movie_filter_title_only() {
+        let filter = build_movie_filter(MovieFilter {
+            title: Some("Casino".to_string()),
+            ..MovieFilter::default()
+        })
+        .unwrap();
+        assert_eq!(filter.len(), 1);
+        let title = filter.get_document("title").unwrap();
+        assert!(matches!(
+            title.get("$regex"),
+            Some(Bson::RegularExpression(re)) if re.pattern == "Casino"
+        ));
+        assert_eq!(title.get_str("$options").unwrap(), "i");
+    }
+
+    #[test]
+    fn test_build_movie_filter_title_and_genre() {
+        let filter = build_movie_filter(MovieFilter {
+            title: Some("Casino".to_string()),
+            genre: Some("Drama".to_string()),
+            year: None,
+        })
+        .unwrap();
+        // Both keys in one document, so Mongo matches only movies meeting both conditions
+        assert_eq!(filter.len(), 2);
+        assert!(filter.contains_key("title"));
+        assert_eq!(filter.get_str("genres").unwrap(), "Drama");
+        assert!(build_movie_filter(MovieFilter::default()).is_none());
+    }
+
+    #[test]
+    fn test_build_movie_filter_release_year() {
+        let filter = build_movie_filter(MovieFilter {
+            genre: Some("Drama".to_string()),
+            year: Some("2014".to_string()),
+            ..MovieFilter::default()
+        })
+        .unwrap();
+        assert_eq!(
+            filter,
+            doc! { "genres": "Drama", "releaseDate": { "$regex": "^2014" } }
+        );
+    }
+
+    #[test]
+    fn test_validate_release_year() {
+        assert!(validate_release_year(None).is_ok());
+        assert!(validate_release_year(Some("2014")).is_ok());
+        for year in ["14", "20145", "20a4", "^201", ""] {
+            assert!(validate_release_year(Some(year))
+                .is_err_and(|err| matches!(err, AppError::ValidationAppError(_))));
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_find_all_movies_empty_list() {
+        let mut mock = MockMovieRepository::new();
+
+        mock.expect_find_all_movies()
+            .returning(|_, _, _, _, _| Err(AppError::Empty));
+
+        let result = mock
+            .find_all_movies(MovieFilter::default(), None, None, Some(1), Some(10))
+            .await;
+        assert!(result.is_err_and(|err| err == AppError::Empty));
+    }
+
+    fn build_fields(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(key, val)| (key.to_string(), val.to_string()))
+            .collect()
+    }
+
+    /// Orders `docs` the way MongoDB applies `sort`: key by key, ascending (1) or descending
+    /// (-1), for the string, integer and ObjectId values the movie sorts compare.
+    fn apply_sort(docs: &mut [Document], sort: &Document) {
+        docs.sort_by(|a, b| {
+            sort.iter()
+                .map(|(key, direction)| {
+                    let ordering = match (a.get(key), b.get(key)) {
+                        (Some(Bson::String(x)), Some(Bson::String(y))) => x.cmp(y),
+                        (Some(Bson::Int32(x)), Some(Bson::Int32(y))) => x.cmp(y),
+                        (Some(Bson::ObjectId(x)), Some(Bson::ObjectId(y))) => x.cmp(y),
+                        _ => std::cmp::Ordering::Equal,
+                    };
+                    if direction.as_i32() == Some(-1) {
+                        ordering.reverse()
+                    } else {
+                        ordering
+                    }
+                })
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+    }
+
+    fn build_sort_docs(movies: &[(&str, &str, &str, usize)]) -> Vec<Document> {
+        movies
+            .iter()
+            .map(|(title, release_date, duration, review_count)| {
+                let movie = Movie {
+                    title: title.to_string(),
+                    release_date: release_date.to_string(),
+                    duration: duration.parse().unwrap(),
+                    review_ids: (0..*review_count).map(|_| ObjectId::new()).collect(),
+                    ..build_movie_mock(ObjectId::new())
+                };
+                mongodb::bson::to_document(&movie).unwrap()
+            })
+            .collect()
+    }
+
+    fn sorted_titles(docs: &[Document]) -> Vec<&str> {
+        docs.iter()
+            .map(|doc| doc.get_str("title").unwrap())
+            .collect()
+    }
+
+    /// Evaluates the aggregation operators `duration_pipeline` uses against `movie_doc`.
+    fn eval_expr(expr: &Bson, movie_doc: &Document) -> Bson {
+        let op = match expr {
+            Bson::String(path) if path.starts_with('$') => {
+                return movie_doc.get(&path[1..]).cloned().unwrap_or(Bson::Null);
+            }
+            Bson::Document(op) => op,
+            other => return other.clone(),
+        };
+        let (name, args) = op.iter().next().unwrap();
+        let arg = |key: &str| eval_expr(args.as_document().unwrap().get(key).unwrap(), movie_doc);
+        let list = || -> Vec<Bson> {
+            args.as_array()
+                .unwrap()
+                .iter()
+                .map(|arg| eval_expr(arg, movie_doc))
+                .collect()
+        };
+        let int = |val: &Bson| val.as_i32().unwrap_or_default();
+        match name.as_str() {
+            "$add" => Bson::Int32(list().iter().map(int).sum()),
+            "$multiply" => Bson::Int32(list().iter().map(int).product()),
+            "$gte" => {
+                let args = list();
+                Bson::Boolean(int(&args[0]) >= int(&args[1]))
+            }
+            "$cond" => {
+                let args = list();
+                args[if args[0].as_bool().unwrap() { 1 } else { 2 }].clone()
+            }
+            "$indexOfCP" => match &list()[..] {
+                [Bson::String(text), Bson::String(find)] => {
+                    Bson::Int32(text.find(find.as_str()).map_or(-1, |idx| idx as i32))
+                }
+                _ => Bson::Null,
+            },
+            "$split" => match &list()[..] {
+                [Bson::String(text), Bson::String(sep)] => {
+                    Bson::from(text.split(sep.as_str()).collect::<Vec<_>>())
+                }
+                _ => Bson::Null,
+            },
+            "$arrayElemAt" => match &list()[..] {
+                [Bson::Array(items), idx] => {
+                    let idx = int(idx);
+                    let idx = if idx < 0 {
+                        items.len() as i32 + idx
+                    } else {
+                        idx
+                    };
+                    items.get(idx as usize).cloned().unwrap_or(Bson::Null)
+                }
+                _ => Bson::Null,
+            },
+            "$trim" => match arg("input") {
+                Bson::String(text) => Bson::String(text.trim().to_string()),
+                _ => Bson::Null,
+            },
+            "$replaceAll" => match (arg("input"), arg("find"), arg("replacement")) {
+                (Bson::String(text), Bson::String(find), Bson::String(with)) => {
+                    Bson::String(text.replace(&find, &with))
+                }
+                _ => Bson::Null,
+            },
+            "$convert" => match arg("input") {
+                Bson::Null => arg("onNull"),
+                Bson::String(text) => text
+                    .parse::<i32>()
+                    .map(Bson::Int32)
+                    .unwrap_or_else(|_| arg("onError")),
+                _ => arg("onError"),
+            },
+            _ => panic!("unexpected operator {name}"),
+        }
+    }
+
+    #[test]
+    fn test_build_patch_set_many_fields() {
+        let fields = build_fields(&[
+            ("title", "Casino"),
+            ("director", "Martin Scorsese"),
+            ("genres", r#"["Crimen", "Drama"]"#),
+        ]);
+        assert!(first_not_patchable(&fields).is_none());
+        let mut set_doc = build_patch_set(&fields).unwrap();
+        assert!(set_doc.remove("updatedAt").is_some());
+        assert_eq!(
+            set_doc,
+            doc! {
+                "director": "Martin Scorsese",
+                "genres": ["Crimen", "Drama"],
+                "title": "Casino",
+            }
+        );
+    }
+
+    #[test]
+    fn test_build_patch_set_rejects_malformed_genres() {
+        let fields = build_fields(&[("title", "Casino"), ("genres", "Crimen, Drama")]);
+        let result = build_patch_set(&fields);
+        assert!(
+            matches!(result, Err(AppError::ValidationAppError(ref msg)) if msg.starts_with("genres:"))
+        );
+    }
+
+    #[test]
+    fn test_update_sets_refresh_updated_at() {
+        let created_at = DateTime::from_millis(1_700_000_000_000);
+        let movie = Movie {
+            created_at,
+            updated_at: created_at,
+            ..build_movie_mock(ObjectId::new())
+        };
+        let apply = |set_doc: Document| {
+            assert!(set_doc.get_datetime("updatedAt").is_ok());
+            assert!(!set_doc.contains_key("createdAt"));
+            let mut stored = mongodb::bson::to_document(&movie).unwrap();
+            stored.extend(set_doc);
+            from_document::<Movie>(stored).unwrap()
+        };
+
+        let updated = apply(build_update_set(build_movie_req_mock()).unwrap());
+        assert!(updated.updated_at > movie.updated_at);
+        assert_eq!(updated.created_at, created_at);
+
+        let patched = apply(build_patch_set(&build_fields(&[("title", "Casino")])).unwrap());
+        assert!(patched.updated_at > movie.updated_at);
+        assert_eq!(patched.created_at, created_at);
+        assert_eq!(patched.title, "Casino");
+    }
 
-    fn build_movie_mock(oid: ObjectId) -> Movie {
-        Movie {
-            _id: oid,
-            imdb_id: "tt12345".to_string(),
-            title: "El lobo de Wall Street".to_string(),
-            director: "Martin Scorsese".to_string(),
-            overview: "Testing movies...".to_string(),
-            release_date: "2002-12-4".to_string(),
-            duration: "2h 54m".to_string(),
-            trailer_link: "https://youtube.com/dasDsdXsDS".to_string(),
-            genres: vec![
-                "Crimen".to_string(),
-                "Drama".to_string(),
-                "Ciencia Ficción".to_string(),
-            ],
-            poster: "https://moviedb.com/lobo/lobo_poster.jpg".to_string(),
-            backdrop: "https://moviedb.com/lobo/lobo_backdrop.jpg".to_string(),
-            review_ids: vec![ObjectId::new()],
-        }
+    #[test]
+    fn test_build_update_set_writes_director() {
+        let movie = build_movie_mock(ObjectId::new());
+        let update_set = build_update_set(MovieRequest {
+            director: "Francis Coppola".to_string(),
+            ..build_movie_req_mock()
+        })
+        .unwrap();
+        let mut updated = mongodb::bson::to_document(&movie).unwrap();
+        updated.extend(update_set);
+        let updated: Movie = from_document(updated).unwrap();
+        assert_ne!(movie.director, updated.director);
+        assert_eq!(updated.director, "Francis Coppola");
     }
 
-    fn build_movie_req_mock() -> MovieRequest {
-        MovieRequest {
-            imdb_id: "tt12345".to_string(),
-            title: "Casino".to_string(),
-            overview: "Película que trata de la mafia de los casinos de Las Vegas".to_string(),
-            director: "Martin Scorsese".to_string(),
-            duration: "2h 54m".to_string(),
-            release_date: "1990-3-4".to_string(),
-            genres: vec!["Crímen".to_string(), "Drama".to_string()],
-            trailer_link: "https://youtube.com/video/ds1281o3l1h".to_string(),
-            poster: "https://moviedb.com/casino/poster.jpg".to_string(),
-            backdrop: "https://moviedb.com/casino/poster.jpg".to_string(),
+    #[test]
+    fn test_patch_value_genres_json_array() {
+        assert_eq!(
+            patch_value("genres", r#"["Drama", "Comedia"]"#).unwrap(),
+            Bson::from(vec!["Drama", "Comedia"])
+        );
+        assert_eq!(
+            patch_value("title", "Casino").unwrap(),
+            Bson::String("Casino".to_string())
+        );
+    }
+
+    #[test]
+    fn test_patch_value_genres_malformed() {
+        for val in ["Drama,Comedia", r#"["Drama", 3]"#, r#""Drama""#] {
+            let result = patch_value("genres", val);
+            assert!(
+                matches!(result, Err(AppError::ValidationAppError(ref msg)) if msg.starts_with("genres:")),
+                "{val}"
+            );
         }
     }
 
-    // Unit Tests
+    #[test]
+    fn test_first_not_patchable_names_bad_key() {
+        let fields = build_fields(&[("title", "Casino"), ("reviewIds", "[]")]);
+        assert_eq!(first_not_patchable(&fields), Some("reviewIds"));
+    }
 
     #[actix_web::test]
-    async fn test_find_all_movies_ok() {
+    async fn test_patch_movie_many_ok() {
         let mut mock = MockMovieRepository::new();
+        let oid = ObjectId::new();
 
-        mock.expect_find_all_movies().returning(|_, _, _| {
-            let mut result_map = serde_json::Map::new();
-            let movie = MovieResponse {
-                imdb_id: "tt12345".to_string(),
-                title: "Casino".to_string(),
-                duration: "2h 54m".to_string(),
-                release_date: "1990-3-4".to_string(),
-                poster: "https://moviedb.com/casino/poster.jpg".to_string(),
-            };
-            result_map.insert(
-                "movies".to_string(),
-                serde_json::to_value(vec![movie]).unwrap(),
+        mock.expect_patch_movie_many().returning(|id, fields| {
+            let mut map_result = Map::new();
+            map_result.insert(
+                "message".to_string(),
+                Value::String(format!(
+                    "Movie fields ({}) with id: '{}' were successfully patched",
+                    sorted_keys(&fields).join(", "),
+                    id
+                )),
             );
-            result_map.insert("currentPage".to_string(), serde_json::to_value(1).unwrap());
-            result_map.insert("totalItems".to_string(), serde_json::to_value(1).unwrap());
-            result_map.insert("totalPages".to_string(), serde_json::to_value(1).unwrap());
-            Ok(result_map)
+            Ok(map_result)
         });
 
         let result = mock
-            .find_all_movies(Some("Casino".to_string()), Some(1), Some(10))
+            .patch_movie_many(
+                &oid.to_hex(),
+                build_fields(&[("title", "Casino"), ("director", "Martin Scorsese")]),
+            )
             .await;
+        assert!(result.is_ok_and(|map| map["message"]
+            == format!(
+                "Movie fields (director, title) with id: '{}' were successfully patched",
+                oid.to_hex()
+            )));
+    }
 
-        let map = result.unwrap();
-        assert_eq!(map.get("currentPage").unwrap(), 1);
-        assert_eq!(map.get("totalItems").unwrap(), 1);
-        assert_eq!(map.get("totalPages").unwrap(), 1);
+    #[actix_web::test]
+    async fn test_patch_movie_many_field_not_allowed() {
+        let mut mock = MockMovieRepository::new();
 
-        let movie_list = map.get("movies").unwrap().as_array().unwrap();
-        assert_eq!(movie_list.len(), 1);
-        assert_eq!(movie_list[0].get("title").unwrap(), "Casino");
+        mock.expect_patch_movie_many()
+            .returning(|_, fields| match first_not_patchable(&fields) {
+                Some(_) => Err(AppError::FieldNotAllowed),
+                None => Ok(Map::new()),
+            });
+
+        let result = mock
+            .patch_movie_many(
+                &ObjectId::new().to_hex(),
+                build_fields(&[("title", "Casino"), ("reviewIds", "[]")]),
+            )
+            .await;
+        assert!(result.is_err_and(|err| err == AppError::FieldNotAllowed));
     }
 
     #[actix_web::test]
-    async fn test_find_all_movies_empty_list() {
+    async fn test_set_movie_genres_ok() {
         let mut mock = MockMovieRepository::new();
+        let oid = ObjectId::new();
 
-        mock.expect_find_all_movies()
-            .returning(|_, _, _| Err(AppError::Empty));
+        mock.expect_set_movie_genres().returning(|id, genres| {
+            assert_eq!(genres, vec!["Crimen".to_string(), "Drama".to_string()]);
+            let mut map_result = Map::new();
+            map_result.insert(
+                "message".to_string(),
+                Value::String(format!(
+                    "Movie genres with id: '{}' were successfully updated",
+                    id
+                )),
+            );
+            Ok(map_result)
+        });
 
-        let result = mock.find_all_movies(None, Some(1), Some(10)).await;
-        assert!(result.is_err_and(|err| err == AppError::Empty));
+        let result = mock
+            .set_movie_genres(
+                &oid.to_hex(),
+                vec!["Crimen".to_string(), "Drama".to_string()],
+            )
+            .await;
+        assert!(result.is_ok_and(|map| map["message"]
+            == format!(
+                "Movie genres with id: '{}' were successfully updated",
+                oid.to_hex()
+            )));
+    }
+
+    #[actix_web::test]
+    async fn test_set_movie_genres_not_exists() {
+        let mut mock = MockMovieRepository::new();
+
+        mock.expect_set_movie_genres()
+            .returning(|_, _| Err(AppError::NotExists));
+
+        let result = mock
+            .set_movie_genres(&ObjectId::new().to_hex(), vec!["Drama".to_string()])
+            .await;
+        assert!(result.is_err_and(|err| err == AppError::NotExists));
     }
 
     #[actix_web::test]
@@ -552,9 +1753,11 @@ mod tests {
         let mut mock = MockMovieRepository::new();
 
         mock.expect_find_all_movies()
-            .returning(|_, _, _| Err(AppError::InternalServerError));
+            .returning(|_, _, _, _, _| Err(AppError::InternalServerError));
 
-        let result = mock.find_all_movies(None, Some(1), Some(10)).await;
+        let result = mock
+            .find_all_movies(MovieFilter::default(), None, None, Some(1), Some(10))
+            .await;
         assert!(result.is_err_and(|err| err == AppError::InternalServerError));
     }
 
@@ -610,6 +1813,22 @@ mod tests {
         assert!(result.is_err_and(|err| err == AppError::InternalServerError));
     }
 
+    #[actix_web::test]
+    async fn test_find_movie_projection_by_id_ok() {
+        let mut mock = MockMovieRepository::new();
+        let oid = ObjectId::new();
+
+        mock.expect_find_movie_projection_by_id()
+            .withf(|_, projection| *projection == doc! { "_id": 1, "title": 1 })
+            .returning(move |_, _| Ok(doc! { "_id": oid, "title": "El lobo de Wall Street" }));
+
+        let result = mock
+            .find_movie_projection_by_id(oid.to_string().as_str(), doc! { "_id": 1, "title": 1 })
+            .await;
+        assert!(result.is_ok_and(|movie_doc| movie_doc.len() == 2
+            && movie_doc.get_str("title").unwrap() == "El lobo de Wall Street"));
+    }
+
     #[actix_web::test]
     async fn test_find_movie_by_imdb_id_ok() {
         let mut mock = MockMovieRepository::new();
@@ -625,6 +1844,73 @@ mod tests {
         assert_eq!(movie.title, "El lobo de Wall Street".to_string());
     }
 
+    #[actix_web::test]
+    async fn test_count_movies_filtered() {
+        let mut mock = MockMovieRepository::new();
+
+        mock.expect_count_movies().returning(|filter| {
+            let count = match filter.genre.as_deref() {
+                Some("Drama") => 2,
+                _ => 5,
+            };
+            let mut map_result: Map<String, Value> = Map::new();
+            map_result.insert("count".to_string(), serde_json::to_value(count).unwrap());
+            Ok(map_result)
+        });
+
+        let all = mock.count_movies(MovieFilter::default()).await;
+        assert!(all.is_ok_and(|map| map["count"] == 5));
+        let dramas = mock
+            .count_movies(MovieFilter {
+                genre: Some("Drama".to_string()),
+                ..MovieFilter::default()
+            })
+            .await;
+        assert!(dramas.is_ok_and(|map| map["count"] == 2));
+    }
+
+    #[actix_web::test]
+    async fn test_distinct_genres_sorted_unique() {
+        let mut mock = MockMovieRepository::new();
+
+        mock.expect_distinct_genres().returning(|| {
+            Ok(sorted_genres(vec![
+                Bson::String("Drama".to_string()),
+                Bson::String("Crime".to_string()),
+                Bson::String(" Drama ".to_string()),
+                Bson::String("".to_string()),
+                Bson::Null,
+                Bson::String("Action".to_string()),
+            ]))
+        });
+
+        let result = mock.distinct_genres().await;
+        assert!(result.is_ok_and(|genres| genres == vec!["Action", "Crime", "Drama"]));
+    }
+
+    #[actix_web::test]
+    async fn test_find_random_movie_ok() {
+        let mut mock = MockMovieRepository::new();
+        let oid = ObjectId::new();
+
+        mock.expect_find_random_movie()
+            .returning(move || Ok(build_movie_mock(oid)));
+
+        let result = mock.find_random_movie().await;
+        assert!(result.is_ok_and(|movie| movie._id == oid));
+    }
+
+    #[actix_web::test]
+    async fn test_find_random_movie_empty() {
+        let mut mock = MockMovieRepository::new();
+
+        mock.expect_find_random_movie()
+            .returning(|| Err(AppError::Empty));
+
+        let result = mock.find_random_movie().await;
+        assert!(result.is_err_and(|err| err == AppError::Empty));
+    }
+
     #[actix_web::test]
     async fn test_find_movie_by_imdb_id_wrong_imdb_id() {
         let mut mock = MockMovieRepository::new();
@@ -667,15 +1953,16 @@ mod tests {
 
         mock.expect_create_movie().returning({
             let msg = crt_msg.clone();
-            move |_| {
+            move |movie| {
                 let mut map_result: Map<String, Value> = Map::new();
+                map_result.insert("id".to_string(), Value::String(movie._id.to_hex()));
                 map_result.insert("message".to_string(), Value::String(msg.clone()));
                 Ok(map_result)
             }
         });
 
         let result = mock.create_movie(movie).await;
-        assert!(result.is_ok_and(|map| map["message"] == crt_msg));
+        assert!(result.is_ok_and(|map| map["message"] == crt_msg && map["id"] == oid.to_hex()));
     }
 
     #[actix_web::test]
@@ -704,6 +1991,72 @@ mod tests {
         assert!(result.is_err_and(|err| err == AppError::InternalServerError));
     }
 
+    #[test]
+    fn test_create_movies_mixed_batch_skips_taken_imdb_ids() {
+        let imdb_ids: Vec<String> = ["tt0068646", "tt0110912", "tt0099685", "tt0110912"]
+            .iter()
+            .map(|imdb_id| imdb_id.to_string())
+            .collect();
+        // tt0110912 is already stored, tt0099685 also but as a series
+        let taken: HashSet<String> =
+            HashSet::from(["tt0110912".to_string(), "tt0099685".to_string()]);
+        let insertable = plan_bulk_insert(&imdb_ids, &taken);
+        assert_eq!(insertable, vec![true, false, false, false]);
+
+        let repeated = vec!["tt0068646".to_string(), "tt0068646".to_string()];
+        assert_eq!(
+            plan_bulk_insert(&repeated, &HashSet::new()),
+            vec![true, false]
+        );
+
+        let ids: Vec<ObjectId> = (0..4).map(|_| ObjectId::new()).collect();
+        let outcomes = bulk_outcomes(&ids, &insertable, &HashMap::new());
+        assert_eq!(outcomes[0], Ok(ids[0]));
+        assert!(outcomes[1..]
+            .iter()
+            .all(|outcome| *outcome == Err(AppError::AlreadyExists)));
+    }
+
+    #[test]
+    fn test_create_movies_insert_many_write_errors() {
+        let ids: Vec<ObjectId> = (0..3).map(|_| ObjectId::new()).collect();
+        let failed_codes = HashMap::from([(1, DUPLICATE_KEY_CODE), (2, 121)]);
+
+        let outcomes = bulk_outcomes(&ids, &[true, true, true], &failed_codes);
+        assert_eq!(
+            outcomes,
+            vec![
+                Ok(ids[0]),
+                Err(AppError::AlreadyExists),
+                Err(AppError::InternalServerError)
+            ]
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_create_movies_ok() {
+        let mut mock = MockMovieRepository::new();
+        let movies = vec![
+            build_movie_mock(ObjectId::new()),
+            build_movie_mock(ObjectId::new()),
+        ];
+        let first_oid = movies[0]._id;
+
+        mock.expect_create_movies().returning(|movies| {
+            Ok(movies
+                .iter()
+                .enumerate()
+                .map(|(index, movie)| match index {
+                    0 => Ok(movie._id),
+                    _ => Err(AppError::AlreadyExists),
+                })
+                .collect())
+        });
+
+        let result = mock.create_movies(movies).await.unwrap();
+        assert_eq!(result, vec![Ok(first_oid), Err(AppError::AlreadyExists)]);
+    }
+
     #[actix_web::test]
     async fn test_delete_movie_ok() {
         let mut mock = MockMovieRepository::new();
@@ -736,16 +2089,16 @@ mod tests {
     }
 
     #[actix_web::test]
-    async fn test_delete_movie_not_exists() {
+    async fn test_delete_movie_not_found() {
         let mut mock = MockMovieRepository::new();
         let oid = ObjectId::new();
 
         mock.expect_delete_movie()
-            .returning(|_| Err(AppError::NotExists));
+            .returning(|_| Err(AppError::NotFound));
 
         let result = mock.delete_movie(oid.to_string().as_str()).await;
 
-        assert!(result.is_err_and(|err| err == AppError::NotExists));
+        assert!(result.is_err_and(|err| err == AppError::NotFound));
     }
 
     #[actix_web::test]
@@ -827,6 +2180,18 @@ mod tests {
         assert!(result.is_err_and(|err| err == AppError::InternalServerError));
     }
 
+    #[test]
+    fn test_check_movie_imdb_id() {
+        assert!(check_movie_imdb_id(&build_movie_req_mock().imdb_id, "/update").is_ok());
+        for imdb_id in ["tF123asS", "", "nm0000217", "tt"] {
+            let result = check_movie_imdb_id(imdb_id, "/update");
+            assert!(
+                result.is_err_and(|err| err == AppError::WrongImdbId),
+                "{imdb_id}"
+            );
+        }
+    }
+
     #[actix_web::test]
     async fn test_update_movie_imdb_id_in_use() {
         let mut mock = MockMovieRepository::new();
@@ -953,4 +2318,371 @@ mod tests {
 
         assert!(result.is_err_and(|err| err == AppError::InternalServerError));
     }
+
+    fn build_upsert_result(created: bool, oid: ObjectId) -> Map<String, Value> {
+        let mut map_result: Map<String, Value> = Map::new();
+        map_result.insert("created".to_string(), Value::Bool(created));
+        map_result.insert("id".to_string(), Value::String(oid.to_hex()));
+        map_result
+    }
+
+    #[actix_web::test]
+    async fn test_upsert_movie_by_imdb_id_created() {
+        let mut mock = MockMovieRepository::new();
+        let oid = ObjectId::new();
+
+        mock.expect_upsert_movie_by_imdb_id()
+            .returning(move |_, _| Ok(build_upsert_result(true, oid)));
+
+        let result = mock
+            .upsert_movie_by_imdb_id("tt12345", build_movie_req_mock())
+            .await;
+        assert!(result.is_ok_and(|map| map["created"] == true && map["id"] == oid.to_hex()));
+    }
+
+    #[actix_web::test]
+    async fn test_upsert_movie_by_imdb_id_updated() {
+        let mut mock = MockMovieRepository::new();
+        let oid = ObjectId::new();
+
+        mock.expect_upsert_movie_by_imdb_id()
+            .returning(move |_, _| Ok(build_upsert_result(false, oid)));
+
+        let result = mock
+            .upsert_movie_by_imdb_id("tt12345", build_movie_req_mock())
+            .await;
+        assert!(result.is_ok_and(|map| map["created"] == false && map["id"] == oid.to_hex()));
+    }
+
+    #[test]
+    fn test_build_upsert_update_insert_only_fields() {
+        let new_id = ObjectId::new();
+        let update = build_upsert_update(
+            MovieRequest {
+                duration: "2h 5m".to_string(),
+                ..build_movie_req_mock()
+            },
+            new_id,
+        )
+        .unwrap();
+
+        let set_doc = update.get_document("$set").unwrap();
+        assert_eq!(set_doc.get_str("title").unwrap(), "Casino");
+        assert_eq!(set_doc.get_str("duration").unwrap(), "2h 5m");
+        assert!(set_doc.contains_key("updatedAt"));
+        // The update branch keeps the id, reviews and creation date of the stored movie
+        assert!(!set_doc.contains_key("_id") && !set_doc.contains_key("reviewIds"));
+        let on_insert = update.get_document("$setOnInsert").unwrap();
+        assert_eq!(on_insert.get_object_id("_id").unwrap(), new_id);
+        assert!(on_insert.get_array("reviewIds").unwrap().is_empty());
+        assert!(on_insert.contains_key("createdAt"));
+
+        let bad_duration = build_upsert_update(
+            MovieRequest {
+                duration: "2 horas".to_string(),
+                ..build_movie_req_mock()
+            },
+            new_id,
+        );
+        assert!(bad_duration.is_err_and(|err| matches!(err, AppError::ValidationAppError(_))));
+    }
+
+    #[actix_web::test]
+    async fn test_upsert_movie_by_imdb_id_in_use_by_series() {
+        let mut mock = MockMovieRepository::new();
+
+        mock.expect_upsert_movie_by_imdb_id()
+            .returning(|_, _| Err(AppError::ImdbIdInUse));
+
+        let result = mock
+            .upsert_movie_by_imdb_id("tt12345", build_movie_req_mock())
+            .await;
+        assert!(result.is_err_and(|err| err == AppError::ImdbIdInUse));
+    }
+
+    #[test]
+    fn test_parse_movie_sort() {
+        assert_eq!(parse_movie_sort(None, None).unwrap(), MovieSort::Find(None));
+        assert_eq!(
+            parse_movie_sort(Some("reviewCount"), Some("desc")).unwrap(),
+            MovieSort::ReviewCount(-1)
+        );
+        assert_eq!(
+            parse_movie_sort(Some("title"), None).unwrap(),
+            MovieSort::Find(Some(doc! { "title": 1 }))
+        );
+        assert_eq!(
+            parse_movie_sort(Some("releaseDate"), Some("desc")).unwrap(),
+            MovieSort::Find(Some(doc! { "releaseDate": -1 }))
+        );
+        assert_eq!(
+            parse_movie_sort(Some("duration"), Some("asc")).unwrap(),
+            MovieSort::Duration(1)
+        );
+        assert!(parse_movie_sort(Some("reviewIds"), None)
+            .is_err_and(|err| err == AppError::FieldNotAllowed));
+        assert!(matches!(
+            parse_movie_sort(Some("title"), Some("down")),
+            Err(AppError::ValidationAppError(_))
+        ));
+    }
+
+    #[test]
+    fn test_review_count_pipeline_sorts_by_review_count_desc() {
+        let filter = doc! {"title": { "$regex": "casino", "$options": "i" }};
+        let pipeline = review_count_pipeline(Some(filter.clone()), -1, 20, 10);
+
+        assert_eq!(pipeline[0], doc! { "$match": filter });
+        let rc = pipeline[1]
+            .get_document("$addFields")
+            .unwrap()
+            .get_document("rc")
+            .unwrap();
+        assert!(rc.contains_key("$size"));
+        let sort = pipeline[2].get_document("$sort").unwrap();
+        assert_eq!(sort.keys().next().unwrap(), "rc");
+        assert_eq!(sort.get_i32("rc").unwrap(), -1);
+        assert_eq!(pipeline[3], doc! { "$skip": 20_i64 });
+        assert_eq!(pipeline[4], doc! { "$limit": 10_i64 });
+
+        let unfiltered = review_count_pipeline(None, 1, 0, 10);
+        assert!(unfiltered[0].contains_key("$addFields"));
+    }
+
+    #[test]
+    fn test_find_sort_orders_by_title_and_release_date() {
+        let movies = [
+            ("Heat", "1995-12-15", "2h 50m", 0),
+            ("Apocalypse Now", "1979-08-15", "2h 27m", 0),
+            ("Casino", "1995-11-22", "2h 58m", 0),
+        ];
+
+        let MovieSort::Find(Some(by_title)) = parse_movie_sort(Some("title"), None).unwrap() else {
+            panic!("title is a find sort");
+        };
+        let mut docs = build_sort_docs(&movies);
+        apply_sort(&mut docs, &by_title);
+        assert_eq!(
+            sorted_titles(&docs),
+            vec!["Apocalypse Now", "Casino", "Heat"]
+        );
+
+        let MovieSort::Find(Some(by_release)) =
+            parse_movie_sort(Some("releaseDate"), Some("desc")).unwrap()
+        else {
+            panic!("releaseDate is a find sort");
+        };
+        let mut docs = build_sort_docs(&movies);
+        apply_sort(&mut docs, &by_release);
+        assert_eq!(
+            sorted_titles(&docs),
+            vec!["Heat", "Casino", "Apocalypse Now"]
+        );
+    }
+
+    #[test]
+    fn test_computed_sorts_order_by_value_then_id() {
+        let movies = [
+            ("Casino", "1995-11-22", "2h 58m", 2),
+            ("Uno", "2000-01-01", "58m", 3),
+            ("Heat", "1995-12-15", "2h 50m", 2),
+        ];
+        // What the `$addFields` stage computes for each movie
+        let computed = |key: &str, value: fn(&Movie) -> i32| {
+            let mut docs = build_sort_docs(&movies);
+            for doc in docs.iter_mut() {
+                let movie: Movie = from_document(doc.clone()).unwrap();
+                doc.insert(key, value(&movie));
+            }
+            docs
+        };
+        let sort_stage =
+            |pipeline: Vec<Document>| pipeline[1].get_document("$sort").unwrap().clone();
+
+        // "2h 58m" sorts after "58m" by minutes even though it is before it as a string
+        let mut docs = computed("runtime", |movie| movie.duration.total_minutes() as i32);
+        apply_sort(&mut docs, &sort_stage(duration_pipeline(None, -1, 0, 10)));
+        assert_eq!(sorted_titles(&docs), vec!["Casino", "Heat", "Uno"]);
+
+        // Casino and Heat tie on reviews, so the older `_id` (Casino) stays first in both orders
+        let mut docs = computed("rc", |movie| movie.review_ids.len() as i32);
+        apply_sort(
+            &mut docs,
+            &sort_stage(review_count_pipeline(None, -1, 0, 10)),
+        );
+        assert_eq!(sorted_titles(&docs), vec!["Uno", "Casino", "Heat"]);
+        apply_sort(
+            &mut docs,
+            &sort_stage(review_count_pipeline(None, 1, 0, 10)),
+        );
+        assert_eq!(sorted_titles(&docs), vec!["Casino", "Heat", "Uno"]);
+    }
+
+    #[test]
+    fn test_duration_pipeline_reads_every_duration_form() {
+        let pipeline = duration_pipeline(None, 1, 0, 10);
+        let runtime = pipeline[0]
+            .get_document("$addFields")
+            .unwrap()
+            .get("runtime")
+            .unwrap();
+
+        for (duration, minutes) in [
+            (Some("2h 5m"), 125),
+            (Some("2h"), 120),
+            (Some("45m"), 45),
+            (Some("0h 58m"), 58),
+            (Some("Próximamente"), 0),
+            (None, 0),
+        ] {
+            let mut movie_doc = doc! { "title": "Casino" };
+            if let Some(duration) = duration {
+                movie_doc.insert("duration", duration);
+            }
+            assert_eq!(
+                eval_expr(runtime, &movie_doc),
+                Bson::Int32(minutes),
+                "{duration:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_patch_value_normalizes_duration() {
+        assert_eq!(
+            patch_value("duration", "120m").unwrap(),
+            Bson::String("2h 0m".to_string())
+        );
+        assert_eq!(
+            patch_value("duration", "2h").unwrap(),
+            Bson::String("2h 0m".to_string())
+        );
+        for duration in ["2h 75m", "dos horas", ""] {
+            assert!(
+                matches!(patch_value("duration", duration), Err(AppError::ValidationAppError(ref msg)) if msg.starts_with("duration:")),
+                "{duration}"
+            );
+        }
+        let update = build_update_set(MovieRequest {
+            duration: "45m".to_string(),
+            ..build_movie_req_mock()
+        })
+        .unwrap();
+        assert_eq!(update.get_str("duration").unwrap(), "0h 45m");
+    }
+
+    #[test]
+    fn test_duration_pipeline_sorts_by_runtime_minutes() {
+        let pipeline = duration_pipeline(None, -1, 0, 10);
+
+        let runtime = pipeline[0]
+            .get_document("$addFields")
+            .unwrap()
+            .get_document("runtime")
+            .unwrap();
+        let add = runtime.get_array("$add").unwrap();
+        let hours = add[0]
+            .as_document()
+            .unwrap()
+            .get_array("$multiply")
+            .unwrap();
+        assert_eq!(hours[1], Bson::Int32(60));
+        let sort = pipeline[1].get_document("$sort").unwrap();
+        assert_eq!(sort.keys().collect::<Vec<_>>(), vec!["runtime", "_id"]);
+        assert_eq!(sort.get_i32("runtime").unwrap(), -1);
+        assert_eq!(pipeline[4], doc! { "$project": { "runtime": 0 } });
+    }
+
+    #[test]
+    fn test_release_date_range_pipeline_compares_dates() {
+        let pipeline = release_date_range_pipeline();
+        let pattern = pipeline[0]
+            .get_document("$match")
+            .and_then(|stage| stage.get_document("releaseDate"))
+            .and_then(|date| date.get_str("$regex"))
+            .unwrap();
+        let re = regex::Regex::new(pattern).unwrap();
+        assert!(re.is_match("1972-3-24") && re.is_match("2014-01-17"));
+        assert!(!re.is_match("Próximamente"));
+
+        // The bounds are dates built from the parts, not the raw (possibly unpadded) strings
+        let group = pipeline[1].get_document("$group").unwrap();
+        for (bound, op) in [("earliest", "$min"), ("latest", "$max")] {
+            let date_parts = group
+                .get_document(bound)
+                .and_then(|bound| bound.get_document(op))
+                .and_then(|date| date.get_document("$dateFromParts"))
+                .unwrap();
+            assert_eq!(
+                date_parts.keys().collect::<Vec<_>>(),
+                vec!["year", "month", "day"]
+            );
+        }
+        let project = pipeline[2].get_document("$project").unwrap();
+        assert_eq!(
+            project.get_document("earliest").unwrap(),
+            &doc! { "$dateToString": { "format": "%Y-%m-%d", "date": "$earliest" } }
+        );
+
+        let range = to_date_range(Some(doc! {
+            "earliest": "1941-09-05",
+            "latest": "2024-03-01",
+        }));
+        assert_eq!(range["earliest"], "1941-09-05");
+        assert_eq!(range["latest"], "2024-03-01");
+    }
+
+    #[test]
+    fn test_release_date_range_empty() {
+        let range = to_date_range(None);
+        assert!(range["earliest"].is_null());
+        assert!(range["latest"].is_null());
+    }
+
+    #[test]
+    fn test_to_imdb_id_list_only_imdb_ids() {
+        let imdb_id_docs = vec![
+            doc! { "imdbId": "tt0068646" },
+            doc! { "imdbId": "tt0071562" },
+            doc! { "title": "Sin imdbId" },
+        ];
+
+        assert_eq!(
+            to_imdb_id_list(imdb_id_docs),
+            vec!["tt0068646".to_string(), "tt0071562".to_string()]
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_list_movie_imdb_ids_ok() {
+        let mut mock = MockMovieRepository::new();
+
+        mock.expect_list_movie_imdb_ids().returning(|_, _| {
+            let mut result_map = Map::new();
+            result_map.insert(
+                "imdbIds".to_string(),
+                serde_json::to_value(to_imdb_id_list(vec![doc! { "imdbId": "tt12345" }])).unwrap(),
+            );
+            Ok(build_pagination_map(result_map, 1, 10, 1))
+        });
+
+        let result = mock.list_movie_imdb_ids(Some(1), Some(10)).await.unwrap();
+        assert_eq!(result["imdbIds"], serde_json::json!(["tt12345"]));
+        assert_eq!(result["currentPage"], 1);
+    }
+
+    #[actix_web::test]
+    async fn test_find_release_date_range_ok() {
+        let mut mock = MockMovieRepository::new();
+
+        mock.expect_find_release_date_range().returning(|| {
+            Ok(to_date_range(Some(
+                doc! { "earliest": "1972-03-24", "latest": "2023-07-21" },
+            )))
+        });
+
+        let result = mock.find_release_date_range().await;
+        assert!(result
+            .is_ok_and(|map| map["earliest"] == "1972-03-24" && map["latest"] == "2023-07-21"));
+    }
 }
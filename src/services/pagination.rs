@@ -0,0 +1,155 @@
+use log::warn;
+use serde_json::{Map, Value};
+
+use crate::error::AppError;
+
+/// Largest offset a paginated list accepts; deeper pages should use a filter or a cursor.
+pub const MAX_SKIP: u64 = 1_000_000;
+
+/// Documents to skip for `page` of `size`, computed in `u64` so big pages cannot wrap around.
+pub fn page_skip(page: u32, size: u32) -> Result<u64, AppError> {
+    let skip = page as u64 * size as u64;
+    if skip > MAX_SKIP {
+        let err = AppError::ValidationAppError(format!(
+            "page: page * size ({}) exceeds the maximum offset of {}",
+            skip, MAX_SKIP
+        ));
+        warn!("Warn computing pagination [{}]", err);
+        return Err(err);
+    }
+    Ok(skip)
+}
+
+/// 1-based page requested through `page`, the first one when it is missing or 0.
+pub fn current_page(page: Option<u32>) -> u32 {
+    page.filter(|page| *page > 0).unwrap_or(1)
+}
+
+/// Adds the pagination fields of a `/findAll` response to `items` (the map already holding the
+/// page of results). Pages are 1-based, so the last page is `totalPages`.
+pub fn build_pagination_map(
+    mut items: Map<String, Value>,
+    page: u32,
+    size: u32,
+    total: u64,
+) -> Map<String, Value> {
+    let total_pages = total.div_ceil(size.max(1) as u64);
+    items.insert("currentPage".to_string(), Value::from(page));
+    items.insert("pageSize".to_string(), Value::from(size));
+    items.insert("totalItems".to_string(), Value::from(total));
+    items.insert("totalPages".to_string(), Value::from(total_pages));
+    items.insert("hasPrev".to_string(), Value::Bool(page > 1));
+    items.insert(
+        "hasNext".to_string(),
+        Value::Bool((page as u64) < total_pages),
+    );
+    items
+}
+
+/// `Empty` (204) when the `list_key` page of a `/findAll` response has no items, unless the
+/// client asked for the envelope with an empty list instead (`emptyAsOk=true`).
+pub fn non_empty_page(
+    res: Map<String, Value>,
+    list_key: &str,
+    empty_as_ok: bool,
+) -> Result<Map<String, Value>, AppError> {
+    let is_empty = res
+        .get(list_key)
+        .and_then(Value::as_array)
+        .filter(|items| !items.is_empty())
+        .is_none();
+    if is_empty && !empty_as_ok {
+        warn!("Warn in {} /findAll [{}]", list_key, AppError::Empty);
+        return Err(AppError::Empty);
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Unit Tests
+
+    #[test]
+    fn test_page_skip_ok() {
+        assert_eq!(page_skip(0, 10).unwrap(), 0);
+        assert_eq!(page_skip(3, 10).unwrap(), 30);
+        assert_eq!(page_skip(1000, 1000).unwrap(), MAX_SKIP);
+    }
+
+    #[test]
+    fn test_page_skip_u32_overflow() {
+        // 70_000 * 70_000 wraps to 605_032_704 as u32
+        let result = page_skip(70_000, 70_000);
+        assert!(result.is_err_and(|err| matches!(err, AppError::ValidationAppError(_))));
+        assert!(page_skip(u32::MAX, u32::MAX).is_err());
+    }
+
+    #[test]
+    fn test_build_pagination_map_first_middle_last() {
+        let page_flags = |page: u32| {
+            let map = build_pagination_map(Map::new(), page, 10, 25);
+            assert_eq!(map["pageSize"], 10);
+            assert_eq!(map["totalItems"], 25);
+            assert_eq!(map["totalPages"], 3);
+            assert_eq!(map["currentPage"], page);
+            (map["hasPrev"].clone(), map["hasNext"].clone())
+        };
+        assert_eq!(page_flags(1), (Value::Bool(false), Value::Bool(true)));
+        assert_eq!(page_flags(2), (Value::Bool(true), Value::Bool(true)));
+        assert_eq!(page_flags(3), (Value::Bool(true), Value::Bool(false)));
+
+        let single = build_pagination_map(Map::new(), 1, 10, 4);
+        assert_eq!(single["totalPages"], 1);
+        assert_eq!(single["hasPrev"], false);
+        assert_eq!(single["hasNext"], false);
+    }
+
+    #[test]
+    fn test_first_and_last_page_numbers_consistent() {
+        for page in [None, Some(0), Some(1)] {
+            let first = current_page(page);
+            assert_eq!(page_skip(first - 1, 10).unwrap(), 0);
+            assert_eq!(
+                build_pagination_map(Map::new(), first, 10, 25)["currentPage"],
+                1
+            );
+        }
+        let last = current_page(Some(3));
+        assert_eq!(page_skip(last - 1, 10).unwrap(), 20);
+        let map = build_pagination_map(Map::new(), last, 10, 25);
+        assert_eq!(map["currentPage"], map["totalPages"]);
+        assert_eq!(map["hasNext"], false);
+    }
+
+    #[test]
+    fn test_non_empty_page_both_modes() {
+        let mut items = Map::new();
+        items.insert("series".to_string(), Value::Array(vec![]));
+        let empty_page = build_pagination_map(items, 1, 10, 0);
+
+        let result = non_empty_page(empty_page.clone(), "series", false);
+        assert!(result.is_err_and(|err| err == AppError::Empty));
+
+        let envelope = non_empty_page(empty_page, "series", true).unwrap();
+        assert_eq!(envelope["series"], Value::Array(vec![]));
+        assert_eq!(envelope["totalItems"], 0);
+        assert_eq!(envelope["currentPage"], 1);
+        assert_eq!(envelope["hasNext"], false);
+
+        let mut items = Map::new();
+        items.insert(
+            "series".to_string(),
+            serde_json::json!([{"title": "The Wire"}]),
+        );
+        let page = build_pagination_map(items, 1, 10, 1);
+        assert!(non_empty_page(page, "series", false).is_ok());
+
+        // A response without the list counts as empty too
+        let missing = build_pagination_map(Map::new(), 1, 10, 0);
+        assert!(non_empty_page(missing.clone(), "series", false)
+            .is_err_and(|err| err == AppError::Empty));
+        assert!(non_empty_page(missing, "series", true).is_ok());
+    }
+}
@@ -1,29 +1,50 @@
-use std::str::FromStr;
+use std::{collections::HashMap, fmt::Display, str::FromStr};
 
 use crate::{
-    error::AppError,
-    models::review::{Review, ReviewResponse, ReviewUpdate},
+    error::{internal_error, AppError},
+    models::{
+        fields::{is_patchable, REVIEW_PATCHABLE_FIELDS},
+        review::{
+            validate_half_star_rating, Review, ReviewParent, ReviewResponse, ReviewResponseDoc,
+            ReviewUpdate, ReviewWithParent,
+        },
+        validation::{is_valid_imdb_id, normalize_imdb_id},
+    },
 };
 use async_trait::async_trait;
 use futures_util::{StreamExt, TryStreamExt};
-use lazy_static::lazy_static;
 use log::{error, info, warn};
 use mongodb::{
-    bson::{doc, oid::ObjectId, to_bson, DateTime},
+    bson::{doc, from_document, oid::ObjectId, Bson, DateTime, Document},
     options::{CountOptions, FindOptions},
+    ClientSession, Cursor,
 };
 use serde_json::{Map, Value};
 
-use super::{db::Database, movie_repo::MovieRepository, series_repo::SeriesRepository};
+use super::{
+    cursor::{decode_cursor, encode_cursor},
+    db::Database,
+    movie_repo::MovieRepository,
+    pagination::{build_pagination_map, current_page, page_skip},
+    series_repo::SeriesRepository,
+};
 
-lazy_static! {
-    static ref RE_IMDB_ID: regex::Regex = regex::Regex::new(r"^tt\d+$").unwrap();
-}
+/// Most reviews `/latest` answers, whatever `limit` asks for.
+pub const MAX_LATEST_REVIEWS: u32 = 50;
 
 #[cfg_attr(test, mockall::automock)]
 #[async_trait]
 pub trait ReviewRepository {
     async fn find_all_reviews(
+        &self,
+        q: Option<String>,
+        min_rating: Option<f32>,
+        sort: Option<String>,
+        cursor: Option<String>,
+        page: Option<u32>,
+        size: Option<u32>,
+    ) -> Result<Map<String, Value>, AppError>;
+    async fn find_all_reviews_with_parents(
         &self,
         page: Option<u32>,
         size: Option<u32>,
@@ -31,8 +52,12 @@ pub trait ReviewRepository {
     async fn find_all_reviews_by_imdb_id(
         &self,
         imdb_id: &str,
-    ) -> Result<Vec<ReviewResponse>, AppError>;
+        page: Option<u32>,
+        size: Option<u32>,
+    ) -> Result<Map<String, Value>, AppError>;
+    async fn find_latest_reviews(&self, limit: u32) -> Result<Vec<ReviewResponse>, AppError>;
     async fn find_review_by_id(&self, id: &str) -> Result<ReviewResponse, AppError>;
+    async fn find_reviews_by_ids(&self, ids: &[ObjectId]) -> Result<Vec<ReviewResponse>, AppError>;
     async fn create_review(
         &self,
         review: Review,
@@ -58,101 +83,184 @@ pub trait ReviewRepository {
         field: &str,
         val: &str,
     ) -> Result<Map<String, Value>, AppError>;
+    async fn reassign_review(
+        &self,
+        id: &str,
+        new_imdb_id: &str,
+    ) -> Result<Map<String, Value>, AppError>;
 }
 
 #[async_trait]
 impl ReviewRepository for Database {
     async fn find_all_reviews(
         &self,
+        q: Option<String>,
+        min_rating: Option<f32>,
+        sort: Option<String>,
+        cursor: Option<String>,
         page: Option<u32>,
         size: Option<u32>,
     ) -> Result<Map<String, Value>, AppError> {
         info!("GET reviews /findAll executed");
         let mut result_map: Map<String, Value> = Map::new();
 
-        let page_num = match page {
-            None => 0,
-            Some(page) => {
-                if page > 0 {
-                    page
-                } else {
-                    0
-                }
-            }
-        };
+        let page_num = current_page(page);
         let page_size = match size {
-            None => 10,
-            Some(size) => {
-                if size > 0 {
-                    size
-                } else {
-                    10
-                }
-            }
+            Some(size) if size > 0 => size,
+            _ => 10,
         };
+        let sort_doc = parse_review_sort(sort.as_deref())?;
+        if sort.is_some() && cursor.is_some() {
+            let err = AppError::ValidationAppError(
+                "cursor: only supported with the default sort, use page instead".to_string(),
+            );
+            warn!("Warn in reviews /findAll [{}]", err);
+            return Err(err);
+        }
+        let after_id = cursor.as_deref().map(decode_cursor).transpose()?;
+        let filter = with_min_rating(build_search_filter(q), min_rating)?;
 
         let total_items = self
             .reviews
-            .count_documents(None, CountOptions::default())
+            .count_documents(filter.clone(), CountOptions::default())
             .await
-            .ok()
-            .expect("Error counting total of reviews");
-        let total_pages = (total_items as f64 / page_size as f64).ceil() as u64;
+            .map_err(internal_error("Error counting total of reviews"))?;
 
+        // A cursor replaces the page offset: results continue right after the cursor's review
+        let skip = match after_id {
+            Some(_) => 0,
+            None => page_skip(page_num - 1, page_size)?,
+        };
         let options = FindOptions::builder()
-            .skip((page_num * page_size) as u64)
+            .sort(sort_doc)
+            .skip(skip)
             .limit(page_size as i64)
             .build();
 
-        let cursor = self
+        let db_cursor = self
             .reviews
-            .find(None, options)
-            .await
-            .ok()
-            .expect("Error finding all reviews");
-
-        let review_list: Vec<ReviewResponse> = cursor
-            .map(|review| ReviewResponse::try_from(review.unwrap()))
-            .try_collect()
+            .find(with_cursor(filter, after_id), options)
             .await
-            .ok()
-            .expect("Error collecting reviews");
+            .map_err(internal_error("Error finding all reviews"))?;
 
-        if review_list.is_empty() {
-            warn!("Warn in reviews /findAll [{}]", AppError::Empty.to_string());
-            return Err(AppError::Empty);
-        }
+        let (review_list, skipped_count) = partition_reviews(db_cursor.collect::<Vec<_>>().await);
 
+        // Cursors continue by `_id`, so they are only handed out for the default order
+        let next_cursor = match sort {
+            Some(_) => None,
+            None => next_cursor(&review_list, review_list.len() + skipped_count, page_size),
+        };
+        // An empty page still gets its envelope, the route decides whether it is a 204
         result_map.insert(
             "reviews".to_string(),
             serde_json::to_value(review_list).unwrap(),
         );
         result_map.insert(
-            "currentPage".to_string(),
-            serde_json::to_value(page_num).unwrap(),
+            "skippedCount".to_string(),
+            serde_json::to_value(skipped_count).unwrap(),
         );
         result_map.insert(
-            "totalItems".to_string(),
-            serde_json::to_value(total_items).unwrap(),
+            "nextCursor".to_string(),
+            serde_json::to_value(next_cursor).unwrap(),
         );
-        result_map.insert(
-            "totalPages".to_string(),
-            serde_json::to_value(total_pages).unwrap(),
+        Ok(build_pagination_map(
+            result_map,
+            page_num,
+            page_size,
+            total_items,
+        ))
+    }
+
+    async fn find_all_reviews_with_parents(
+        &self,
+        page: Option<u32>,
+        size: Option<u32>,
+    ) -> Result<Map<String, Value>, AppError> {
+        info!("GET reviews /findAllWithParents executed");
+        let page_num = current_page(page);
+        let page_size = match size {
+            Some(size) if size > 0 => size,
+            _ => 10,
+        };
+
+        let total_items = self
+            .reviews
+            .count_documents(None, CountOptions::default())
+            .await
+            .map_err(internal_error("Error counting total of reviews"))?;
+
+        let pipeline = with_parents_pipeline(
+            self.movies.name(),
+            self.series.name(),
+            page_skip(page_num - 1, page_size)? as i64,
+            page_size as i64,
         );
+        let cursor = match self
+            .reviews
+            .clone_with_type::<Document>()
+            .aggregate(pipeline, None)
+            .await
+        {
+            Ok(cursor) => cursor,
+            Err(_) => {
+                error!(
+                    "Error in reviews /findAllWithParents [{}]",
+                    AppError::InternalServerError
+                );
+                return Err(AppError::InternalServerError);
+            }
+        };
+        let review_docs: Vec<Document> = match cursor.try_collect().await {
+            Ok(review_docs) => review_docs,
+            Err(_) => {
+                error!(
+                    "Error in reviews /findAllWithParents collecting reviews [{}]",
+                    AppError::InternalServerError
+                );
+                return Err(AppError::InternalServerError);
+            }
+        };
+        let review_list: Vec<ReviewWithParent> = review_docs
+            .into_iter()
+            .filter_map(to_review_with_parent)
+            .collect();
+
+        if review_list.is_empty() {
+            warn!("Warn in reviews /findAllWithParents [{}]", AppError::Empty);
+            return Err(AppError::Empty);
+        }
 
-        Ok(result_map)
+        let mut result_map: Map<String, Value> = Map::new();
+        result_map.insert(
+            "reviews".to_string(),
+            serde_json::to_value(review_list).unwrap(),
+        );
+        Ok(build_pagination_map(
+            result_map,
+            page_num,
+            page_size,
+            total_items,
+        ))
     }
 
     async fn find_all_reviews_by_imdb_id(
         &self,
         imdb_id: &str,
-    ) -> Result<Vec<ReviewResponse>, AppError> {
+        page: Option<u32>,
+        size: Option<u32>,
+    ) -> Result<Map<String, Value>, AppError> {
+        let imdb_id = &normalize_imdb_id(imdb_id);
         info!(
             "GET reviews /findAllByImdbId with imdbId: '{}' executed",
             imdb_id
         );
+        let page_num = current_page(page);
+        let page_size = match size {
+            Some(size) if size > 0 => size,
+            _ => 10,
+        };
 
-        if !RE_IMDB_ID.is_match(imdb_id) {
+        if !is_valid_imdb_id(imdb_id) {
             error!(
                 "Error in reviews /findAllByImdbId with imdbId: '{}' [{}]",
                 imdb_id,
@@ -164,7 +272,7 @@ impl ReviewRepository for Database {
         let reviews_id_list: Vec<ObjectId>;
         if self.movie_exists_by_imdb_id(imdb_id).await? {
             reviews_id_list = match self.movies.find_one(doc! {"imdbId": imdb_id}, None).await {
-                Ok(movie) => movie.unwrap().review_ids,
+                Ok(movie) => movie.map(|movie| movie.review_ids).unwrap_or_default(),
                 Err(_) => {
                     error!(
                         "Error in reviews /findAllByImdbId with imdbId: '{}' [{}]",
@@ -176,7 +284,7 @@ impl ReviewRepository for Database {
             }
         } else if self.series_exists_by_imdb_id(imdb_id).await? {
             reviews_id_list = match self.series.find_one(doc! {"imdbId": imdb_id}, None).await {
-                Ok(series) => series.unwrap().review_ids,
+                Ok(series) => series.map(|series| series.review_ids).unwrap_or_default(),
                 Err(_) => {
                     error!(
                         "Error in reviews /findAllByImdbId with imdbId: '{}' [{}]",
@@ -195,19 +303,37 @@ impl ReviewRepository for Database {
             return Err(AppError::NotExists);
         }
 
-        let cursor = self
+        let skip = page_skip(page_num - 1, page_size)?;
+        let total_items = self
             .reviews
-            .find(doc! { "_id": { "$in": reviews_id_list } }, None)
+            .count_documents(
+                doc! { "_id": { "$in": &reviews_id_list } },
+                CountOptions::default(),
+            )
             .await
-            .ok()
-            .expect("Error finding all reviews");
-
-        let review_list: Vec<ReviewResponse> = cursor
-            .map(|review| ReviewResponse::try_from(review.unwrap()))
+            .map_err(internal_error("Error counting reviews by imdbId"))?;
+        let review_docs: Vec<Document> = self
+            .reviews
+            .aggregate(
+                reviews_by_ids_page_pipeline(&reviews_id_list, skip, page_size),
+                None,
+            )
+            .await
+            .map_err(internal_error("Error finding reviews by imdbId"))?
             .try_collect()
             .await
-            .ok()
-            .expect("Error collecting reviews");
+            .map_err(internal_error("Error collecting reviews by imdbId"))?;
+        let review_list: Vec<ReviewResponse> = review_docs
+            .into_iter()
+            .map(|review_doc| {
+                from_document::<Review>(review_doc)
+                    .map_err(|err| err.to_string())
+                    .and_then(|review| {
+                        ReviewResponse::try_from(review).map_err(|err| err.to_string())
+                    })
+            })
+            .collect::<Result<_, _>>()
+            .map_err(internal_error("Error converting reviews by imdbId"))?;
 
         if review_list.is_empty() {
             warn!(
@@ -217,6 +343,32 @@ impl ReviewRepository for Database {
             return Err(AppError::Empty);
         }
 
+        let mut result_map: Map<String, Value> = Map::new();
+        result_map.insert(
+            "reviews".to_string(),
+            serde_json::to_value(review_list).unwrap(),
+        );
+        Ok(build_pagination_map(
+            result_map,
+            page_num,
+            page_size,
+            total_items,
+        ))
+    }
+
+    async fn find_latest_reviews(&self, limit: u32) -> Result<Vec<ReviewResponse>, AppError> {
+        info!("GET reviews /latest with limit: {} executed", limit);
+        let db_cursor = self
+            .reviews
+            .find(None, latest_find_options(limit))
+            .await
+            .map_err(internal_error("Error finding the latest reviews"))?;
+
+        let (review_list, _) = partition_reviews(db_cursor.collect::<Vec<_>>().await);
+        if review_list.is_empty() {
+            warn!("Warn in reviews /latest [{}]", AppError::Empty);
+            return Err(AppError::Empty);
+        }
         Ok(review_list)
     }
 
@@ -224,7 +376,9 @@ impl ReviewRepository for Database {
         info!("GET reviews /findById with id: '{}' executed", id);
         let obj_id = ObjectId::from_str(id)?;
         let review: ReviewResponse = match self.reviews.find_one(doc! {"_id": obj_id}, None).await {
-            Ok(Some(review)) => ReviewResponse::try_from(review).unwrap(),
+            Ok(Some(review)) => ReviewResponse::try_from(review).map_err(internal_error(
+                format!("Error converting review with id: '{}'", id),
+            ))?,
             Ok(None) => {
                 warn!(
                     "Warn in reviews /findById with id: '{}' [{}]",
@@ -245,13 +399,35 @@ impl ReviewRepository for Database {
         Ok(review)
     }
 
+    async fn find_reviews_by_ids(&self, ids: &[ObjectId]) -> Result<Vec<ReviewResponse>, AppError> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let cursor = self
+            .reviews
+            .find(doc! { "_id": { "$in": ids } }, None)
+            .await
+            .map_err(internal_error("Error finding reviews by ids"))?;
+        let reviews: Vec<Review> = cursor
+            .try_collect()
+            .await
+            .map_err(internal_error("Error collecting reviews by ids"))?;
+        let reviews: Vec<ReviewResponse> = reviews
+            .into_iter()
+            .map(ReviewResponse::try_from)
+            .collect::<Result<_, _>>()
+            .map_err(internal_error("Error converting reviews by ids"))?;
+        Ok(in_requested_order(ids, reviews))
+    }
+
     async fn create_review(
         &self,
         review: Review,
         imdb_id: &str,
     ) -> Result<Map<String, Value>, AppError> {
         info!("POST reviews /new executed");
-        let mut map_result: Map<String, Value> = Map::new();
+        check_rating(review.rating, "/new")?;
+        let map_result: Map<String, Value>;
 
         if self.movie_exists_by_imdb_id(imdb_id).await? {
             let movie = match self.movies.find_one(doc! {"imdbId": imdb_id}, None).await {
@@ -275,37 +451,28 @@ impl ReviewRepository for Database {
             };
             let result = self
                 .reviews
-                .insert_one(review, None)
+                .insert_one(with_parent(review, movie._id, "movie"), None)
                 .await
-                .ok()
-                .expect(format!("Error creating review with imdbId: '{}'", imdb_id).as_str());
+                .map_err(internal_error(format!(
+                    "Error creating review with imdbId: '{}'",
+                    imdb_id
+                )))?;
 
             self.movies
                 .update_one(
                     doc! { "_id": movie._id },
-                    doc! { "$push": { "reviewIds": &result.inserted_id } },
+                    add_review_ref(&result.inserted_id),
                     None,
                 )
                 .await
-                .ok()
-                .expect(
-                    format!(
-                        "Error updating movie reviewIds field with imdbId: '{}'",
-                        imdb_id
-                    )
-                    .as_str(),
-                );
-
-            map_result.insert(
-                "message".to_string(),
-                Value::String(
-                    format!(
-                        "Review was successfully created. (id: '{}')",
-                        result.inserted_id.as_object_id().unwrap().to_string()
-                    )
-                    .to_string(),
-                ),
-            );
+                .map_err(internal_error(format!(
+                    "Error updating movie reviewIds field with imdbId: '{}'",
+                    imdb_id
+                )))?;
+
+            self.movie_cache.purge(&[movie._id.to_hex()]);
+            map_result =
+                build_created_review(&result.inserted_id, imdb_id, "movie", &movie.review_ids);
         } else if self.series_exists_by_imdb_id(imdb_id).await? {
             let series = match self.series.find_one(doc! {"imdbId": imdb_id}, None).await {
                 Ok(Some(series)) => series,
@@ -328,37 +495,28 @@ impl ReviewRepository for Database {
             };
             let result = self
                 .reviews
-                .insert_one(review, None)
+                .insert_one(with_parent(review, series._id, "series"), None)
                 .await
-                .ok()
-                .expect(format!("Error creating review with imdbId: '{}'", imdb_id).as_str());
+                .map_err(internal_error(format!(
+                    "Error creating review with imdbId: '{}'",
+                    imdb_id
+                )))?;
 
             self.series
                 .update_one(
                     doc! { "_id": series._id },
-                    doc! { "$push": { "reviewIds": &result.inserted_id } },
+                    add_review_ref(&result.inserted_id),
                     None,
                 )
                 .await
-                .ok()
-                .expect(
-                    format!(
-                        "Error updating series reviewIds field with imdbId: '{}'",
-                        imdb_id
-                    )
-                    .as_str(),
-                );
-
-            map_result.insert(
-                "message".to_string(),
-                Value::String(
-                    format!(
-                        "Review was successfully created. (id: '{}')",
-                        result.inserted_id.as_object_id().unwrap().to_string()
-                    )
-                    .to_string(),
-                ),
-            );
+                .map_err(internal_error(format!(
+                    "Error updating series reviewIds field with imdbId: '{}'",
+                    imdb_id
+                )))?;
+
+            self.series_cache.purge(&[series._id.to_hex()]);
+            map_result =
+                build_created_review(&result.inserted_id, imdb_id, "series", &series.review_ids);
         } else {
             error!(
                 "Error finding movie and series in reviews /findAllByImdbId with imdbId: '{}' [{}]",
@@ -419,8 +577,20 @@ impl ReviewRepository for Database {
     async fn delete_review(&self, id: &str) -> Result<Map<String, Value>, AppError> {
         info!("DELETE reviews /delete with id: '{}' executed", id);
         let obj_id = ObjectId::from_str(id)?;
-        let del_result = match self.reviews.delete_one(doc! {"_id": obj_id}, None).await {
-            Ok(res) => res,
+        let review = match self
+            .reviews
+            .find_one_and_delete(doc! {"_id": obj_id}, None)
+            .await
+        {
+            Ok(Some(review)) => review,
+            Ok(None) => {
+                warn!(
+                    "Warn in review /delete with id: '{}' [{}]",
+                    obj_id,
+                    AppError::NotFound.to_string()
+                );
+                return Err(AppError::NotFound);
+            }
             Err(_) => {
                 error!(
                     "Error in reviews /delete with id: '{}' [{}]",
@@ -431,65 +601,58 @@ impl ReviewRepository for Database {
             }
         };
 
-        let exists_movie_tup = self.movie_exists_by_review_id(obj_id).await?;
-        let exists_series_tup = self.series_exists_by_review_id(obj_id).await?;
-        if exists_movie_tup.0 {
-            self.movies
+        // Reviews created before `parentId` existed still need the scan over both collections
+        let (parent_type, parent_id) = match stored_parent(&review) {
+            Some(parent) => parent,
+            None => match self.movie_exists_by_review_id(obj_id).await? {
+                (true, Some(movie_id)) => ("movie".to_string(), movie_id),
+                _ => match self.series_exists_by_review_id(obj_id).await? {
+                    (true, Some(series_id)) => ("series".to_string(), series_id),
+                    _ => {
+                        error!(
+                            "Error finding movie and series in reviews /delete with id: '{}' [{}]",
+                            id,
+                            AppError::NotExists.to_string()
+                        );
+                        return Err(AppError::NotExists);
+                    }
+                },
+            },
+        };
+
+        if parent_type == "series" {
+            self.series
                 .update_one(
-                    doc! { "_id": exists_movie_tup.1.unwrap() },
+                    doc! { "_id": parent_id },
                     doc! { "$pull": { "reviewIds": obj_id } },
                     None,
                 )
                 .await
-                .ok()
-                .expect(
-                    format!(
-                        "Error removing review id from movie reviewsIds field with id: '{}'",
-                        id
-                    )
-                    .as_str(),
-                );
-        } else if exists_series_tup.0 {
-            self.series
+                .map_err(internal_error(format!(
+                    "Error removing review id from series reviewsIds field with id: '{}'",
+                    id
+                )))?;
+            self.series_cache.purge(&[parent_id.to_hex()]);
+        } else {
+            self.movies
                 .update_one(
-                    doc! { "_id": exists_series_tup.1.unwrap() },
+                    doc! { "_id": parent_id },
                     doc! { "$pull": { "reviewIds": obj_id } },
                     None,
                 )
                 .await
-                .ok()
-                .expect(
-                    format!(
-                        "Error removing review id from series reviewsIds field with id: '{}'",
-                        id
-                    )
-                    .as_str(),
-                );
-        } else {
-            error!(
-                "Error finding movie and series in reviews /delete with id: '{}' [{}]",
-                id,
-                AppError::NotExists.to_string()
-            );
-            return Err(AppError::NotExists);
+                .map_err(internal_error(format!(
+                    "Error removing review id from movie reviewsIds field with id: '{}'",
+                    id
+                )))?;
+            self.movie_cache.purge(&[parent_id.to_hex()]);
         }
 
         let mut map_result: Map<String, Value> = Map::new();
-        if del_result.deleted_count > 0 {
-            map_result.insert(
-                "message".to_string(),
-                Value::String(
-                    format!("Review with id: '{}' was successfully deleted", id).to_string(),
-                ),
-            );
-        } else {
-            warn!(
-                "Warn in review /delete with id: '{}' [{}]",
-                obj_id,
-                AppError::NotExists.to_string()
-            );
-            return Err(AppError::NotExists);
-        }
+        map_result.insert(
+            "message".to_string(),
+            Value::String(format!("Review with id: '{}' was successfully deleted", id).to_string()),
+        );
         Ok(map_result)
     }
 
@@ -499,9 +662,10 @@ impl ReviewRepository for Database {
         review: ReviewUpdate,
     ) -> Result<Map<String, Value>, AppError> {
         info!("PUT reviews /update with id: '{}' executed", id);
+        check_rating(review.rating, "/update")?;
         let obj_id = ObjectId::from_str(id)?;
-        match self.reviews.find_one(doc! { "_id": obj_id }, None).await {
-            Ok(Some(_)) => {}
+        let created_at = match self.reviews.find_one(doc! { "_id": obj_id }, None).await {
+            Ok(Some(stored)) => stored.created_at,
             Ok(None) => {
                 warn!(
                     "Warn in reviews /update with id: '{}' [{}]",
@@ -519,6 +683,7 @@ impl ReviewRepository for Database {
                 return Err(AppError::InternalServerError);
             }
         };
+        let updated_at = DateTime::now();
         let result = self
             .reviews
             .update_one(
@@ -528,23 +693,21 @@ impl ReviewRepository for Database {
                     "title": review.title,
                     "rating": review.rating,
                     "body": review.body,
-                    "updatedAt": DateTime::now(),
+                    "updatedAt": updated_at,
                 }},
                 None,
             )
             .await
-            .ok()
-            .expect(format!("Error updating review with id: '{}'", id).as_str());
-        let mut map_result: Map<String, Value> = Map::new();
-        map_result.insert(
-            "message".to_string(),
-            Value::String(if result.modified_count != 0 {
-                format!("Review with id: '{}' was successfully updated", id)
-            } else {
-                "Fields have the same value, no update was performed".to_string()
-            }),
-        );
-        Ok(map_result)
+            .map_err(internal_error(format!(
+                "Error updating review with id: '{}'",
+                id
+            )))?;
+        let message = if result.modified_count != 0 {
+            format!("Review with id: '{}' was successfully updated", id)
+        } else {
+            "Fields have the same value, no update was performed".to_string()
+        };
+        Ok(build_written_review(message, created_at, updated_at))
     }
 
     async fn patch_review(
@@ -554,9 +717,8 @@ impl ReviewRepository for Database {
         val: &str,
     ) -> Result<Map<String, Value>, AppError> {
         info!("PATCH reviews /patch with id: '{}' executed", id);
-        let fields_vec: Vec<&str> = vec!["title", "rating", "body"];
         let obj_id = ObjectId::from_str(id)?;
-        if !fields_vec.contains(&field) {
+        if !is_patchable(REVIEW_PATCHABLE_FIELDS, field) {
             warn!(
                 "Warn in reviews /patch with id: '{}' [{}]",
                 obj_id,
@@ -564,8 +726,9 @@ impl ReviewRepository for Database {
             );
             return Err(AppError::FieldNotAllowed);
         }
-        match self.reviews.find_one(doc! { "_id": obj_id }, None).await {
-            Ok(Some(_)) => {}
+        let patch_val = patch_value(field, val)?;
+        let created_at = match self.reviews.find_one(doc! { "_id": obj_id }, None).await {
+            Ok(Some(stored)) => stored.created_at,
             Ok(None) => {
                 warn!(
                     "Warn in reviews /patch with id: '{}' [{}]",
@@ -580,82 +743,768 @@ impl ReviewRepository for Database {
                     obj_id,
                     AppError::InternalServerError.to_string()
                 );
-                return Err(AppError::InternalServerError);
+                return Err(AppError::InternalServerError);
+            }
+        };
+        let updated_at = DateTime::now();
+        let result = self
+            .reviews
+            .update_one(
+                doc! { "_id": obj_id },
+                doc! {
+                "$set": doc! {
+                    field: patch_val,
+                    "updatedAt": updated_at,
+                }},
+                None,
+            )
+            .await
+            .map_err(internal_error(format!(
+                "Error patching reviews with id: '{}'",
+                id
+            )))?;
+        let message = if result.modified_count != 0 {
+            format!(
+                "Review {} with id: '{}' was successfully patched",
+                field, id
+            )
+        } else {
+            "Field has the same value, no patch was performed".to_string()
+        };
+        Ok(build_written_review(message, created_at, updated_at))
+    }
+
+    async fn reassign_review(
+        &self,
+        id: &str,
+        new_imdb_id: &str,
+    ) -> Result<Map<String, Value>, AppError> {
+        info!("PATCH reviews /{{id}}/reassign with id: '{}' executed", id);
+        let obj_id = ObjectId::from_str(id)?;
+        let review = match self.reviews.find_one(doc! { "_id": obj_id }, None).await {
+            Ok(Some(review)) => review,
+            Ok(None) => {
+                warn!(
+                    "Warn in reviews /reassign with id: '{}' [{}]",
+                    obj_id,
+                    AppError::NotFound
+                );
+                return Err(AppError::NotFound);
+            }
+            Err(err) => {
+                error!("Error in reviews /reassign with id: '{}' [{}]", obj_id, err);
+                return Err(AppError::InternalServerError);
+            }
+        };
+
+        let new_parent = self.find_parent_by_imdb_id(new_imdb_id).await?;
+        // Reviews created before `parentId` existed still need the scan over both collections
+        let old_parent = match stored_parent(&review) {
+            Some(parent) => Some(parent),
+            None => match self.movie_exists_by_review_id(obj_id).await? {
+                (true, Some(movie_id)) => Some(("movie".to_string(), movie_id)),
+                _ => match self.series_exists_by_review_id(obj_id).await? {
+                    (true, Some(series_id)) => Some(("series".to_string(), series_id)),
+                    _ => None,
+                },
+            },
+        };
+
+        let mut map_result: Map<String, Value> = Map::new();
+        if old_parent.as_ref() == Some(&new_parent) {
+            map_result.insert(
+                "message".to_string(),
+                Value::String(format!(
+                    "Review with id: '{}' already belongs to imdbId: '{}', no reassign was performed",
+                    id, new_imdb_id
+                )),
+            );
+            return Ok(map_result);
+        }
+
+        let mut session = self
+            .client
+            .start_session(None)
+            .await
+            .map_err(internal_error(
+                "Error in reviews /reassign starting session",
+            ))?;
+        session
+            .start_transaction(None)
+            .await
+            .map_err(internal_error(
+                "Error in reviews /reassign starting transaction",
+            ))?;
+        if let Err(err) = self
+            .reassign_in_session(&mut session, obj_id, old_parent.as_ref(), &new_parent)
+            .await
+        {
+            let _ = session.abort_transaction().await;
+            return Err(err);
+        }
+        session.commit_transaction().await.map_err(internal_error(
+            "Error in reviews /reassign committing transaction",
+        ))?;
+        for parent in old_parent.iter().chain([&new_parent]) {
+            self.purge_parent_cache(parent);
+        }
+
+        map_result.insert(
+            "message".to_string(),
+            Value::String(format!(
+                "Review with id: '{}' was successfully reassigned to imdbId: '{}'",
+                id, new_imdb_id
+            )),
+        );
+        map_result.insert("parentType".to_string(), Value::String(new_parent.0));
+        Ok(map_result)
+    }
+}
+
+impl Database {
+    /// Mean rating of the given reviews rounded to 2 decimals, `None` when there are none.
+    pub async fn average_rating(&self, review_ids: &[ObjectId]) -> Result<Option<f64>, AppError> {
+        if review_ids.is_empty() {
+            return Ok(None);
+        }
+        let mut cursor = match self
+            .reviews
+            .aggregate(average_rating_pipeline(review_ids), None)
+            .await
+        {
+            Ok(cursor) => cursor,
+            Err(err) => {
+                error!("Error averaging review ratings [{}]", err);
+                return Err(AppError::InternalServerError);
+            }
+        };
+        match cursor.try_next().await {
+            Ok(group_doc) => Ok(to_average_rating(group_doc)),
+            Err(err) => {
+                error!("Error reading averaged review ratings [{}]", err);
+                Err(AppError::InternalServerError)
+            }
+        }
+    }
+
+    /// The given reviews as embedded by `findById?includeReviews=true`.
+    pub async fn embedded_reviews(
+        &self,
+        review_ids: &[ObjectId],
+    ) -> Result<Vec<ReviewResponseDoc>, AppError> {
+        let reviews = self.find_reviews_by_ids(review_ids).await?;
+        Ok(reviews.into_iter().map(ReviewResponseDoc::from).collect())
+    }
+
+    /// `(parentType, parentId)` of the movie or series with `imdb_id`, `NotExists` when there is
+    /// none.
+    async fn find_parent_by_imdb_id(&self, imdb_id: &str) -> Result<(String, ObjectId), AppError> {
+        let imdb_id = &normalize_imdb_id(imdb_id);
+        let movie = self
+            .movies
+            .find_one(doc! { "imdbId": imdb_id }, None)
+            .await
+            .map_err(internal_error("Error finding the movie of a review"))?;
+        if let Some(movie) = movie {
+            return Ok(("movie".to_string(), movie._id));
+        }
+        let series = self
+            .series
+            .find_one(doc! { "imdbId": imdb_id }, None)
+            .await
+            .map_err(internal_error("Error finding the series of a review"))?;
+        match series {
+            Some(series) => Ok(("series".to_string(), series._id)),
+            None => {
+                warn!(
+                    "Warn finding the parent of a review with imdbId: '{}' [{}]",
+                    imdb_id,
+                    AppError::NotExists
+                );
+                Err(AppError::NotExists)
+            }
+        }
+    }
+
+    /// The writes of a reassign: moves the review id between the `reviewIds` of both parents and
+    /// stamps the new parent on the review, all inside the caller's transaction.
+    async fn reassign_in_session(
+        &self,
+        session: &mut ClientSession,
+        review_id: ObjectId,
+        old_parent: Option<&(String, ObjectId)>,
+        new_parent: &(String, ObjectId),
+    ) -> Result<(), AppError> {
+        for ((parent_type, parent_id), update) in
+            reassign_parent_updates(review_id, old_parent, new_parent)
+        {
+            let context = format!(
+                "Error updating reviewIds of {} with id: '{}'",
+                parent_type, parent_id
+            );
+            if parent_type == "series" {
+                self.series
+                    .update_one_with_session(doc! { "_id": parent_id }, update, None, session)
+                    .await
+                    .map_err(internal_error(context))?;
+            } else {
+                self.movies
+                    .update_one_with_session(doc! { "_id": parent_id }, update, None, session)
+                    .await
+                    .map_err(internal_error(context))?;
+            }
+        }
+        self.reviews
+            .update_one_with_session(
+                doc! { "_id": review_id },
+                reassigned_review_update(new_parent),
+                None,
+                session,
+            )
+            .await
+            .map_err(internal_error(format!(
+                "Error in reviews /reassign updating the parent of review with id: '{}'",
+                review_id
+            )))?;
+        Ok(())
+    }
+
+    /// Evicts a movie or series from its `findById` cache.
+    fn purge_parent_cache(&self, (parent_type, parent_id): &(String, ObjectId)) {
+        if parent_type == "series" {
+            self.series_cache.purge(&[parent_id.to_hex()]);
+        } else {
+            self.movie_cache.purge(&[parent_id.to_hex()]);
+        }
+    }
+
+    /// Cursor over every review in `_id` order. The export streams it line by line instead of
+    /// collecting it, so memory stays flat however big the collection gets.
+    pub async fn export_reviews_cursor(&self) -> Result<Cursor<Review>, AppError> {
+        info!("GET reviews /export.ndjson executed");
+        let options = FindOptions::builder().sort(doc! {"_id": 1}).build();
+        match self.reviews.find(None, options).await {
+            Ok(cursor) => Ok(cursor),
+            Err(err) => {
+                error!("Error in reviews /export.ndjson opening cursor [{}]", err);
+                Err(AppError::InternalServerError)
+            }
+        }
+    }
+}
+
+/// One NDJSON line (a `ReviewResponse` plus `\n`) per cursor result. The headers are already
+/// sent when a result fails, so the error just ends the stream.
+pub fn to_ndjson_line<E: Display>(result: Result<Review, E>) -> Result<String, AppError> {
+    let line = result
+        .map_err(|err| err.to_string())
+        .and_then(|review| ReviewResponse::try_from(review).map_err(|err| err.to_string()))
+        .and_then(|review| serde_json::to_string(&review).map_err(|err| err.to_string()));
+    match line {
+        Ok(line) => Ok(line + "\n"),
+        Err(err) => {
+            error!("Error in reviews /export.ndjson writing review [{}]", err);
+            Err(AppError::InternalServerError)
+        }
+    }
+}
+
+/// Reviews in the order of `ids` (the order they were added to their movie or series), without
+/// the ids that matched no review.
+fn in_requested_order(ids: &[ObjectId], reviews: Vec<ReviewResponse>) -> Vec<ReviewResponse> {
+    let mut by_id: HashMap<ObjectId, ReviewResponse> = reviews
+        .into_iter()
+        .map(|review| (review._id, review))
+        .collect();
+    ids.iter().filter_map(|id| by_id.remove(id)).collect()
+}
+
+/// One page of the reviews in `ids`, kept in the order of `ids` (the order they were added to
+/// their movie or series).
+fn reviews_by_ids_page_pipeline(ids: &[ObjectId], skip: u64, limit: u32) -> Vec<Document> {
+    vec![
+        doc! { "$match": { "_id": { "$in": ids } } },
+        doc! { "$addFields": { "order": { "$indexOfArray": [ids, "$_id"] } } },
+        doc! { "$sort": { "order": 1 } },
+        doc! { "$skip": skip as i64 },
+        doc! { "$limit": limit as i64 },
+        doc! { "$project": { "order": 0 } },
+    ]
+}
+
+fn average_rating_pipeline(review_ids: &[ObjectId]) -> Vec<Document> {
+    vec![
+        doc! { "$match": { "_id": { "$in": review_ids } } },
+        doc! { "$group": { "_id": Bson::Null, "avg": { "$avg": "$rating" } } },
+    ]
+}
+
+/// No matching reviews (no group emitted) or a `null` average gives `None`.
+pub fn to_average_rating(group_doc: Option<Document>) -> Option<f64> {
+    let avg = group_doc?.get_f64("avg").ok()?;
+    Some((avg * 100.0).round() / 100.0)
+}
+
+/// Same rule as the request validators, for callers that skip them (internal code, tests).
+fn check_rating(rating: f32, endpoint: &str) -> Result<(), AppError> {
+    if validate_half_star_rating(rating).is_ok() {
+        return Ok(());
+    }
+    let err = AppError::ValidationAppError(format!(
+        "rating: '{}' is not valid, the rating must be between 0 and 5 in steps of 0.5",
+        rating
+    ));
+    warn!("Warn in reviews {} [{}]", endpoint, err);
+    Err(err)
+}
+
+/// `val` as stored for `field`: the rating is a number in half-star steps, the rest is text.
+fn patch_value(field: &str, val: &str) -> Result<Bson, AppError> {
+    if field != "rating" {
+        return Ok(Bson::String(val.to_string()));
+    }
+    match val.trim().parse::<f32>() {
+        Ok(rating) if validate_half_star_rating(rating).is_ok() => Ok(Bson::Double(rating.into())),
+        _ => {
+            let err = AppError::ValidationAppError(format!(
+                "rating: '{}' is not valid, the rating must be between 0 and 5 in steps of 0.5",
+                val
+            ));
+            warn!("Warn in reviews /patch [{}]", err);
+            Err(err)
+        }
+    }
+}
+
+/// Body of `/update` and `/patch`: the message plus the written `createdAt`/`updatedAt` in the
+/// same RFC 3339 format as the review responses, so clients don't need to re-fetch the review.
+fn build_written_review(
+    message: String,
+    created_at: DateTime,
+    updated_at: DateTime,
+) -> Map<String, Value> {
+    let mut map_result: Map<String, Value> = Map::new();
+    map_result.insert("message".to_string(), Value::String(message));
+    for (key, date) in [("createdAt", created_at), ("updatedAt", updated_at)] {
+        let datetime: chrono::DateTime<chrono::Utc> = date.into();
+        map_result.insert(key.to_string(), Value::String(datetime.to_rfc3339()));
+    }
+    map_result
+}
+
+/// Sort for `/findAll`: insertion (`_id`) order by default, `_id` also breaks ties.
+fn parse_review_sort(sort: Option<&str>) -> Result<Document, AppError> {
+    match sort {
+        None => Ok(doc! { "_id": 1 }),
+        Some("ratingAsc") => Ok(doc! { "rating": 1, "_id": 1 }),
+        Some("ratingDesc") => Ok(doc! { "rating": -1, "_id": 1 }),
+        Some("newest") => Ok(doc! { "createdAt": -1, "_id": -1 }),
+        Some("oldest") => Ok(doc! { "createdAt": 1, "_id": 1 }),
+        Some(sort) => {
+            let err = AppError::ValidationAppError(format!(
+                "sort: '{}' is not valid, use 'ratingAsc', 'ratingDesc', 'newest' or 'oldest'",
+                sort
+            ));
+            warn!("Warn in reviews /findAll [{}]", err);
+            Err(err)
+        }
+    }
+}
+
+/// Newest first, with `limit` kept between 1 and `MAX_LATEST_REVIEWS`.
+fn latest_find_options(limit: u32) -> FindOptions {
+    FindOptions::builder()
+        .sort(doc! { "createdAt": -1, "_id": -1 })
+        .limit(limit.clamp(1, MAX_LATEST_REVIEWS) as i64)
+        .build()
+}
+
+/// `$addToSet` keeps `reviewIds` unique even if a create is retried.
+fn add_review_ref(review_id: &Bson) -> Document {
+    doc! { "$addToSet": { "reviewIds": review_id } }
+}
+
+/// `reviewIds` updates of a reassign: pulled from the old parent (if any), added to the new one.
+fn reassign_parent_updates(
+    review_id: ObjectId,
+    old_parent: Option<&(String, ObjectId)>,
+    new_parent: &(String, ObjectId),
+) -> Vec<((String, ObjectId), Document)> {
+    let mut updates: Vec<((String, ObjectId), Document)> = old_parent
+        .map(|old_parent| {
+            (
+                old_parent.clone(),
+                doc! { "$pull": { "reviewIds": review_id } },
+            )
+        })
+        .into_iter()
+        .collect();
+    updates.push((
+        new_parent.clone(),
+        add_review_ref(&Bson::ObjectId(review_id)),
+    ));
+    updates
+}
+
+/// Points a reassigned review at its new movie or series.
+fn reassigned_review_update((parent_type, parent_id): &(String, ObjectId)) -> Document {
+    doc! { "$set": {
+        "parentId": parent_id,
+        "parentType": parent_type.as_str(),
+        "updatedAt": DateTime::now(),
+    } }
+}
+
+/// Stamps the parent on a review about to be inserted, so deleting it later needs no scan.
+fn with_parent(review: Review, parent_id: ObjectId, parent_type: &str) -> Review {
+    Review {
+        parent_id: Some(parent_id),
+        parent_type: Some(parent_type.to_string()),
+        ..review
+    }
+}
+
+/// `(parentType, parentId)` of a review, `None` when it was stored without them.
+fn stored_parent(review: &Review) -> Option<(String, ObjectId)> {
+    Some((review.parent_type.clone()?, review.parent_id?))
+}
+
+/// Case-insensitive match of the (escaped) search term on the review title or body. A regex
+/// cannot use an index, so a large collection should get a text index and a `$text` query.
+fn build_search_filter(q: Option<String>) -> Option<Document> {
+    let term = q.map(|q| q.trim().to_string()).filter(|q| !q.is_empty())?;
+    let pattern = regex::escape(&term);
+    Some(doc! {
+        "$or": [
+            { "title": { "$regex": pattern.as_str(), "$options": "i" } },
+            { "body": { "$regex": pattern.as_str(), "$options": "i" } },
+        ]
+    })
+}
+
+/// Adds `rating >= min_rating` to `filter`; the minimum has to be a valid rating bound (0 to 5).
+fn with_min_rating(
+    filter: Option<Document>,
+    min_rating: Option<f32>,
+) -> Result<Option<Document>, AppError> {
+    let Some(min_rating) = min_rating else {
+        return Ok(filter);
+    };
+    if !(0.0..=5.0).contains(&min_rating) {
+        let err = AppError::ValidationAppError(format!(
+            "minRating: '{}' is not valid, it must be between 0 and 5",
+            min_rating
+        ));
+        warn!("Warn in reviews /findAll [{}]", err);
+        return Err(err);
+    }
+    let at_least = doc! {"rating": { "$gte": min_rating as f64 }};
+    Ok(Some(match filter {
+        Some(filter) => doc! {"$and": [filter, at_least]},
+        None => at_least,
+    }))
+}
+
+/// `newReviewCount` is derived from the parent's ids read before the `$addToSet`, so the
+/// frontend can update its counter without refetching the title.
+fn build_created_review(
+    inserted_id: &Bson,
+    imdb_id: &str,
+    parent_type: &str,
+    review_ids: &[ObjectId],
+) -> Map<String, Value> {
+    let review_id = inserted_id.as_object_id().unwrap_or_default();
+    let new_review_count = if review_ids.contains(&review_id) {
+        review_ids.len()
+    } else {
+        review_ids.len() + 1
+    };
+    let mut map_result: Map<String, Value> = Map::new();
+    map_result.insert("id".to_string(), Value::String(review_id.to_hex()));
+    map_result.insert(
+        "message".to_string(),
+        Value::String(format!(
+            "Review was successfully created. (id: '{}')",
+            review_id
+        )),
+    );
+    map_result.insert("imdbId".to_string(), Value::String(imdb_id.to_string()));
+    map_result.insert(
+        "parentType".to_string(),
+        Value::String(parent_type.to_string()),
+    );
+    map_result.insert(
+        "newReviewCount".to_string(),
+        serde_json::to_value(new_review_count).unwrap(),
+    );
+    map_result
+}
+
+/// Paginates first so the parent lookups only run for the returned page. A review belongs to
+/// the movie or series whose `reviewIds` contains it; movies win if both (wrongly) reference it.
+fn with_parents_pipeline(movies: &str, series: &str, skip: i64, limit: i64) -> Vec<Document> {
+    let parent_of = |parents: &str, parent_type: &str| {
+        doc! { "$cond": [
+            { "$gt": [{ "$size": format!("${}", parents) }, 0] },
+            {
+                "imdbId": { "$first": format!("${}.imdbId", parents) },
+                "title": { "$first": format!("${}.title", parents) },
+                "type": parent_type,
+            },
+            Bson::Null,
+        ] }
+    };
+    vec![
+        doc! { "$sort": { "_id": 1 } },
+        doc! { "$skip": skip },
+        doc! { "$limit": limit },
+        doc! { "$lookup": {
+            "from": movies,
+            "localField": "_id",
+            "foreignField": "reviewIds",
+            "as": "movieParents",
+        } },
+        doc! { "$lookup": {
+            "from": series,
+            "localField": "_id",
+            "foreignField": "reviewIds",
+            "as": "seriesParents",
+        } },
+        doc! { "$addFields": { "parent": { "$ifNull": [
+            parent_of("movieParents", "movie"),
+            parent_of("seriesParents", "series"),
+        ] } } },
+        doc! { "$project": { "movieParents": 0, "seriesParents": 0 } },
+    ]
+}
+
+fn to_review_with_parent(mut review_doc: Document) -> Option<ReviewWithParent> {
+    let parent = match review_doc.remove("parent") {
+        Some(Bson::Document(parent)) => from_document::<ReviewParent>(parent).ok(),
+        _ => None,
+    };
+    let review = from_document::<Review>(review_doc).ok()?;
+    Some(ReviewWithParent::new(
+        ReviewResponse::try_from(review).ok()?,
+        parent,
+    ))
+}
+
+fn with_cursor(filter: Option<Document>, after_id: Option<ObjectId>) -> Option<Document> {
+    let Some(after_id) = after_id else {
+        return filter;
+    };
+    let after = doc! {"_id": { "$gt": after_id }};
+    Some(match filter {
+        Some(filter) => doc! {"$and": [filter, after]},
+        None => after,
+    })
+}
+
+/// Only full pages get a cursor; a short page means there is nothing left to fetch.
+fn next_cursor(
+    review_list: &[ReviewResponse],
+    fetched_count: usize,
+    page_size: u32,
+) -> Option<String> {
+    if fetched_count < page_size as usize {
+        return None;
+    }
+    review_list.last().map(|review| encode_cursor(&review._id))
+}
+
+/// Converts the raw cursor results, skipping (and logging) the reviews that cannot be read.
+fn partition_reviews<E: std::fmt::Display>(
+    results: Vec<Result<Review, E>>,
+) -> (Vec<ReviewResponse>, usize) {
+    let mut review_list: Vec<ReviewResponse> = Vec::new();
+    let mut skipped_count = 0;
+    for result in results {
+        match result
+            .map_err(|err| err.to_string())
+            .and_then(|review| ReviewResponse::try_from(review).map_err(|err| err.to_string()))
+        {
+            Ok(review) => review_list.push(review),
+            Err(err) => {
+                skipped_count += 1;
+                error!(
+                    "Error in reviews /findAll skipping malformed review [{}]",
+                    err
+                );
             }
-        };
-        let result = self
-            .reviews
-            .update_one(
-                doc! { "_id": obj_id },
-                doc! {
-                "$set": doc! {
-                    field: to_bson(val).unwrap(),
-                    "updatedAt": DateTime::now(),
-                }},
-                None,
-            )
-            .await
-            .ok()
-            .expect(format!("Error patching reviews with id: '{}'", id).as_str());
-        let mut map_result: Map<String, Value> = Map::new();
-        map_result.insert(
-            "message".to_string(),
-            Value::String(if result.modified_count != 0 {
-                format!(
-                    "Review {} with id: '{}' was successfully patched",
-                    field, id
-                )
-            } else {
-                "Field has the same value, no patch was performed".to_string()
-            }),
-        );
-        Ok(map_result)
+        }
     }
+    (review_list, skipped_count)
 }
 
 #[cfg(test)]
 mod tests {
 
+    use mongodb::bson::to_document;
+
     use super::*;
+    use crate::{models::review::ReviewRequest, tests::build_test_db};
 
     // Auxiliary Functions
 
     fn build_review_update_mock() -> ReviewUpdate {
         ReviewUpdate {
             title: "El padrino es una obra de arte.".to_string(),
-            rating: 4,
+            rating: 4.0,
             body: "En esta nueva entrega del padrino vemos a un Michael Corleone mucho más maduro."
                 .to_string(),
         }
     }
 
+    fn build_review_response_mock(oid: ObjectId, title: &str) -> ReviewResponse {
+        ReviewResponse {
+            _id: oid,
+            title: title.to_string(),
+            rating: 4.5,
+            body: "Muy recomendable".to_string(),
+            created_at: DateTime::now(),
+            updated_at: DateTime::now(),
+        }
+    }
+
     // Unit Tests
 
+    #[test]
+    fn test_average_rating_rounded() {
+        // Reviews rated 5, 4 and 2
+        let avg = 11.0 / 3.0;
+        assert_eq!(
+            to_average_rating(Some(doc! { "_id": Bson::Null, "avg": avg })),
+            Some(3.67)
+        );
+        assert_eq!(
+            to_average_rating(Some(doc! { "_id": Bson::Null, "avg": 4.5 })),
+            Some(4.5)
+        );
+        assert_eq!(
+            to_average_rating(Some(doc! { "_id": Bson::Null, "avg": Bson::Null })),
+            None
+        );
+        assert_eq!(to_average_rating(None), None);
+
+        let review_ids = vec![ObjectId::new(), ObjectId::new(), ObjectId::new()];
+        let pipeline = average_rating_pipeline(&review_ids);
+        assert_eq!(
+            pipeline[0],
+            doc! { "$match": { "_id": { "$in": review_ids.clone() } } }
+        );
+        assert_eq!(
+            pipeline[1]
+                .get_document("$group")
+                .unwrap()
+                .get_document("avg")
+                .unwrap(),
+            &doc! { "$avg": "$rating" }
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_to_ndjson_line_emits_one_json_per_line() {
+        let reviews: Vec<Result<Review, AppError>> = ["Obra maestra", "Muy buena", "Aburrida"]
+            .iter()
+            .map(|title| {
+                Ok(Review {
+                    _id: ObjectId::new(),
+                    title: title.to_string(),
+                    rating: 4.0,
+                    body: "Una película con \"comillas\"\ny saltos de línea".to_string(),
+                    created_at: DateTime::now(),
+                    updated_at: DateTime::now(),
+                    parent_id: None,
+                    parent_type: None,
+                })
+            })
+            .collect();
+
+        let export: Vec<String> = futures_util::stream::iter(reviews)
+            .map(to_ndjson_line)
+            .try_collect()
+            .await
+            .unwrap();
+        let body = export.concat();
+
+        assert!(body.ends_with('\n'));
+        let lines: Vec<&str> = body.lines().collect();
+        assert_eq!(lines.len(), 3);
+        for (line, title) in lines.iter().zip(["Obra maestra", "Muy buena", "Aburrida"]) {
+            let review: Value = serde_json::from_str(line).unwrap();
+            assert_eq!(review["title"], title);
+            assert!(review["body"].as_str().unwrap().contains('\n'));
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_to_ndjson_line_cursor_error() {
+        let result = to_ndjson_line::<String>(Err("cursor closed".to_string()));
+        assert!(result.is_err_and(|err| err == AppError::InternalServerError));
+    }
+
+    #[test]
+    fn test_in_requested_order_skips_missing_ids() {
+        let (first, missing, second) = (ObjectId::new(), ObjectId::new(), ObjectId::new());
+        // The database returns the found reviews in its own order
+        let found = vec![
+            build_review_response_mock(second, "Segunda"),
+            build_review_response_mock(first, "Primera"),
+        ];
+
+        let reviews = in_requested_order(&[first, missing, second], found);
+        let titles: Vec<&str> = reviews.iter().map(|review| review.title.as_str()).collect();
+        assert_eq!(titles, vec!["Primera", "Segunda"]);
+    }
+
+    #[test]
+    fn test_reviews_by_ids_page_pipeline() {
+        let ids = vec![ObjectId::new(), ObjectId::new(), ObjectId::new()];
+        let pipeline = reviews_by_ids_page_pipeline(&ids, 20, 10);
+        let id_array: Vec<Bson> = ids.iter().map(|id| Bson::ObjectId(*id)).collect();
+
+        assert_eq!(
+            pipeline,
+            vec![
+                doc! { "$match": { "_id": { "$in": id_array.clone() } } },
+                doc! { "$addFields": { "order": { "$indexOfArray": [id_array, "$_id"] } } },
+                doc! { "$sort": { "order": 1 } },
+                doc! { "$skip": 20_i64 },
+                doc! { "$limit": 10_i64 },
+                doc! { "$project": { "order": 0 } },
+            ]
+        );
+    }
+
     #[actix_web::test]
     async fn test_find_all_reviews_ok() {
         let mut mock = MockReviewRepository::new();
 
-        mock.expect_find_all_reviews().returning(|_, _| {
-            let mut result_map = serde_json::Map::new();
-            let review = ReviewResponse {
-                _id: ObjectId::new(),
-                title: "La mejor película de la historia".to_string(),
-                rating: 5,
-                body: "Esta película es una obra de arte, es perfecta".to_string(),
-                created_at: DateTime::now(),
-                updated_at: DateTime::now(),
-            };
-            result_map.insert(
-                "reviews".to_string(),
-                serde_json::to_value(vec![review]).unwrap(),
-            );
-            result_map.insert("currentPage".to_string(), serde_json::to_value(1).unwrap());
-            result_map.insert("totalItems".to_string(), serde_json::to_value(1).unwrap());
-            result_map.insert("totalPages".to_string(), serde_json::to_value(1).unwrap());
-            Ok(result_map)
-        });
+        mock.expect_find_all_reviews()
+            .returning(|_, _, _, _, _, _| {
+                let mut result_map = serde_json::Map::new();
+                let review = ReviewResponse {
+                    _id: ObjectId::new(),
+                    title: "La mejor película de la historia".to_string(),
+                    rating: 5.0,
+                    body: "Esta película es una obra de arte, es perfecta".to_string(),
+                    created_at: DateTime::now(),
+                    updated_at: DateTime::now(),
+                };
+                result_map.insert(
+                    "reviews".to_string(),
+                    serde_json::to_value(vec![review]).unwrap(),
+                );
+                result_map.insert("currentPage".to_string(), serde_json::to_value(1).unwrap());
+                result_map.insert("totalItems".to_string(), serde_json::to_value(1).unwrap());
+                result_map.insert("totalPages".to_string(), serde_json::to_value(1).unwrap());
+                Ok(result_map)
+            });
 
-        let result = mock.find_all_reviews(Some(1), Some(10)).await;
+        let result = mock
+            .find_all_reviews(None, None, None, None, Some(1), Some(10))
+            .await;
         assert!(result.is_ok());
 
         let map = result.unwrap();
@@ -671,14 +1520,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_review_sort() {
+        assert_eq!(parse_review_sort(None).unwrap(), doc! { "_id": 1 });
+        assert_eq!(
+            parse_review_sort(Some("ratingAsc")).unwrap(),
+            doc! { "rating": 1, "_id": 1 }
+        );
+        assert_eq!(
+            parse_review_sort(Some("ratingDesc")).unwrap(),
+            doc! { "rating": -1, "_id": 1 }
+        );
+        assert_eq!(
+            parse_review_sort(Some("newest")).unwrap(),
+            doc! { "createdAt": -1, "_id": -1 }
+        );
+        assert_eq!(
+            parse_review_sort(Some("oldest")).unwrap(),
+            doc! { "createdAt": 1, "_id": 1 }
+        );
+        assert!(parse_review_sort(Some("rating"))
+            .is_err_and(|err| matches!(err, AppError::ValidationAppError(_))));
+    }
+
     #[actix_web::test]
     async fn test_find_all_reviews_empty_list() {
         let mut mock = MockReviewRepository::new();
 
         mock.expect_find_all_reviews()
-            .returning(|_, _| Err(AppError::Empty));
+            .returning(|_, _, _, _, _, _| Err(AppError::Empty));
 
-        let result = mock.find_all_reviews(Some(1), Some(10)).await;
+        let result = mock
+            .find_all_reviews(None, None, None, None, Some(1), Some(10))
+            .await;
         assert!(result.is_err_and(|err| err == AppError::Empty));
     }
 
@@ -687,9 +1561,11 @@ mod tests {
         let mut mock = MockReviewRepository::new();
 
         mock.expect_find_all_reviews()
-            .returning(|_, _| Err(AppError::InternalServerError));
+            .returning(|_, _, _, _, _, _| Err(AppError::InternalServerError));
 
-        let result = mock.find_all_reviews(Some(1), Some(10)).await;
+        let result = mock
+            .find_all_reviews(None, None, None, None, Some(1), Some(10))
+            .await;
         assert!(result.is_err_and(|err| err == AppError::InternalServerError));
     }
 
@@ -697,24 +1573,44 @@ mod tests {
     async fn test_find_all_reviews_by_imdb_id_ok() {
         let mut mock = MockReviewRepository::new();
 
-        mock.expect_find_all_reviews_by_imdb_id().returning(|_| {
-            let review = ReviewResponse {
-                _id: ObjectId::new(),
-                title: "La mejor película de la historia".to_string(),
-                rating: 5,
-                body: "Esta película es una obra de arte, es perfecta".to_string(),
-                created_at: DateTime::now(),
-                updated_at: DateTime::now(),
-            };
-            Ok(vec![review])
-        });
+        mock.expect_find_all_reviews_by_imdb_id()
+            .returning(|_, page, size| {
+                let review = ReviewResponse {
+                    _id: ObjectId::new(),
+                    title: "La mejor película de la historia".to_string(),
+                    rating: 5.0,
+                    body: "Esta película es una obra de arte, es perfecta".to_string(),
+                    created_at: DateTime::now(),
+                    updated_at: DateTime::now(),
+                };
+                let mut result_map = Map::new();
+                result_map.insert(
+                    "reviews".to_string(),
+                    serde_json::to_value(vec![review]).unwrap(),
+                );
+                result_map.insert("currentPage".to_string(), Value::from(page.unwrap()));
+                result_map.insert("totalItems".to_string(), Value::from(21));
+                result_map.insert(
+                    "totalPages".to_string(),
+                    Value::from((21_f64 / size.unwrap() as f64).ceil() as u64),
+                );
+                Ok(result_map)
+            });
 
-        let result = mock.find_all_reviews_by_imdb_id("tt1234").await;
+        let result = mock
+            .find_all_reviews_by_imdb_id("tt1234", Some(2), Some(10))
+            .await;
         assert!(result.is_ok());
 
-        let review_list = result.unwrap();
-        assert_eq!(review_list.len(), 1);
-        assert_eq!(review_list[0].title, "La mejor película de la historia");
+        let result_map = result.unwrap();
+        assert_eq!(result_map["reviews"].as_array().unwrap().len(), 1);
+        assert_eq!(
+            result_map["reviews"][0]["title"],
+            "La mejor película de la historia"
+        );
+        assert_eq!(result_map["currentPage"], 2);
+        assert_eq!(result_map["totalItems"], 21);
+        assert_eq!(result_map["totalPages"], 3);
     }
 
     #[actix_web::test]
@@ -722,9 +1618,9 @@ mod tests {
         let mut mock = MockReviewRepository::new();
 
         mock.expect_find_all_reviews_by_imdb_id()
-            .returning(|_| Err(AppError::WrongImdbId));
+            .returning(|_, _, _| Err(AppError::WrongImdbId));
 
-        let result = mock.find_all_reviews_by_imdb_id("tt1234").await;
+        let result = mock.find_all_reviews_by_imdb_id("tt1234", None, None).await;
 
         assert!(result.is_err_and(|err| err == AppError::WrongImdbId));
     }
@@ -734,9 +1630,9 @@ mod tests {
         let mut mock = MockReviewRepository::new();
 
         mock.expect_find_all_reviews_by_imdb_id()
-            .returning(|_| Err(AppError::InternalServerError));
+            .returning(|_, _, _| Err(AppError::InternalServerError));
 
-        let result = mock.find_all_reviews_by_imdb_id("tt1234").await;
+        let result = mock.find_all_reviews_by_imdb_id("tt1234", None, None).await;
 
         assert!(result.is_err_and(|err| err == AppError::InternalServerError));
     }
@@ -746,9 +1642,9 @@ mod tests {
         let mut mock = MockReviewRepository::new();
 
         mock.expect_find_all_reviews_by_imdb_id()
-            .returning(|_| Err(AppError::NotExists));
+            .returning(|_, _, _| Err(AppError::NotExists));
 
-        let result = mock.find_all_reviews_by_imdb_id("tt1234").await;
+        let result = mock.find_all_reviews_by_imdb_id("tt1234", None, None).await;
 
         assert!(result.is_err_and(|err| err == AppError::NotExists));
     }
@@ -758,13 +1654,42 @@ mod tests {
         let mut mock = MockReviewRepository::new();
 
         mock.expect_find_all_reviews_by_imdb_id()
-            .returning(|_| Err(AppError::Empty));
+            .returning(|_, _, _| Err(AppError::Empty));
 
-        let result = mock.find_all_reviews_by_imdb_id("tt1234").await;
+        let result = mock.find_all_reviews_by_imdb_id("tt1234", None, None).await;
 
         assert!(result.is_err_and(|err| err == AppError::Empty));
     }
 
+    #[test]
+    fn test_latest_find_options_newest_first_and_capped() {
+        let options = latest_find_options(5);
+        assert_eq!(options.sort, Some(doc! { "createdAt": -1, "_id": -1 }));
+        assert_eq!(options.limit, Some(5));
+
+        assert_eq!(latest_find_options(500).limit, Some(50));
+        assert_eq!(latest_find_options(0).limit, Some(1));
+    }
+
+    #[actix_web::test]
+    async fn test_find_latest_reviews_ok() {
+        let mut mock = MockReviewRepository::new();
+        let (older, newer) = (ObjectId::new(), ObjectId::new());
+
+        mock.expect_find_latest_reviews().returning(move |limit| {
+            let reviews = vec![
+                build_review_response_mock(newer, "Lo último"),
+                build_review_response_mock(older, "Lo anterior"),
+            ];
+            Ok(reviews.into_iter().take(limit as usize).collect())
+        });
+
+        let result = mock.find_latest_reviews(5).await.unwrap();
+        let ids: Vec<ObjectId> = result.iter().map(|review| review._id).collect();
+        assert_eq!(ids, vec![newer, older]);
+        assert_eq!(mock.find_latest_reviews(1).await.unwrap().len(), 1);
+    }
+
     #[actix_web::test]
     async fn test_find_review_by_id_ok() {
         let mut mock = MockReviewRepository::new();
@@ -774,7 +1699,7 @@ mod tests {
             let review = ReviewResponse {
                 _id: oid,
                 title: "La mejor película de la historia".to_string(),
-                rating: 5,
+                rating: 5.0,
                 body: "Esta película es una obra de arte, es perfecta".to_string(),
                 created_at: DateTime::now(),
                 updated_at: DateTime::now(),
@@ -784,7 +1709,7 @@ mod tests {
 
         let result = mock.find_review_by_id(oid.to_string().as_str()).await;
         assert!(result.is_ok_and(|res| {
-            res.title == "La mejor película de la historia" && res.rating == 5
+            res.title == "La mejor película de la historia" && res.rating == 5.0
         }));
     }
 
@@ -834,29 +1759,66 @@ mod tests {
         let review = Review {
             _id: oid,
             title: "La mejor película de la historia".to_string(),
-            rating: 5,
+            rating: 5.0,
             body: "Esta película es una obra de arte, es perfecta".to_string(),
             created_at: DateTime::now(),
             updated_at: DateTime::now(),
+            parent_id: None,
+            parent_type: None,
         };
 
-        mock.expect_create_review().returning(|review, _| {
-            let mut map_result: Map<String, Value> = Map::new();
-            map_result.insert(
-                "message".to_string(),
-                Value::String(format!(
-                    "Review was successfully created. (id: '{}')",
-                    review._id
-                )),
-            );
-            Ok(map_result)
+        mock.expect_create_review().returning(|review, imdb_id| {
+            Ok(build_created_review(
+                &Bson::ObjectId(review._id),
+                imdb_id,
+                "movie",
+                &[ObjectId::new(), ObjectId::new()],
+            ))
         });
 
         let result = mock.create_review(review, "tt12345").await;
 
-        assert!(result.is_ok_and(
-            |map| map["message"] == format!("Review was successfully created. (id: '{}')", oid)
-        ));
+        assert!(result.is_ok_and(|map| {
+            map["id"] == oid.to_hex()
+                && map["message"] == format!("Review was successfully created. (id: '{}')", oid)
+                && map["imdbId"] == "tt12345"
+                && map["parentType"] == "movie"
+                && map["newReviewCount"] == 3
+        }));
+    }
+
+    #[actix_web::test]
+    async fn test_create_and_update_review_reject_out_of_range_rating() {
+        // The driver only connects on the first operation, so the rating check runs without a server
+        let db = build_test_db().await;
+        let now = DateTime::now();
+        let review = Review {
+            _id: ObjectId::new(),
+            title: "Una obra maestra".to_string(),
+            rating: 7.0,
+            body: "Imprescindible".to_string(),
+            created_at: now,
+            updated_at: now,
+            parent_id: None,
+            parent_type: None,
+        };
+        let expected = AppError::ValidationAppError(
+            "rating: '7' is not valid, the rating must be between 0 and 5 in steps of 0.5"
+                .to_string(),
+        );
+
+        let result = db.create_review(review, "tt0068646").await;
+        assert!(result.is_err_and(|err| err == expected));
+
+        let update = ReviewUpdate {
+            title: "Una obra maestra".to_string(),
+            rating: 7.0,
+            body: "Imprescindible".to_string(),
+        };
+        let result = db
+            .update_review(ObjectId::new().to_hex().as_str(), update)
+            .await;
+        assert!(result.is_err_and(|err| err == expected));
     }
 
     #[actix_web::test]
@@ -866,10 +1828,12 @@ mod tests {
         let review = Review {
             _id: oid,
             title: "La mejor película de la historia".to_string(),
-            rating: 5,
+            rating: 5.0,
             body: "Esta película es una obra de arte, es perfecta".to_string(),
             created_at: DateTime::now(),
             updated_at: DateTime::now(),
+            parent_id: None,
+            parent_type: None,
         };
 
         mock.expect_create_review()
@@ -887,10 +1851,12 @@ mod tests {
         let review = Review {
             _id: oid,
             title: "La mejor película de la historia".to_string(),
-            rating: 5,
+            rating: 5.0,
             body: "Esta película es una obra de arte, es perfecta".to_string(),
             created_at: DateTime::now(),
             updated_at: DateTime::now(),
+            parent_id: None,
+            parent_type: None,
         };
 
         mock.expect_create_review()
@@ -901,6 +1867,23 @@ mod tests {
         assert!(result.is_err_and(|err| err == AppError::InternalServerError));
     }
 
+    #[test]
+    fn test_create_review_sets_parent() {
+        let movie_oid = ObjectId::new();
+        let review = Review::try_from(ReviewRequest {
+            title: "La mejor película de la historia".to_string(),
+            rating: 5.0,
+            body: "Esta película es una obra de arte, es perfecta".to_string(),
+            imdb_id: "tt12345".to_string(),
+        })
+        .unwrap();
+        assert!(review.parent_id.is_none() && review.parent_type.is_none());
+
+        let review_doc = to_document(&with_parent(review, movie_oid, "movie")).unwrap();
+        assert_eq!(review_doc.get_object_id("parentId").unwrap(), movie_oid);
+        assert_eq!(review_doc.get_str("parentType").unwrap(), "movie");
+    }
+
     #[actix_web::test]
     async fn test_movie_exists_by_imdb_id_true() {
         let mut mock = MockReviewRepository::new();
@@ -921,7 +1904,7 @@ mod tests {
             .returning(|_| Ok((false, None)));
 
         let result = mock.movie_exists_by_review_id(ObjectId::new()).await;
-        assert!(result.is_ok_and(|tup| { !tup.0 && tup.1 == None }));
+        assert!(result.is_ok_and(|tup| { !tup.0 && tup.1.is_none() }));
     }
 
     #[actix_web::test]
@@ -956,7 +1939,7 @@ mod tests {
             .returning(|_| Ok((false, None)));
 
         let result = mock.series_exists_by_review_id(ObjectId::new()).await;
-        assert!(result.is_ok_and(|tup| { !tup.0 && tup.1 == None }));
+        assert!(result.is_ok_and(|tup| { !tup.0 && tup.1.is_none() }));
     }
 
     #[actix_web::test]
@@ -1005,16 +1988,16 @@ mod tests {
     }
 
     #[actix_web::test]
-    async fn test_delete_review_not_exists() {
+    async fn test_delete_review_not_found() {
         let mut mock = MockReviewRepository::new();
         let oid = ObjectId::new();
 
         mock.expect_delete_review()
-            .returning(|_| Err(AppError::NotExists));
+            .returning(|_| Err(AppError::NotFound));
 
         let result = mock.delete_review(oid.to_string().as_str()).await;
 
-        assert!(result.is_err_and(|err| err == AppError::NotExists));
+        assert!(result.is_err_and(|err| err == AppError::NotFound));
     }
 
     #[actix_web::test]
@@ -1030,6 +2013,27 @@ mod tests {
         assert!(result.is_err_and(|err| err == AppError::InternalServerError));
     }
 
+    #[test]
+    fn test_delete_review_uses_stored_parent() {
+        let series_oid = ObjectId::new();
+        let legacy_doc = doc! {
+            "_id": ObjectId::new(),
+            "title": "Una serie increíble",
+            "rating": 4,
+            "body": "Muy recomendable",
+            "createdAt": DateTime::now(),
+            "updatedAt": DateTime::now(),
+        };
+        let legacy: Review = from_document(legacy_doc).unwrap();
+        assert_eq!(stored_parent(&legacy), None);
+
+        let review = with_parent(legacy, series_oid, "series");
+        assert_eq!(
+            stored_parent(&review),
+            Some(("series".to_string(), series_oid))
+        );
+    }
+
     #[actix_web::test]
     async fn test_update_review_ok() {
         let mut mock = MockReviewRepository::new();
@@ -1101,6 +2105,36 @@ mod tests {
         assert!(result.is_err_and(|err| err == AppError::InternalServerError));
     }
 
+    #[test]
+    fn test_patch_value_half_star_rating() {
+        assert_eq!(patch_value("rating", "3.5").unwrap(), Bson::Double(3.5));
+        assert_eq!(patch_value("rating", "4").unwrap(), Bson::Double(4.0));
+        for rating in ["3.7", "6", "cuatro"] {
+            assert!(patch_value("rating", rating)
+                .is_err_and(|err| matches!(err, AppError::ValidationAppError(_))));
+        }
+        assert_eq!(
+            patch_value("title", "3.7").unwrap(),
+            Bson::String("3.7".to_string())
+        );
+    }
+
+    #[test]
+    fn test_build_written_review_timestamps_rfc3339() {
+        let created_at = DateTime::from_millis(1_700_000_000_000);
+        let updated_at = DateTime::now();
+        let msg = "Review title with id: '1234' was successfully patched".to_string();
+        let map = build_written_review(msg.clone(), created_at, updated_at);
+
+        assert_eq!(map["message"], msg);
+        assert_eq!(map["createdAt"], "2023-11-14T22:13:20+00:00");
+        let parsed = chrono::DateTime::parse_from_rfc3339(map["updatedAt"].as_str().unwrap());
+        assert_eq!(
+            parsed.unwrap().timestamp_millis(),
+            updated_at.timestamp_millis()
+        );
+    }
+
     #[actix_web::test]
     async fn test_patch_review_ok() {
         let mut mock = MockReviewRepository::new();
@@ -1195,4 +2229,322 @@ mod tests {
 
         assert!(result.is_err_and(|err| err == AppError::InternalServerError));
     }
+
+    #[actix_web::test]
+    async fn test_reassign_review_ok() {
+        let mut mock = MockReviewRepository::new();
+        let oid = ObjectId::new();
+        let msg = format!(
+            "Review with id: '{}' was successfully reassigned to imdbId: 'tt0903747'",
+            oid
+        );
+
+        mock.expect_reassign_review().returning({
+            let msg = msg.clone();
+            move |_, _| {
+                let mut map_result: Map<String, Value> = Map::new();
+                map_result.insert("message".to_string(), Value::String(msg.clone()));
+                map_result.insert(
+                    "parentType".to_string(),
+                    Value::String("series".to_string()),
+                );
+                Ok(map_result)
+            }
+        });
+
+        let result = mock
+            .reassign_review(oid.to_string().as_str(), "tt0903747")
+            .await;
+
+        assert!(result.is_ok_and(|map| map["message"] == msg && map["parentType"] == "series"));
+    }
+
+    #[actix_web::test]
+    async fn test_reassign_review_not_exists() {
+        let mut mock = MockReviewRepository::new();
+        let oid = ObjectId::new();
+
+        mock.expect_reassign_review()
+            .returning(|_, _| Err(AppError::NotExists));
+
+        let result = mock
+            .reassign_review(oid.to_string().as_str(), "tt0000001")
+            .await;
+
+        assert!(result.is_err_and(|err| err == AppError::NotExists));
+    }
+
+    #[test]
+    fn test_reassign_parent_updates_moves_between_parents() {
+        let (review_id, movie_id, series_id) = (ObjectId::new(), ObjectId::new(), ObjectId::new());
+        let old_parent = ("movie".to_string(), movie_id);
+        let new_parent = ("series".to_string(), series_id);
+
+        let updates = reassign_parent_updates(review_id, Some(&old_parent), &new_parent);
+
+        assert_eq!(
+            updates,
+            vec![
+                (old_parent, doc! { "$pull": { "reviewIds": review_id } }),
+                (new_parent, doc! { "$addToSet": { "reviewIds": review_id } }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reassign_parent_updates_without_old_parent() {
+        let (review_id, series_id) = (ObjectId::new(), ObjectId::new());
+        let new_parent = ("series".to_string(), series_id);
+
+        let updates = reassign_parent_updates(review_id, None, &new_parent);
+
+        assert_eq!(
+            updates,
+            vec![(new_parent, doc! { "$addToSet": { "reviewIds": review_id } })]
+        );
+    }
+
+    #[test]
+    fn test_reassigned_review_update() {
+        let series_id = ObjectId::new();
+
+        let update = reassigned_review_update(&("series".to_string(), series_id));
+
+        let set = update.get_document("$set").unwrap();
+        assert_eq!(set.get_object_id("parentId").unwrap(), series_id);
+        assert_eq!(set.get_str("parentType").unwrap(), "series");
+        assert!(set.get_datetime("updatedAt").is_ok());
+    }
+
+    #[test]
+    fn test_partition_reviews_skips_malformed() {
+        let good = doc! {
+            "_id": ObjectId::new(),
+            "title": "Una obra maestra",
+            "rating": 5,
+            "body": "Imprescindible.",
+            "createdAt": DateTime::now(),
+            "updatedAt": DateTime::now(),
+        };
+        let malformed = doc! {
+            "_id": ObjectId::new(),
+            "title": "Fecha corrupta",
+            "rating": 3,
+            "body": "Sin fecha válida.",
+            "createdAt": "not-a-date",
+            "updatedAt": DateTime::now(),
+        };
+
+        let (review_list, skipped_count) = partition_reviews(vec![
+            mongodb::bson::from_document::<Review>(good),
+            mongodb::bson::from_document::<Review>(malformed),
+        ]);
+        assert_eq!(review_list.len(), 1);
+        assert_eq!(review_list[0].title, "Una obra maestra");
+        assert_eq!(skipped_count, 1);
+    }
+
+    #[test]
+    fn test_with_min_rating_filter() {
+        assert_eq!(with_min_rating(None, None).unwrap(), None);
+        assert_eq!(
+            with_min_rating(None, Some(3.5)).unwrap(),
+            Some(doc! {"rating": { "$gte": 3.5 }})
+        );
+
+        let search = build_search_filter(Some("Corleone".to_string()));
+        let filter = with_min_rating(search.clone(), Some(4.0)).unwrap().unwrap();
+        assert_eq!(
+            filter,
+            doc! {"$and": [search.unwrap(), {"rating": { "$gte": 4.0 }}]}
+        );
+    }
+
+    #[test]
+    fn test_with_min_rating_out_of_bounds() {
+        for min_rating in [-0.5, 5.5, f32::NAN] {
+            let result = with_min_rating(None, Some(min_rating));
+            assert!(
+                matches!(result, Err(AppError::ValidationAppError(ref msg)) if msg.starts_with("minRating:")),
+                "{min_rating}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_build_search_filter_matches_body_only_term() {
+        assert!(build_search_filter(None).is_none());
+        assert!(build_search_filter(Some("  ".to_string())).is_none());
+
+        // Same pattern and options on both fields, run as MongoDB would with `$options: "i"`
+        let filter_regex = |q: &str| {
+            let filter = build_search_filter(Some(q.to_string())).unwrap();
+            let or_filters = filter.get_array("$or").unwrap();
+            let fields: Vec<&Document> = or_filters
+                .iter()
+                .map(|field| field.as_document().unwrap())
+                .collect();
+            assert_eq!(fields[0].keys().collect::<Vec<_>>(), vec!["title"]);
+            assert_eq!(fields[1].keys().collect::<Vec<_>>(), vec!["body"]);
+            let body = fields[1].get_document("body").unwrap();
+            assert_eq!(fields[0].get_document("title").unwrap(), body);
+            assert_eq!(body.get_str("$options").unwrap(), "i");
+            regex::RegexBuilder::new(body.get_str("$regex").unwrap())
+                .case_insensitive(true)
+                .build()
+                .unwrap()
+        };
+
+        let review = build_review_update_mock();
+        let re = filter_regex("  MICHAEL corleone ");
+        assert!(!re.is_match(&review.title));
+        assert!(re.is_match(&review.body));
+        assert!(!filter_regex("Vito Corleone").is_match(&review.body));
+
+        // Regex metacharacters in the term are matched literally
+        let re = filter_regex("Corleone (1972)");
+        assert!(re.is_match("Michael Corleone (1972) vuelve"));
+        assert!(!re.is_match("Michael Corleone 1972 vuelve"));
+        let re = filter_regex("padrino.*");
+        assert!(!re.is_match(&review.title));
+        assert!(re.is_match("El padrino.* no es una regex"));
+    }
+
+    #[test]
+    fn test_add_review_ref_does_not_duplicate() {
+        let review_id = Bson::ObjectId(ObjectId::new());
+        let update = add_review_ref(&review_id);
+
+        assert!(!update.contains_key("$push"));
+        let add_to_set = update.get_document("$addToSet").unwrap();
+        assert_eq!(add_to_set.get("reviewIds").unwrap(), &review_id);
+        assert_eq!(add_review_ref(&review_id), update);
+    }
+
+    #[test]
+    fn test_with_cursor_continues_after_last_id() {
+        let after_id = ObjectId::new();
+        assert!(with_cursor(None, None).is_none());
+
+        let filter = with_cursor(None, Some(after_id)).unwrap();
+        assert_eq!(filter, doc! {"_id": { "$gt": after_id }});
+
+        let search = build_search_filter(Some("padrino".to_string()));
+        let filter = with_cursor(search, Some(after_id)).unwrap();
+        assert_eq!(filter.get_array("$and").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_next_cursor_only_on_full_pages() {
+        let review = ReviewResponse::try_from(Review {
+            _id: ObjectId::new(),
+            title: "Una obra maestra".to_string(),
+            rating: 5.0,
+            body: "Impresionante".to_string(),
+            created_at: DateTime::now(),
+            updated_at: DateTime::now(),
+            parent_id: None,
+            parent_type: None,
+        })
+        .unwrap();
+        let last_id = review._id;
+        let review_list = vec![review];
+
+        assert!(next_cursor(&review_list, 1, 10).is_none());
+        let cursor = next_cursor(&review_list, 2, 2).unwrap();
+        assert_eq!(decode_cursor(&cursor).unwrap(), last_id);
+    }
+
+    #[test]
+    fn test_build_created_review_does_not_count_existing_ref_twice() {
+        let review_id = ObjectId::new();
+        let created = build_created_review(
+            &Bson::ObjectId(review_id),
+            "tt67890",
+            "series",
+            &[review_id],
+        );
+        assert_eq!(created["id"], review_id.to_hex());
+        assert_eq!(
+            created["message"],
+            format!("Review was successfully created. (id: '{}')", review_id)
+        );
+        assert_eq!(created["parentType"], "series");
+        assert_eq!(created["newReviewCount"], 1);
+    }
+
+    #[test]
+    fn test_with_parents_pipeline_looks_up_both_collections() {
+        let pipeline = with_parents_pipeline("movies", "series", 0, 10);
+        let lookups: Vec<&str> = pipeline
+            .iter()
+            .filter_map(|stage| stage.get_document("$lookup").ok())
+            .map(|lookup| lookup.get_str("from").unwrap())
+            .collect();
+        assert_eq!(lookups, vec!["movies", "series"]);
+        assert_eq!(pipeline[2], doc! { "$limit": 10_i64 });
+    }
+
+    #[test]
+    fn test_to_review_with_parent_shape() {
+        let oid = ObjectId::new();
+        let review_doc = doc! {
+            "_id": oid,
+            "title": "Una obra maestra",
+            "rating": 5,
+            "body": "Impresionante",
+            "createdAt": DateTime::now(),
+            "updatedAt": DateTime::now(),
+            "parent": { "imdbId": "tt12345", "title": "El padrino", "type": "movie" },
+        };
+
+        let joined = to_review_with_parent(review_doc.clone()).unwrap();
+        let json = serde_json::to_value(&joined).unwrap();
+        assert_eq!(json["_id"], serde_json::to_value(oid).unwrap());
+        assert_eq!(json["title"], "Una obra maestra");
+        assert_eq!(json["parent"]["imdbId"], "tt12345");
+        assert_eq!(json["parent"]["type"], "movie");
+        assert_eq!(json["bodyPreview"], "Impresionante");
+
+        let mut orphan = review_doc;
+        orphan.insert("parent", Bson::Null);
+        assert!(to_review_with_parent(orphan).unwrap().parent.is_none());
+    }
+
+    #[actix_web::test]
+    async fn test_find_all_reviews_with_parents_ok() {
+        let mut mock = MockReviewRepository::new();
+
+        mock.expect_find_all_reviews_with_parents()
+            .returning(|_, _| {
+                let review = ReviewWithParent::new(
+                    ReviewResponse {
+                        _id: ObjectId::new(),
+                        title: "Serie imprescindible".to_string(),
+                        rating: 5.0,
+                        body: "De lo mejor de la televisión".to_string(),
+                        created_at: DateTime::now(),
+                        updated_at: DateTime::now(),
+                    },
+                    Some(ReviewParent {
+                        imdb_id: "tt67890".to_string(),
+                        title: "Breaking Bad".to_string(),
+                        parent_type: "series".to_string(),
+                    }),
+                );
+                let mut result_map = Map::new();
+                result_map.insert(
+                    "reviews".to_string(),
+                    serde_json::to_value(vec![review]).unwrap(),
+                );
+                Ok(result_map)
+            });
+
+        let result = mock.find_all_reviews_with_parents(None, None).await;
+        assert!(result.is_ok_and(|map| {
+            map["reviews"][0]["parent"]["type"] == "series"
+                && map["reviews"][0]["parent"]["imdbId"] == "tt67890"
+                && map["reviews"][0]["rating"] == 5.0
+        }));
+    }
 }
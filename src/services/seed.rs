@@ -0,0 +1,272 @@
+use std::{env, fs};
+
+use log::{error, info, warn};
+use mongodb::{
+    bson::{doc, oid::ObjectId},
+    options::CountOptions,
+};
+use serde::Deserialize;
+use validator::Validate;
+
+use crate::models::{
+    movie::{Movie, MovieRequest},
+    review::{Review, ReviewRequest},
+    series::{Series, SeriesRequest},
+};
+
+use super::{
+    db::Database, movie_repo::MovieRepository, review_repo::ReviewRepository,
+    series_repo::SeriesRepository,
+};
+
+/// Fixtures file layout expected in `SEED_FILE`.
+#[derive(Debug, Default, Deserialize)]
+pub struct SeedData {
+    #[serde(default)]
+    pub movies: Vec<MovieRequest>,
+    #[serde(default)]
+    pub series: Vec<SeriesRequest>,
+    #[serde(default)]
+    pub reviews: Vec<ReviewRequest>,
+}
+
+/// Validated documents ready to be inserted, reviews paired with their parent imdbId.
+#[derive(Debug, Default)]
+pub struct PreparedSeed {
+    pub movies: Vec<Movie>,
+    pub series: Vec<Series>,
+    pub reviews: Vec<(Review, String)>,
+}
+
+pub fn parse_seed(raw: &str) -> Result<SeedData, serde_json::Error> {
+    serde_json::from_str(raw)
+}
+
+/// Runs the same validation as the create endpoints, skipping (and logging) invalid entries.
+pub fn prepare_seed(data: SeedData) -> PreparedSeed {
+    let mut prepared = PreparedSeed::default();
+    for movie in data.movies {
+        if let Err(err) = movie.validate() {
+            warn!("Skipping seed movie '{}' [{}]", movie.imdb_id, err);
+            continue;
+        }
+        match Movie::try_from(movie) {
+            Ok(movie) => prepared.movies.push(movie),
+            Err(err) => warn!("Skipping seed movie [{}]", err),
+        }
+    }
+    for series in data.series {
+        if let Err(err) = series.validate() {
+            warn!("Skipping seed series '{}' [{}]", series.imdb_id, err);
+            continue;
+        }
+        match Series::try_from(series) {
+            Ok(series) => prepared.series.push(series),
+            Err(err) => warn!("Skipping seed series [{}]", err),
+        }
+    }
+    for review in data.reviews {
+        if let Err(err) = review.validate() {
+            warn!("Skipping seed review for '{}' [{}]", review.imdb_id, err);
+            continue;
+        }
+        let imdb_id = review.imdb_id.clone();
+        match Review::try_from(review) {
+            Ok(review) => prepared.reviews.push((review, imdb_id)),
+            Err(err) => warn!("Skipping seed review [{}]", err),
+        }
+    }
+    prepared
+}
+
+/// imdbIds touched by the seed: every movie and series plus the parents of its reviews.
+pub fn seeded_imdb_ids(prepared: &PreparedSeed) -> Vec<String> {
+    let mut ids: Vec<String> = prepared
+        .movies
+        .iter()
+        .map(|movie| movie.imdb_id.clone())
+        .chain(prepared.series.iter().map(|series| series.imdb_id.clone()))
+        .chain(prepared.reviews.iter().map(|(_, imdb_id)| imdb_id.clone()))
+        .collect();
+    ids.sort();
+    ids.dedup();
+    ids
+}
+
+/// Deletes the movies and series with the given imdbIds and the reviews they hold, so a
+/// forced reseed starts from scratch instead of piling duplicated reviews on the parents.
+async fn clear_seeded(db: &Database, imdb_ids: &[String]) {
+    let filter = doc! { "imdbId": { "$in": imdb_ids } };
+    let mut review_ids: Vec<ObjectId> = Vec::new();
+    match db.movies.distinct("reviewIds", filter.clone(), None).await {
+        Ok(ids) => review_ids.extend(ids.iter().filter_map(|id| id.as_object_id())),
+        Err(err) => warn!("Error reading seeded movies reviewIds [{}]", err),
+    }
+    match db.series.distinct("reviewIds", filter.clone(), None).await {
+        Ok(ids) => review_ids.extend(ids.iter().filter_map(|id| id.as_object_id())),
+        Err(err) => warn!("Error reading seeded series reviewIds [{}]", err),
+    }
+    if !review_ids.is_empty() {
+        if let Err(err) = db
+            .reviews
+            .delete_many(doc! { "_id": { "$in": &review_ids } }, None)
+            .await
+        {
+            warn!("Error deleting seeded reviews [{}]", err);
+        }
+    }
+    if let Err(err) = db.movies.delete_many(filter.clone(), None).await {
+        warn!("Error deleting seeded movies [{}]", err);
+    }
+    if let Err(err) = db.series.delete_many(filter, None).await {
+        warn!("Error deleting seeded series [{}]", err);
+    }
+    db.purge_find_by_id_cache(None);
+}
+
+async fn is_empty(db: &Database) -> bool {
+    let movies = db
+        .movies
+        .count_documents(None, CountOptions::default())
+        .await
+        .unwrap_or_default();
+    let series = db
+        .series
+        .count_documents(None, CountOptions::default())
+        .await
+        .unwrap_or_default();
+    let reviews = db
+        .reviews
+        .count_documents(None, CountOptions::default())
+        .await
+        .unwrap_or_default();
+    movies + series + reviews == 0
+}
+
+/// Loads the `SEED_FILE` fixtures on an empty database (or always when `SEED_FORCE=true`).
+pub async fn seed_from_env(db: &Database) {
+    let path = match env::var("SEED_FILE") {
+        Ok(path) if !path.is_empty() => path,
+        _ => return,
+    };
+    let force = env::var("SEED_FORCE")
+        .map(|val| val.eq_ignore_ascii_case("true"))
+        .unwrap_or(false);
+    if !force && !is_empty(db).await {
+        info!("Database already has data, skipping seed from '{}'", path);
+        return;
+    }
+    let data = match fs::read_to_string(&path).map(|raw| parse_seed(&raw)) {
+        Ok(Ok(data)) => data,
+        Ok(Err(err)) => {
+            error!("Error parsing seed file '{}' [{}]", path, err);
+            return;
+        }
+        Err(err) => {
+            error!("Error reading seed file '{}' [{}]", path, err);
+            return;
+        }
+    };
+    info!("Seeding the database from '{}'", path);
+    seed(db, prepare_seed(data), force).await;
+}
+
+/// Inserts the prepared documents. With `force` the seeded movies, series and their reviews
+/// are deleted first, so running it again leaves the same data instead of duplicating reviews.
+pub async fn seed(db: &Database, prepared: PreparedSeed, force: bool) {
+    if force {
+        clear_seeded(db, &seeded_imdb_ids(&prepared)).await;
+    }
+    info!(
+        "Seeding {} movies, {} series and {} reviews",
+        prepared.movies.len(),
+        prepared.series.len(),
+        prepared.reviews.len()
+    );
+    for movie in prepared.movies {
+        if let Err(err) = db.create_movie(movie).await {
+            warn!("Seed movie not inserted [{}]", err);
+        }
+    }
+    for series in prepared.series {
+        if let Err(err) = db.create_series(series).await {
+            warn!("Seed series not inserted [{}]", err);
+        }
+    }
+    for (review, imdb_id) in prepared.reviews {
+        if let Err(err) = db.create_review(review, imdb_id.as_str()).await {
+            warn!("Seed review for '{}' not inserted [{}]", imdb_id, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = r#"{
+        "movies": [
+            {
+                "imdbId": "tt0993846",
+                "title": "El lobo de Wall Street",
+                "overview": "La biografía de Jordan Belfort.",
+                "duration": "2h 59m",
+                "director": "Martin Scorsese",
+                "releaseDate": "2014-01-17",
+                "trailerLink": "https://youtu.be/DEMZSa0esCU",
+                "genres": ["Crimen", "Drama"],
+                "poster": "https://image.tmdb.org/t/p/original/poster.jpg",
+                "backdrop": "https://image.tmdb.org/t/p/original/backdrop.jpg"
+            },
+            {
+                "imdbId": "not-an-imdb-id",
+                "title": "",
+                "overview": "Invalid entry",
+                "duration": "2h",
+                "director": "Nobody",
+                "releaseDate": "2014",
+                "trailerLink": "",
+                "genres": [],
+                "poster": "",
+                "backdrop": ""
+            }
+        ],
+        "reviews": [
+            { "title": "Obra maestra", "rating": 5, "body": "Imprescindible.", "imdbId": "tt0993846" }
+        ]
+    }"#;
+
+    // Unit Tests
+
+    #[test]
+    fn test_prepare_seed_from_fixture() {
+        let prepared = prepare_seed(parse_seed(FIXTURE).unwrap());
+
+        assert_eq!(prepared.movies.len(), 1);
+        assert_eq!(prepared.movies[0].imdb_id, "tt0993846");
+        assert_eq!(prepared.movies[0].duration.to_string(), "2h 59m");
+        assert!(prepared.series.is_empty());
+        assert_eq!(prepared.reviews.len(), 1);
+        assert_eq!(prepared.reviews[0].1, "tt0993846");
+        assert_eq!(prepared.reviews[0].0.body, "Imprescindible.");
+    }
+
+    #[test]
+    fn test_seeded_imdb_ids_include_review_parents() {
+        let mut data = parse_seed(FIXTURE).unwrap();
+        data.reviews.push(
+            serde_json::from_str(
+                r#"{ "title": "Gran serie", "rating": 4, "body": "Muy buena.", "imdbId": "tt0903747" }"#,
+            )
+            .unwrap(),
+        );
+        let prepared = prepare_seed(data);
+
+        assert_eq!(seeded_imdb_ids(&prepared), vec!["tt0903747", "tt0993846"]);
+    }
+
+    #[test]
+    fn test_parse_seed_malformed_json() {
+        assert!(parse_seed("{ \"movies\": 3 }").is_err());
+    }
+}
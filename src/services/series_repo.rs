@@ -1,26 +1,32 @@
 use std::str::FromStr;
 
 use async_trait::async_trait;
-use futures_util::{StreamExt, TryStreamExt};
-use lazy_static::lazy_static;
+use futures_util::TryStreamExt;
 use log::{error, info, warn};
 use mongodb::{
-    bson::{doc, oid::ObjectId, to_bson, Regex},
+    bson::{doc, oid::ObjectId, to_bson, Bson, DateTime, Document, Regex},
     options::{CountOptions, FindOptions},
 };
 use serde_json::{Map, Value};
+use validator::Validate;
 
-use super::{db::Database, movie_repo::MovieRepository};
+use super::{
+    db::Database,
+    movie_repo::{sorted_genres, MovieRepository},
+    pagination::{build_pagination_map, current_page, page_skip},
+};
 
 use crate::{
-    error::AppError,
-    models::series::{Series, SeriesRequest, SeriesResponse},
+    error::{insert_error, inserted_object_id, internal_error, AppError},
+    models::{
+        fields::{is_patchable, SERIES_PATCHABLE_FIELDS},
+        series::{
+            Episode, FlatEpisode, Season, SeasonListRequest, Series, SeriesRequest, SeriesResponse,
+        },
+        validation::{normalize_imdb_id, RE_SERIES_IMDB_ID},
+    },
 };
 
-lazy_static! {
-    static ref RE_IMDB_ID: regex::Regex = regex::Regex::new(r"^tt\d+$").unwrap();
-}
-
 #[cfg_attr(test, mockall::automock)]
 #[async_trait]
 pub trait SeriesRepository {
@@ -31,6 +37,8 @@ pub trait SeriesRepository {
         size: Option<u32>,
     ) -> Result<Map<String, Value>, AppError>;
     async fn find_series_by_id(&self, id: &str) -> Result<Series, AppError>;
+    async fn count_series(&self, title: Option<String>) -> Result<Map<String, Value>, AppError>;
+    async fn distinct_genres(&self) -> Result<Vec<String>, AppError>;
     async fn find_series_by_imdb_id(&self, imdb_id: &str) -> Result<Series, AppError>;
     async fn create_series(&self, series: Series) -> Result<Map<String, Value>, AppError>;
     async fn delete_series(&self, id: &str) -> Result<Map<String, Value>, AppError>;
@@ -46,6 +54,25 @@ pub trait SeriesRepository {
         field: &str,
         val: &str,
     ) -> Result<Map<String, Value>, AppError>;
+    async fn total_runtime(&self, id: &str) -> Result<Map<String, Value>, AppError>;
+    async fn find_series_season(&self, id: &str, index: usize) -> Result<Season, AppError>;
+    async fn add_season_episode(
+        &self,
+        id: &str,
+        index: usize,
+        episode: Episode,
+    ) -> Result<Map<String, Value>, AppError>;
+    async fn find_flattened_episodes(
+        &self,
+        id: &str,
+        page: Option<u32>,
+        size: Option<u32>,
+    ) -> Result<Map<String, Value>, AppError>;
+    async fn replace_season_list(
+        &self,
+        id: &str,
+        season_list: Vec<Season>,
+    ) -> Result<Map<String, Value>, AppError>;
 }
 
 #[async_trait]
@@ -59,47 +86,21 @@ impl SeriesRepository for Database {
         info!("GET series /findAll executed");
         let mut result_map: Map<String, Value> = Map::new();
 
-        let page_num = match page {
-            None => 0,
-            Some(page) => {
-                if page > 0 {
-                    page
-                } else {
-                    0
-                }
-            }
-        };
+        let page_num = current_page(page);
         let page_size = match size {
-            None => 10,
-            Some(size) => {
-                if size > 0 {
-                    size
-                } else {
-                    10
-                }
-            }
-        };
-        let filter = match title {
-            None => None,
-            Some(title) => {
-                let regex = Regex {
-                    pattern: format!("{}", title),
-                    options: String::new(),
-                };
-                doc! {"title": { "$regex": regex, "$options": "i" }}.into()
-            }
+            Some(size) if size > 0 => size,
+            _ => 10,
         };
+        let filter = build_series_filter(title);
 
         let total_items = self
             .series
             .count_documents(filter.clone(), CountOptions::default())
             .await
-            .ok()
-            .expect("Error counting total of series");
-        let total_pages = (total_items as f64 / page_size as f64).ceil() as u64;
+            .map_err(internal_error("Error counting total of series"))?;
 
         let options = FindOptions::builder()
-            .skip((page_num * page_size) as u64)
+            .skip(page_skip(page_num - 1, page_size)?)
             .limit(page_size as i64)
             .build();
 
@@ -107,69 +108,87 @@ impl SeriesRepository for Database {
             .series
             .find(filter, options)
             .await
-            .ok()
-            .expect("Error finding all series");
+            .map_err(internal_error("Error finding all series"))?;
 
-        let series_list: Vec<SeriesResponse> = cursor
-            .map(|series| SeriesResponse::try_from(series.unwrap()))
+        let series: Vec<Series> = cursor
             .try_collect()
             .await
-            .ok()
-            .expect("Error collecting series");
-
-        if series_list.is_empty() {
-            warn!("Warn in series /findAll [{}]", AppError::Empty.to_string());
-            return Err(AppError::Empty);
-        }
-
+            .map_err(internal_error("Error collecting series"))?;
+        let series_list: Vec<SeriesResponse> = series
+            .into_iter()
+            .map(SeriesResponse::try_from)
+            .collect::<Result<_, _>>()
+            .map_err(internal_error("Error converting series"))?;
+
+        // An empty page still gets its envelope, the route decides whether it is a 204
         result_map.insert(
             "series".to_string(),
             serde_json::to_value(series_list).unwrap(),
         );
-        result_map.insert(
-            "currentPage".to_string(),
-            serde_json::to_value(page_num).unwrap(),
-        );
-        result_map.insert(
-            "totalItems".to_string(),
-            serde_json::to_value(total_items).unwrap(),
-        );
-        result_map.insert(
-            "totalPages".to_string(),
-            serde_json::to_value(total_pages).unwrap(),
-        );
+        Ok(build_pagination_map(
+            result_map,
+            page_num,
+            page_size,
+            total_items,
+        ))
+    }
 
-        Ok(result_map)
+    async fn count_series(&self, title: Option<String>) -> Result<Map<String, Value>, AppError> {
+        info!("GET series /count executed");
+        let count = self
+            .series
+            .count_documents(build_series_filter(title), CountOptions::default())
+            .await
+            .map_err(internal_error("Error in series /count counting series"))?;
+        let mut map_result: Map<String, Value> = Map::new();
+        map_result.insert("count".to_string(), serde_json::to_value(count).unwrap());
+        Ok(map_result)
+    }
+
+    async fn distinct_genres(&self) -> Result<Vec<String>, AppError> {
+        info!("GET series /genres executed");
+        let genres = self
+            .series
+            .distinct("genres", None, None)
+            .await
+            .map_err(internal_error(
+                "Error in series /genres finding distinct genres",
+            ))?;
+        Ok(sorted_genres(genres))
     }
 
     async fn find_series_by_id(&self, id: &str) -> Result<Series, AppError> {
         info!("GET series /findById with id: '{}' executed", id);
         let obj_id = ObjectId::from_str(id)?;
-        let series: Series = match self.series.find_one(doc! {"_id": obj_id}, None).await {
-            Ok(Some(series)) => series,
-            Ok(None) => {
-                warn!(
-                    "Warn in series /findById with id: '{}' [{}]",
-                    id,
-                    AppError::NotFound.to_string()
-                );
-                return Err(AppError::NotFound);
-            }
-            Err(_) => {
-                error!(
-                    "Error in series /findById with id: '{}' [{}]",
-                    id,
-                    AppError::InternalServerError.to_string()
-                );
-                return Err(AppError::InternalServerError);
-            }
-        };
-        Ok(series)
+        self.series_cache
+            .get_or_load(&obj_id.to_hex(), || async {
+                match self.series.find_one(doc! {"_id": obj_id}, None).await {
+                    Ok(Some(series)) => Ok(series),
+                    Ok(None) => {
+                        warn!(
+                            "Warn in series /findById with id: '{}' [{}]",
+                            id,
+                            AppError::NotFound.to_string()
+                        );
+                        Err(AppError::NotFound)
+                    }
+                    Err(_) => {
+                        error!(
+                            "Error in series /findById with id: '{}' [{}]",
+                            id,
+                            AppError::InternalServerError.to_string()
+                        );
+                        Err(AppError::InternalServerError)
+                    }
+                }
+            })
+            .await
     }
 
     async fn find_series_by_imdb_id(&self, imdb_id: &str) -> Result<Series, AppError> {
+        let imdb_id = &normalize_imdb_id(imdb_id);
         info!("GET series /findByImdbId with id: '{}' executed", imdb_id);
-        if !RE_IMDB_ID.is_match(imdb_id) {
+        if !RE_SERIES_IMDB_ID.is_match(imdb_id) {
             error!(
                 "Error in series /findByImdbId with imdbId: '{}' [{}]",
                 imdb_id,
@@ -222,21 +241,24 @@ impl SeriesRepository for Database {
             );
             return Err(AppError::AlreadyExists);
         }
-        let result =
-            self.series.insert_one(&series, None).await.ok().expect(
-                format!("Error creating series with imdbId: '{}'", series.imdb_id).as_str(),
-            );
+        let result = self
+            .series
+            .insert_one(&series, None)
+            .await
+            .map_err(insert_error(format!(
+                "Error creating series with imdbId: '{}'",
+                series.imdb_id
+            )))?;
 
+        let inserted_id = inserted_object_id(&result.inserted_id)?;
         let mut map_result: Map<String, Value> = Map::new();
+        map_result.insert("id".to_string(), Value::String(inserted_id.to_hex()));
         map_result.insert(
             "message".to_string(),
-            Value::String(
-                format!(
-                    "Series was successfully created. (id: '{}')",
-                    result.inserted_id.as_object_id().unwrap().to_string()
-                )
-                .to_string(),
-            ),
+            Value::String(format!(
+                "Series was successfully created. (id: '{}')",
+                inserted_id
+            )),
         );
         Ok(map_result)
     }
@@ -255,6 +277,7 @@ impl SeriesRepository for Database {
                 return Err(AppError::InternalServerError);
             }
         };
+        self.series_cache.purge(&[obj_id.to_hex()]);
         let mut map_result: Map<String, Value> = Map::new();
         if del_result.deleted_count > 0 {
             map_result.insert(
@@ -267,9 +290,9 @@ impl SeriesRepository for Database {
             warn!(
                 "Warn in series /delete with id: '{}' [{}]",
                 obj_id,
-                AppError::NotExists.to_string()
+                AppError::NotFound.to_string()
             );
-            return Err(AppError::NotExists);
+            return Err(AppError::NotFound);
         }
         Ok(map_result)
     }
@@ -329,29 +352,16 @@ impl SeriesRepository for Database {
             );
             return Err(AppError::ImdbIdInUse);
         }
+        let update_set = build_update_set(series)?;
         let result = self
             .series
-            .update_one(
-                doc! { "_id": obj_id },
-                doc! {
-                "$set": doc! {
-                    "imdbId": series.imdb_id,
-                    "title": series.title,
-                    "overview": series.overview,
-                    "numberOfSeasons": series.number_of_seasons,
-                    "creator": series.creator,
-                    "releaseDate": series.release_date,
-                    "trailerLink": series.trailer_link,
-                    "genres": series.genres,
-                    "seasonList": to_bson(&series.season_list).unwrap(),
-                    "poster": series.poster,
-                    "backdrop": series.backdrop
-                }},
-                None,
-            )
+            .update_one(doc! { "_id": obj_id }, doc! { "$set": update_set }, None)
             .await
-            .ok()
-            .expect(format!("Error updating series with id: '{}'", id).as_str());
+            .map_err(internal_error(format!(
+                "Error updating series with id: '{}'",
+                id
+            )))?;
+        self.series_cache.purge(&[obj_id.to_hex()]);
         let mut map_result: Map<String, Value> = Map::new();
         map_result.insert(
             "message".to_string(),
@@ -371,21 +381,8 @@ impl SeriesRepository for Database {
         val: &str,
     ) -> Result<Map<String, Value>, AppError> {
         info!("PATCH series /patch with id: '{}' executed", id);
-        let fields_vec: Vec<&str> = vec![
-            "imdbId",
-            "title",
-            "overview",
-            "numberOfSeasons",
-            "creator",
-            "releaseDate",
-            "trailerLink",
-            "genres",
-            "seasonList",
-            "poster",
-            "backdrop",
-        ];
         let obj_id = ObjectId::from_str(id)?;
-        if !fields_vec.contains(&field) {
+        if !is_patchable(SERIES_PATCHABLE_FIELDS, field) {
             warn!(
                 "Warn in series /patch with id: '{}' [{}]",
                 obj_id,
@@ -393,6 +390,7 @@ impl SeriesRepository for Database {
             );
             return Err(AppError::FieldNotAllowed);
         }
+        let update = patch_update(field, val)?;
         let series_founded: Series = match self.series.find_one(doc! { "_id": obj_id }, None).await
         {
             Ok(Some(series)) => series,
@@ -414,7 +412,7 @@ impl SeriesRepository for Database {
             }
         };
         if field == "imdbId" {
-            if !RE_IMDB_ID.is_match(val) {
+            if !RE_SERIES_IMDB_ID.is_match(val) {
                 error!(
                     "Error in series /patch with id: '{}' [{}]",
                     id,
@@ -435,17 +433,13 @@ impl SeriesRepository for Database {
         }
         let result = self
             .series
-            .update_one(
-                doc! { "_id": obj_id },
-                doc! {
-                "$set": doc! {
-                    field: to_bson(val).unwrap()
-                }},
-                None,
-            )
+            .update_one(doc! { "_id": obj_id }, update, None)
             .await
-            .ok()
-            .expect(format!("Error patching series with id: '{}'", id).as_str());
+            .map_err(internal_error(format!(
+                "Error patching series with id: '{}'",
+                id
+            )))?;
+        self.series_cache.purge(&[obj_id.to_hex()]);
         let mut map_result: Map<String, Value> = Map::new();
         map_result.insert(
             "message".to_string(),
@@ -460,6 +454,367 @@ impl SeriesRepository for Database {
         );
         Ok(map_result)
     }
+
+    async fn total_runtime(&self, id: &str) -> Result<Map<String, Value>, AppError> {
+        info!(
+            "GET series /findById/totalRuntime with id: '{}' executed",
+            id
+        );
+        let series = self.find_series_by_id(id).await?;
+        let runtime = series.total_runtime();
+        let mut map_result: Map<String, Value> = Map::new();
+        map_result.insert(
+            "totalMinutes".to_string(),
+            serde_json::to_value(runtime.total_minutes()).unwrap(),
+        );
+        map_result.insert("formatted".to_string(), Value::String(runtime.to_string()));
+        Ok(map_result)
+    }
+
+    async fn find_series_season(&self, id: &str, index: usize) -> Result<Season, AppError> {
+        info!(
+            "GET series /{{id}}/seasons/{{index}} with id: '{}' and index: {} executed",
+            id, index
+        );
+        let series = self.find_series_by_id(id).await?;
+        match series.season(index) {
+            Some(season) => Ok(season.clone()),
+            None => {
+                warn!(
+                    "Warn in series /{{id}}/seasons/{{index}} with id: '{}' and index: {} [{}]",
+                    id,
+                    index,
+                    AppError::NotFound
+                );
+                Err(AppError::NotFound)
+            }
+        }
+    }
+
+    async fn add_season_episode(
+        &self,
+        id: &str,
+        index: usize,
+        episode: Episode,
+    ) -> Result<Map<String, Value>, AppError> {
+        info!(
+            "POST series /{{id}}/seasons/{{index}}/episodes with id: '{}' and index: {} executed",
+            id, index
+        );
+        let season = self.find_series_season(id, index).await?;
+        if season.is_full() {
+            let err = AppError::ValidationAppError(
+                "episode: The season cannot have more episodes".to_string(),
+            );
+            warn!(
+                "Warn in series /{{id}}/seasons/{{index}}/episodes with id: '{}' [{}]",
+                id, err
+            );
+            return Err(err);
+        }
+        let obj_id = ObjectId::from_str(id)?;
+        let update = episode_push_update(index, &episode)?;
+        let result = self
+            .series
+            .update_one(doc! { "_id": obj_id }, update, None)
+            .await
+            .map_err(internal_error(format!(
+                "Error adding episode to season {} of series with id: '{}'",
+                index, id
+            )))?;
+        if result.matched_count == 0 {
+            warn!(
+                "Warn in series /{{id}}/seasons/{{index}}/episodes with id: '{}' [{}]",
+                id,
+                AppError::NotFound
+            );
+            return Err(AppError::NotFound);
+        }
+        self.series_cache.purge(&[obj_id.to_hex()]);
+        let mut map_result: Map<String, Value> = Map::new();
+        map_result.insert(
+            "message".to_string(),
+            Value::String(format!(
+                "Episode was successfully added to season {} of series with id: '{}'",
+                index, id
+            )),
+        );
+        Ok(map_result)
+    }
+
+    async fn replace_season_list(
+        &self,
+        id: &str,
+        season_list: Vec<Season>,
+    ) -> Result<Map<String, Value>, AppError> {
+        info!("PUT series /{{id}}/seasons with id: '{}' executed", id);
+        let obj_id = ObjectId::from_str(id)?;
+        let number_of_seasons = season_list.len();
+        let result = self
+            .series
+            .update_one(
+                doc! { "_id": obj_id },
+                season_list_update(&season_list)?,
+                None,
+            )
+            .await
+            .map_err(internal_error(format!(
+                "Error replacing the seasons of series with id: '{}'",
+                id
+            )))?;
+        if result.matched_count == 0 {
+            warn!(
+                "Warn in series /{{id}}/seasons with id: '{}' [{}]",
+                id,
+                AppError::NotFound
+            );
+            return Err(AppError::NotFound);
+        }
+        self.series_cache.purge(&[obj_id.to_hex()]);
+        let mut map_result: Map<String, Value> = Map::new();
+        map_result.insert(
+            "message".to_string(),
+            Value::String(format!(
+                "Seasons of series with id: '{}' were successfully replaced",
+                id
+            )),
+        );
+        map_result.insert(
+            "numberOfSeasons".to_string(),
+            Value::from(number_of_seasons),
+        );
+        Ok(map_result)
+    }
+
+    async fn find_flattened_episodes(
+        &self,
+        id: &str,
+        page: Option<u32>,
+        size: Option<u32>,
+    ) -> Result<Map<String, Value>, AppError> {
+        info!(
+            "GET series /findById/episodes/all with id: '{}' executed",
+            id
+        );
+        let oid = ObjectId::from_str(id)?;
+        let page_num = current_page(page);
+        let page_size = match size {
+            Some(size) if size > 0 => size,
+            _ => 10,
+        };
+
+        let exists = self
+            .series
+            .count_documents(doc! {"_id": oid}, CountOptions::default())
+            .await
+            .map_err(internal_error("Error counting series by id"))?;
+        if exists == 0 {
+            warn!(
+                "Warn in series /findById/episodes/all with id: '{}' [{}]",
+                id,
+                AppError::NotFound
+            );
+            return Err(AppError::NotFound);
+        }
+
+        let pipeline = flattened_episodes_pipeline(
+            oid,
+            page_skip(page_num - 1, page_size)? as i64,
+            page_size as i64,
+        );
+        let facet: Option<Document> = match self
+            .series
+            .clone_with_type::<Document>()
+            .aggregate(pipeline, None)
+            .await
+        {
+            Ok(mut cursor) => cursor.try_next().await.ok().flatten(),
+            Err(_) => {
+                error!(
+                    "Error in series /findById/episodes/all with id: '{}' [{}]",
+                    id,
+                    AppError::InternalServerError
+                );
+                return Err(AppError::InternalServerError);
+            }
+        };
+        let facet = facet.unwrap_or_default();
+        let episode_list: Vec<FlatEpisode> = facet
+            .get_array("episodes")
+            .map(|episodes| {
+                episodes
+                    .iter()
+                    .filter_map(|episode| episode.as_document().and_then(to_flat_episode))
+                    .collect()
+            })
+            .unwrap_or_default();
+        if episode_list.is_empty() {
+            warn!(
+                "Warn in series /findById/episodes/all with id: '{}' [{}]",
+                id,
+                AppError::Empty
+            );
+            return Err(AppError::Empty);
+        }
+        let total_items = facet
+            .get_array("total")
+            .ok()
+            .and_then(|total| total.first())
+            .and_then(|total| total.as_document())
+            .and_then(|total| total.get_i32("count").ok())
+            .unwrap_or_default() as u64;
+
+        let mut result_map: Map<String, Value> = Map::new();
+        result_map.insert(
+            "episodes".to_string(),
+            serde_json::to_value(episode_list).unwrap(),
+        );
+        Ok(build_pagination_map(
+            result_map,
+            page_num,
+            page_size,
+            total_items,
+        ))
+    }
+}
+
+/// `$set` of `/update`, refreshing `updatedAt` (`createdAt` is kept).
+fn build_update_set(series: SeriesRequest) -> Result<Document, AppError> {
+    let season_list =
+        to_bson(&series.season_list).map_err(internal_error("Error converting the seasons"))?;
+    Ok(doc! {
+        "imdbId": series.imdb_id,
+        "title": series.title,
+        "overview": series.overview,
+        "numberOfSeasons": series.number_of_seasons,
+        "creator": series.creator,
+        "releaseDate": series.release_date,
+        "trailerLink": series.trailer_link,
+        "genres": series.genres,
+        "seasonList": season_list,
+        "poster": series.poster,
+        "backdrop": series.backdrop,
+        "updatedAt": DateTime::now(),
+    })
+}
+
+/// Update of `/patch`: `seasonList` gets the validation and update of `PUT /{id}/seasons`
+/// (season cap included, `numberOfSeasons` kept equal to its length), the rest a single `$set`.
+fn patch_update(field: &str, val: &str) -> Result<Document, AppError> {
+    if field == "seasonList" {
+        let request: SeasonListRequest =
+            serde_json::from_str(val).map_err(|err| invalid_array(field, val, err))?;
+        request.validate()?;
+        return season_list_update(&request.season_list);
+    }
+    Ok(doc! {
+        "$set": {
+            field: patch_value(field, val)?,
+            "updatedAt": DateTime::now(),
+        },
+    })
+}
+
+/// `val` as stored for `field`: `numberOfSeasons` is a number, `genres` a JSON array, the rest
+/// is text.
+fn patch_value(field: &str, val: &str) -> Result<Bson, AppError> {
+    match field {
+        "numberOfSeasons" => match val.trim().parse::<u32>() {
+            Ok(number_of_seasons) => to_bson(&number_of_seasons).map_err(internal_error(
+                "Error in series /patch serializing numberOfSeasons",
+            )),
+            Err(_) => {
+                let err = AppError::ValidationAppError(format!(
+                    "numberOfSeasons: '{}' is not valid, use a whole number like '3'",
+                    val
+                ));
+                warn!("Warn in series /patch [{}]", err);
+                Err(err)
+            }
+        },
+        "genres" => serde_json::from_str::<Vec<String>>(val)
+            .map(Bson::from)
+            .map_err(|err| invalid_array(field, val, err)),
+        _ => Ok(Bson::String(val.to_string())),
+    }
+}
+
+fn invalid_array(field: &str, val: &str, err: serde_json::Error) -> AppError {
+    let err = AppError::ValidationAppError(format!(
+        "{}: '{}' is not valid, use a JSON array ({})",
+        field, val, err
+    ));
+    warn!("Warn in series /patch [{}]", err);
+    err
+}
+
+/// `/findAll` and `/count` filter: case-insensitive title regex.
+fn build_series_filter(title: Option<String>) -> Option<Document> {
+    let regex = Regex {
+        pattern: title?,
+        options: String::new(),
+    };
+    Some(doc! { "title": { "$regex": regex, "$options": "i" } })
+}
+
+/// Pushes `episode` onto the season at the 1-based `index` (already checked to exist).
+fn episode_push_update(index: usize, episode: &Episode) -> Result<Document, AppError> {
+    let episode = to_bson(episode).map_err(internal_error("Error converting the episode"))?;
+    Ok(doc! {
+        "$push": { format!("seasonList.{}.episodeList", index - 1): episode },
+        "$set": { "updatedAt": DateTime::now() },
+    })
+}
+
+/// Replaces `seasonList` and keeps `numberOfSeasons` equal to its length.
+fn season_list_update(season_list: &[Season]) -> Result<Document, AppError> {
+    let seasons = to_bson(season_list).map_err(internal_error("Error converting the seasons"))?;
+    let number_of_seasons = to_bson(&(season_list.len() as u32))
+        .map_err(internal_error("Error converting the number of seasons"))?;
+    Ok(doc! {
+        "$set": {
+            "seasonList": seasons,
+            "numberOfSeasons": number_of_seasons,
+            "updatedAt": DateTime::now(),
+        },
+    })
+}
+
+/// Unwinds seasons then episodes, keeping each array position so the guide order is preserved.
+fn flattened_episodes_pipeline(oid: ObjectId, skip: i64, limit: i64) -> Vec<Document> {
+    vec![
+        doc! { "$match": { "_id": oid } },
+        doc! { "$unwind": { "path": "$seasonList", "includeArrayIndex": "seasonIndex" } },
+        doc! { "$unwind": {
+            "path": "$seasonList.episodeList",
+            "includeArrayIndex": "episodeIndex",
+        } },
+        doc! { "$project": {
+            "_id": 0,
+            "seasonIndex": 1,
+            "episodeIndex": 1,
+            "episode": "$seasonList.episodeList",
+        } },
+        doc! { "$sort": { "seasonIndex": 1, "episodeIndex": 1 } },
+        doc! { "$facet": {
+            "episodes": [{ "$skip": skip }, { "$limit": limit }],
+            "total": [{ "$count": "count" }],
+        } },
+    ]
+}
+
+/// `includeArrayIndex` positions are 0-based longs, exposed as 1-based season/episode numbers.
+fn to_flat_episode(unwound: &Document) -> Option<FlatEpisode> {
+    let episode = unwound.get_document("episode").ok()?;
+    let field = |name: &str| episode.get_str(name).unwrap_or_default().to_string();
+    Some(FlatEpisode {
+        season_index: unwound.get_i64("seasonIndex").ok()? as u32 + 1,
+        episode_index: unwound.get_i64("episodeIndex").ok()? as u32 + 1,
+        title: field("title"),
+        release_date: field("releaseDate"),
+        duration: field("duration"),
+        description: field("description"),
+    })
 }
 
 #[cfg(test)]
@@ -483,6 +838,8 @@ mod tests {
             poster: "https://moviedb.com/breaking_bad/poster.jpg".to_string(),
             backdrop: "https://moviedb.com/breaking_bad/backdrop.jpg".to_string(),
             review_ids: vec![ObjectId::new()],
+            created_at: DateTime::now(),
+            updated_at: DateTime::now(),
         }
     }
 
@@ -504,6 +861,104 @@ mod tests {
 
     // Unit Tests
 
+    #[test]
+    fn test_build_update_set_refreshes_updated_at() {
+        let created_at = DateTime::from_millis(1_700_000_000_000);
+        let series = Series {
+            created_at,
+            updated_at: created_at,
+            ..build_series_mock(ObjectId::new())
+        };
+        let update_set = build_update_set(build_series_req_mock()).unwrap();
+        assert!(update_set.get_datetime("updatedAt").is_ok());
+        assert!(!update_set.contains_key("createdAt"));
+
+        let mut stored = mongodb::bson::to_document(&series).unwrap();
+        stored.extend(update_set);
+        let updated: Series = mongodb::bson::from_document(stored).unwrap();
+        assert!(updated.updated_at > series.updated_at);
+        assert_eq!(updated.created_at, created_at);
+        assert_eq!(updated.title, "Breaking Bad");
+    }
+
+    #[test]
+    fn test_patch_value_array_fields() {
+        assert_eq!(
+            patch_value("genres", r#"["Crimen", "Drama"]"#).unwrap(),
+            Bson::from(vec!["Crimen", "Drama"])
+        );
+        let season_list = r#"[{
+            "overview": "Primera temporada",
+            "episodeList": [{
+                "title": "Piloto",
+                "releaseDate": "2008-01-20",
+                "duration": "58m",
+                "description": "Walter White recibe su diagnóstico"
+            }],
+            "poster": "https://moviedb.com/breaking_bad/season1.jpg"
+        }]"#;
+        let update = patch_update("seasonList", season_list).unwrap();
+        let set_doc = update.get_document("$set").unwrap();
+        let seasons = set_doc.get_array("seasonList").unwrap();
+        assert_eq!(seasons.len(), 1);
+        assert_eq!(
+            seasons[0]
+                .as_document()
+                .unwrap()
+                .get_str("overview")
+                .unwrap(),
+            "Primera temporada"
+        );
+        assert_eq!(
+            set_doc.get("numberOfSeasons"),
+            Some(&to_bson(&1u32).unwrap())
+        );
+        assert!(set_doc.get_datetime("updatedAt").is_ok());
+
+        // Same season cap as PUT /{id}/seasons
+        let too_many = format!(
+            "[{}]",
+            vec![season_list.trim_matches(['[', ']']); 1000].join(",")
+        );
+        let err = patch_update("seasonList", &too_many).unwrap_err();
+        let AppError::InvalidFields(field_errors) = err else {
+            panic!("expected InvalidFields, got {err:?}");
+        };
+        assert_eq!(field_errors[0].field, "seasonList");
+    }
+
+    #[test]
+    fn test_patch_value_array_fields_malformed() {
+        for (field, val) in [
+            ("genres", "Crimen,Drama"),
+            ("seasonList", r#"[{"overview": "Sin episodios"}]"#),
+        ] {
+            let result = patch_update(field, val);
+            assert!(
+                matches!(result, Err(AppError::ValidationAppError(ref msg)) if msg.starts_with(field)),
+                "{field}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_patch_value_number_of_seasons() {
+        let patched = patch_value("numberOfSeasons", " 5 ").unwrap();
+        assert_eq!(patched, to_bson(&5u32).unwrap());
+        let mut stored = mongodb::bson::to_document(&build_series_mock(ObjectId::new())).unwrap();
+        stored.insert("numberOfSeasons", patched);
+        let series: Series = mongodb::bson::from_document(stored).unwrap();
+        assert_eq!(series.number_of_seasons, 5);
+
+        for val in ["cinco", "-1", "2.5", ""] {
+            let result = patch_value("numberOfSeasons", val);
+            assert!(
+                matches!(result, Err(AppError::ValidationAppError(ref msg)) if msg.starts_with("numberOfSeasons:")),
+                "{val}"
+            );
+        }
+    }
+
     #[actix_web::test]
     async fn test_find_all_series_ok() {
         let mut mock = MockSeriesRepository::new();
@@ -513,16 +968,23 @@ mod tests {
             let series = SeriesResponse {
                 imdb_id: "tt12345".to_string(),
                 title: "Breaking Bad".to_string(),
+                creator: "Vince Gilligan".to_string(),
                 number_of_seasons: 5,
                 release_date: "1990-3-4".to_string(),
                 poster: "https://moviedb.com/breaking_bad/poster.jpg".to_string(),
+                review_count: 0,
             };
+            let reviewed_series = SeriesResponse::try_from(Series {
+                review_ids: vec![ObjectId::new(), ObjectId::new()],
+                ..build_series_mock(ObjectId::new())
+            })
+            .unwrap();
             result_map.insert(
                 "series".to_string(),
-                serde_json::to_value(vec![series]).unwrap(),
+                serde_json::to_value(vec![series, reviewed_series]).unwrap(),
             );
             result_map.insert("currentPage".to_string(), serde_json::to_value(1).unwrap());
-            result_map.insert("totalItems".to_string(), serde_json::to_value(1).unwrap());
+            result_map.insert("totalItems".to_string(), serde_json::to_value(2).unwrap());
             result_map.insert("totalPages".to_string(), serde_json::to_value(1).unwrap());
             Ok(result_map)
         });
@@ -533,12 +995,15 @@ mod tests {
 
         let map = result.unwrap();
         assert_eq!(map.get("currentPage").unwrap(), 1);
-        assert_eq!(map.get("totalItems").unwrap(), 1);
+        assert_eq!(map.get("totalItems").unwrap(), 2);
         assert_eq!(map.get("totalPages").unwrap(), 1);
 
         let series_list = map.get("series").unwrap().as_array().unwrap();
-        assert_eq!(series_list.len(), 1);
+        assert_eq!(series_list.len(), 2);
         assert_eq!(series_list[0].get("title").unwrap(), "Breaking Bad");
+        assert_eq!(series_list[0].get("creator").unwrap(), "Vince Gilligan");
+        assert_eq!(series_list[0].get("reviewCount").unwrap(), 0);
+        assert_eq!(series_list[1].get("reviewCount").unwrap(), 2);
     }
 
     #[actix_web::test]
@@ -672,8 +1137,9 @@ mod tests {
 
         mock.expect_create_series().returning({
             let msg = crt_msg.clone();
-            move |_| {
+            move |series| {
                 let mut map_result: Map<String, Value> = Map::new();
+                map_result.insert("id".to_string(), Value::String(series._id.to_hex()));
                 map_result.insert("message".to_string(), Value::String(msg.clone()));
                 Ok(map_result)
             }
@@ -681,7 +1147,7 @@ mod tests {
 
         let result = mock.create_series(series).await;
 
-        assert!(result.is_ok_and(|map| map["message"] == crt_msg));
+        assert!(result.is_ok_and(|map| map["message"] == crt_msg && map["id"] == oid.to_hex()));
     }
 
     #[actix_web::test]
@@ -744,16 +1210,16 @@ mod tests {
     }
 
     #[actix_web::test]
-    async fn test_delete_series_not_exists() {
+    async fn test_delete_series_not_found() {
         let mut mock = MockSeriesRepository::new();
         let oid = ObjectId::new();
 
         mock.expect_delete_series()
-            .returning(|_| Err(AppError::NotExists));
+            .returning(|_| Err(AppError::NotFound));
 
         let result = mock.delete_series(oid.to_string().as_str()).await;
 
-        assert!(result.is_err_and(|err| err == AppError::NotExists));
+        assert!(result.is_err_and(|err| err == AppError::NotFound));
     }
 
     #[actix_web::test]
@@ -966,4 +1432,309 @@ mod tests {
 
         assert!(result.is_err_and(|err| err == AppError::InternalServerError));
     }
+
+    #[actix_web::test]
+    async fn test_total_runtime_ok() {
+        let mut mock = MockSeriesRepository::new();
+        let oid = ObjectId::new();
+
+        mock.expect_total_runtime().returning(|_| {
+            let mut map_result: Map<String, Value> = Map::new();
+            map_result.insert(
+                "totalMinutes".to_string(),
+                serde_json::to_value(231).unwrap(),
+            );
+            map_result.insert("formatted".to_string(), Value::String("3h 51m".to_string()));
+            Ok(map_result)
+        });
+
+        let result = mock.total_runtime(oid.to_string().as_str()).await;
+        assert!(result.is_ok_and(|map| map["totalMinutes"] == 231 && map["formatted"] == "3h 51m"));
+    }
+
+    #[actix_web::test]
+    async fn test_total_runtime_not_found() {
+        let mut mock = MockSeriesRepository::new();
+        let oid = ObjectId::new();
+
+        mock.expect_total_runtime()
+            .returning(|_| Err(AppError::NotFound));
+
+        let result = mock.total_runtime(oid.to_string().as_str()).await;
+        assert!(result.is_err_and(|err| err == AppError::NotFound));
+    }
+
+    #[actix_web::test]
+    async fn test_find_series_season_ok() {
+        let mut mock = MockSeriesRepository::new();
+        let oid = ObjectId::new();
+
+        mock.expect_find_series_season()
+            .withf(|_, index| *index == 2)
+            .returning(|_, _| {
+                Ok(serde_json::from_value(serde_json::json!({
+                    "overview": "Segunda temporada",
+                    "episodeList": [],
+                    "poster": "https://moviedb.com/breaking_bad/season_2.jpg",
+                }))
+                .unwrap())
+            });
+
+        let result = mock.find_series_season(oid.to_string().as_str(), 2).await;
+        assert!(result.is_ok_and(|season| {
+            serde_json::to_value(season).unwrap()["overview"] == "Segunda temporada"
+        }));
+    }
+
+    #[actix_web::test]
+    async fn test_find_series_season_out_of_range() {
+        let mut mock = MockSeriesRepository::new();
+        let oid = ObjectId::new();
+
+        mock.expect_find_series_season()
+            .returning(|_, _| Err(AppError::NotFound));
+
+        let result = mock.find_series_season(oid.to_string().as_str(), 9).await;
+        assert!(result.is_err_and(|err| err == AppError::NotFound));
+    }
+
+    #[actix_web::test]
+    async fn test_find_series_season_cannot_parse_obj_id() {
+        let mut mock = MockSeriesRepository::new();
+
+        mock.expect_find_series_season()
+            .returning(|_, _| Err(AppError::CannotParseObjId));
+
+        let result = mock.find_series_season("1234", 1).await;
+        assert!(result.is_err_and(|err| err == AppError::CannotParseObjId));
+    }
+
+    #[test]
+    fn test_build_series_filter_title() {
+        assert!(build_series_filter(None).is_none());
+        let filter = build_series_filter(Some("breaking".to_string())).unwrap();
+        let title = filter.get_document("title").unwrap();
+        assert!(matches!(
+            title.get("$regex"),
+            Some(Bson::RegularExpression(re)) if re.pattern == "breaking"
+        ));
+        assert_eq!(title.get_str("$options").unwrap(), "i");
+    }
+
+    #[actix_web::test]
+    async fn test_count_series_filtered() {
+        let mut mock = MockSeriesRepository::new();
+
+        mock.expect_count_series().returning(|title| {
+            let count = if title.is_some() { 1 } else { 4 };
+            let mut map_result: Map<String, Value> = Map::new();
+            map_result.insert("count".to_string(), serde_json::to_value(count).unwrap());
+            Ok(map_result)
+        });
+
+        let all = mock.count_series(None).await;
+        assert!(all.is_ok_and(|map| map["count"] == 4));
+        let filtered = mock.count_series(Some("Breaking".to_string())).await;
+        assert!(filtered.is_ok_and(|map| map["count"] == 1));
+    }
+
+    #[test]
+    fn test_episode_push_update_targets_season() {
+        let episode: Episode = serde_json::from_value(serde_json::json!({
+            "title": "Piloto",
+            "releaseDate": "2008-01-20",
+            "duration": "58m",
+            "description": "Walter White descubre que tiene cáncer.",
+        }))
+        .unwrap();
+        let update = episode_push_update(2, &episode).unwrap();
+        let pushed = update
+            .get_document("$push")
+            .unwrap()
+            .get_document("seasonList.1.episodeList")
+            .unwrap();
+        assert_eq!(pushed.get_str("title").unwrap(), "Piloto");
+        assert!(update
+            .get_document("$set")
+            .unwrap()
+            .contains_key("updatedAt"));
+    }
+
+    #[actix_web::test]
+    async fn test_add_season_episode_ok() {
+        let mut mock = MockSeriesRepository::new();
+        let oid = ObjectId::new();
+        let added_msg = format!(
+            "Episode was successfully added to season 1 of series with id: '{}'",
+            oid
+        );
+
+        mock.expect_add_season_episode().returning({
+            let msg = added_msg.clone();
+            move |_, _, _| {
+                let mut map_result: Map<String, Value> = Map::new();
+                map_result.insert("message".to_string(), Value::String(msg.clone()));
+                Ok(map_result)
+            }
+        });
+
+        let episode: Episode = serde_json::from_value(serde_json::json!({
+            "title": "Piloto",
+            "releaseDate": "2008-01-20",
+            "duration": "58m",
+            "description": "Walter White descubre que tiene cáncer.",
+        }))
+        .unwrap();
+        let result = mock
+            .add_season_episode(oid.to_string().as_str(), 1, episode)
+            .await;
+        assert!(result.is_ok_and(|map| map["message"] == added_msg));
+    }
+
+    fn build_season_list_json(episode_count: usize) -> serde_json::Value {
+        let episodes: Vec<serde_json::Value> = (1..=episode_count)
+            .map(|number| {
+                serde_json::json!({
+                    "title": format!("Episodio {}", number),
+                    "releaseDate": "2008-01-20",
+                    "duration": "47m",
+                    "description": "Walter White cruza la línea.",
+                })
+            })
+            .collect();
+        serde_json::json!([
+            {
+                "overview": "Primera temporada",
+                "episodeList": episodes,
+                "poster": "https://moviedb.com/breaking_bad/season1.jpg",
+            },
+            {
+                "overview": "Segunda temporada",
+                "episodeList": episodes,
+                "poster": "https://moviedb.com/breaking_bad/season2.jpg",
+            },
+        ])
+    }
+
+    #[test]
+    fn test_season_list_update_sets_number_of_seasons() {
+        let request: SeasonListRequest = serde_json::from_value(build_season_list_json(1)).unwrap();
+        assert!(request.validate().is_ok());
+
+        let update = season_list_update(&request.season_list).unwrap();
+        let set = update.get_document("$set").unwrap();
+        assert_eq!(set.get_array("seasonList").unwrap().len(), 2);
+        assert_eq!(set.get("numberOfSeasons"), Some(&to_bson(&2u32).unwrap()));
+        assert!(set.get_datetime("updatedAt").is_ok());
+    }
+
+    #[test]
+    fn test_season_list_request_rejects_empty_episode_list() {
+        let request: SeasonListRequest = serde_json::from_value(build_season_list_json(0)).unwrap();
+
+        let err = AppError::from(request.validate().unwrap_err());
+        let AppError::InvalidFields(field_errors) = err else {
+            panic!("expected InvalidFields, got {err:?}");
+        };
+        let fields: Vec<&str> = field_errors
+            .iter()
+            .map(|field_error| field_error.field.as_str())
+            .collect();
+        assert_eq!(
+            fields,
+            vec!["seasonList[0].episodeList", "seasonList[1].episodeList"]
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_replace_season_list_ok() {
+        let mut mock = MockSeriesRepository::new();
+        let oid = ObjectId::new();
+
+        mock.expect_replace_season_list()
+            .returning(|id, season_list| {
+                let mut map_result: Map<String, Value> = Map::new();
+                map_result.insert(
+                    "message".to_string(),
+                    Value::String(format!(
+                        "Seasons of series with id: '{}' were successfully replaced",
+                        id
+                    )),
+                );
+                map_result.insert(
+                    "numberOfSeasons".to_string(),
+                    Value::from(season_list.len()),
+                );
+                Ok(map_result)
+            });
+
+        let request: SeasonListRequest = serde_json::from_value(build_season_list_json(3)).unwrap();
+        let result = mock
+            .replace_season_list(oid.to_string().as_str(), request.season_list)
+            .await;
+        assert!(result.is_ok_and(|map| map["numberOfSeasons"] == 2));
+    }
+
+    #[test]
+    fn test_flattened_episodes_pipeline_unwinds_in_order() {
+        let oid = ObjectId::new();
+        let pipeline = flattened_episodes_pipeline(oid, 10, 10);
+
+        assert_eq!(pipeline[0], doc! { "$match": { "_id": oid } });
+        let seasons = pipeline[1].get_document("$unwind").unwrap();
+        assert_eq!(seasons.get_str("path").unwrap(), "$seasonList");
+        let episodes = pipeline[2].get_document("$unwind").unwrap();
+        assert_eq!(episodes.get_str("path").unwrap(), "$seasonList.episodeList");
+        let sort = pipeline[4].get_document("$sort").unwrap();
+        assert_eq!(
+            sort.keys().collect::<Vec<_>>(),
+            vec!["seasonIndex", "episodeIndex"]
+        );
+    }
+
+    #[test]
+    fn test_to_flat_episode_two_seasons() {
+        let episode = |title: &str| {
+            doc! {
+                "title": title,
+                "releaseDate": "2008-01-20",
+                "duration": "58m",
+                "description": "Walter White empieza a cocinar.",
+            }
+        };
+        // Documents as emitted by the two $unwind stages for a two-season series
+        let unwound = [
+            doc! { "seasonIndex": 0_i64, "episodeIndex": 0_i64, "episode": episode("Piloto") },
+            doc! { "seasonIndex": 0_i64, "episodeIndex": 1_i64, "episode": episode("El gato está en la bolsa") },
+            doc! { "seasonIndex": 1_i64, "episodeIndex": 0_i64, "episode": episode("Siete treinta y siete") },
+        ];
+
+        let flat: Vec<FlatEpisode> = unwound.iter().filter_map(to_flat_episode).collect();
+        let positions: Vec<(u32, u32, &str)> = flat
+            .iter()
+            .map(|ep| (ep.season_index, ep.episode_index, ep.title.as_str()))
+            .collect();
+        assert_eq!(
+            positions,
+            vec![
+                (1, 1, "Piloto"),
+                (1, 2, "El gato está en la bolsa"),
+                (2, 1, "Siete treinta y siete"),
+            ]
+        );
+        assert_eq!(flat[0].duration, "58m");
+    }
+
+    #[actix_web::test]
+    async fn test_find_flattened_episodes_not_found() {
+        let mut mock = MockSeriesRepository::new();
+
+        mock.expect_find_flattened_episodes()
+            .returning(|_, _, _| Err(AppError::NotFound));
+
+        let result = mock
+            .find_flattened_episodes(ObjectId::new().to_hex().as_str(), None, None)
+            .await;
+        assert!(result.is_err_and(|err| err == AppError::NotFound));
+    }
 }
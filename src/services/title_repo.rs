@@ -0,0 +1,878 @@
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+use futures_util::TryStreamExt;
+use log::{error, info, warn};
+use mongodb::{
+    bson::{doc, from_document, Bson, Document},
+    options::{CountOptions, FindOptions},
+    Collection,
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::{
+    error::{internal_error, AppError},
+    models::{
+        movie::MovieDoc,
+        series::SeriesDoc,
+        title::{MostReviewedTitle, PopularTitle, SearchHit},
+        validation::{is_valid_imdb_id, normalize_imdb_id},
+    },
+};
+
+use super::{
+    db::Database,
+    movie_repo::MovieRepository,
+    pagination::{build_pagination_map, current_page, page_skip},
+    series_repo::SeriesRepository,
+};
+
+/// Number of reviews a title needs before its own average outweighs the global one.
+const POPULARITY_MIN_REVIEWS: f64 = 5.0;
+
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait TitleRepository {
+    async fn find_popular_titles(&self, limit: Option<u32>)
+        -> Result<Map<String, Value>, AppError>;
+    async fn find_most_reviewed_titles(
+        &self,
+        genre: &str,
+        limit: Option<u32>,
+    ) -> Result<Map<String, Value>, AppError>;
+    async fn find_missing_imdb_ids(
+        &self,
+        imdb_ids: Vec<String>,
+    ) -> Result<Map<String, Value>, AppError>;
+    async fn search_all(
+        &self,
+        query: &str,
+        page: Option<u32>,
+        size: Option<u32>,
+    ) -> Result<Map<String, Value>, AppError>;
+    async fn find_title_by_imdb_id(&self, imdb_id: &str) -> Result<Map<String, Value>, AppError>;
+}
+
+#[async_trait]
+impl TitleRepository for Database {
+    async fn find_popular_titles(
+        &self,
+        limit: Option<u32>,
+    ) -> Result<Map<String, Value>, AppError> {
+        info!("GET titles /popular executed");
+        let limit = match limit {
+            Some(limit) if limit > 0 => limit.min(100),
+            _ => 10,
+        };
+
+        let global_mean = self.global_average_rating().await?;
+        let pipeline = popular_pipeline(self.reviews.name(), global_mean, limit as i64);
+        let movie_titles = collect_popular(
+            self.movies.clone_with_type::<Document>(),
+            pipeline.clone(),
+            "movie",
+        )
+        .await?;
+        let series_titles = collect_popular(
+            self.series.clone_with_type::<Document>(),
+            pipeline,
+            "series",
+        )
+        .await?;
+
+        let title_list = merge_popular(movie_titles, series_titles, limit as usize);
+        if title_list.is_empty() {
+            return Err(AppError::Empty);
+        }
+
+        let mut result_map: Map<String, Value> = Map::new();
+        result_map.insert(
+            "titles".to_string(),
+            serde_json::to_value(title_list).unwrap(),
+        );
+        result_map.insert(
+            "globalAverageRating".to_string(),
+            serde_json::to_value(global_mean).unwrap(),
+        );
+        Ok(result_map)
+    }
+
+    async fn find_most_reviewed_titles(
+        &self,
+        genre: &str,
+        limit: Option<u32>,
+    ) -> Result<Map<String, Value>, AppError> {
+        info!("GET titles /mostReviewed with genre: '{}' executed", genre);
+        let genre = genre.trim();
+        if genre.is_empty() {
+            return Err(AppError::ValidationAppError(
+                "genre: The genre cannot be empty".to_string(),
+            ));
+        }
+        let limit = match limit {
+            Some(limit) if limit > 0 => limit.min(100),
+            _ => 10,
+        };
+
+        let pipeline = most_reviewed_pipeline(genre, limit as i64);
+        let movie_titles = collect_most_reviewed(
+            self.movies.clone_with_type::<Document>(),
+            pipeline.clone(),
+            "movie",
+        )
+        .await?;
+        let series_titles = collect_most_reviewed(
+            self.series.clone_with_type::<Document>(),
+            pipeline,
+            "series",
+        )
+        .await?;
+        let title_list = merge_most_reviewed(movie_titles, series_titles, limit as usize);
+        if title_list.is_empty() {
+            return Err(AppError::Empty);
+        }
+
+        let mut result_map: Map<String, Value> = Map::new();
+        result_map.insert("genre".to_string(), Value::from(genre));
+        result_map.insert(
+            "titles".to_string(),
+            serde_json::to_value(title_list).unwrap(),
+        );
+        Ok(result_map)
+    }
+
+    async fn find_missing_imdb_ids(
+        &self,
+        imdb_ids: Vec<String>,
+    ) -> Result<Map<String, Value>, AppError> {
+        info!("POST titles /missing executed");
+        let mut present =
+            present_imdb_ids(self.movies.clone_with_type::<Document>(), &imdb_ids).await?;
+        present
+            .extend(present_imdb_ids(self.series.clone_with_type::<Document>(), &imdb_ids).await?);
+        let missing = missing_imdb_ids(&imdb_ids, &present);
+
+        let mut result_map: Map<String, Value> = Map::new();
+        result_map.insert(
+            "missingCount".to_string(),
+            serde_json::to_value(missing.len()).unwrap(),
+        );
+        result_map.insert(
+            "missingImdbIds".to_string(),
+            serde_json::to_value(missing).unwrap(),
+        );
+        Ok(result_map)
+    }
+
+    async fn search_all(
+        &self,
+        query: &str,
+        page: Option<u32>,
+        size: Option<u32>,
+    ) -> Result<Map<String, Value>, AppError> {
+        info!("GET /search with query: '{}' executed", query);
+        let query = query.trim();
+        if query.is_empty() {
+            return Err(AppError::ValidationAppError(
+                "query: The search query cannot be empty".to_string(),
+            ));
+        }
+        let page_num = current_page(page);
+        let page_size = match size {
+            Some(size) if size > 0 => size,
+            _ => 10,
+        };
+        let filter = doc! {"title": { "$regex": regex::escape(query), "$options": "i" }};
+
+        let movies = self.movies.clone_with_type::<Document>();
+        let series = self.series.clone_with_type::<Document>();
+        let total_items = movies
+            .count_documents(filter.clone(), CountOptions::default())
+            .await
+            .map_err(internal_error("Error in /search counting movies"))?
+            + series
+                .count_documents(filter.clone(), CountOptions::default())
+                .await
+                .map_err(internal_error("Error in /search counting series"))?;
+
+        let skip = page_skip(page_num - 1, page_size)?;
+        let pipeline = search_pipeline(filter, self.series.name(), skip, page_size as i64);
+        let hit_list = collect_search_hits(movies, pipeline).await?;
+        if hit_list.is_empty() {
+            return Err(AppError::Empty);
+        }
+
+        let mut result_map: Map<String, Value> = Map::new();
+        result_map.insert(
+            "results".to_string(),
+            serde_json::to_value(hit_list).unwrap(),
+        );
+        Ok(build_pagination_map(
+            result_map,
+            page_num,
+            page_size,
+            total_items,
+        ))
+    }
+
+    async fn find_title_by_imdb_id(&self, imdb_id: &str) -> Result<Map<String, Value>, AppError> {
+        let imdb_id = &normalize_imdb_id(imdb_id);
+        info!("GET /findByImdbId with imdbId: '{}' executed", imdb_id);
+        if !is_valid_imdb_id(imdb_id) {
+            warn!(
+                "Warn in /findByImdbId with imdbId: '{}' [{}]",
+                imdb_id,
+                AppError::WrongImdbId
+            );
+            return Err(AppError::WrongImdbId);
+        }
+
+        let context = format!("Error in /findByImdbId converting imdbId: '{}'", imdb_id);
+        if self.movie_exists_by_imdb_id(imdb_id).await? {
+            let movie = self.find_movie_by_imdb_id(imdb_id).await?;
+            let movie_doc = MovieDoc::try_from(movie).map_err(internal_error(context))?;
+            return Ok(tag_title("movie", movie_doc));
+        }
+        if self.series_exists_by_imdb_id(imdb_id).await? {
+            let series = self.find_series_by_imdb_id(imdb_id).await?;
+            let series_doc = SeriesDoc::try_from(series).map_err(internal_error(context))?;
+            return Ok(tag_title("series", series_doc));
+        }
+
+        warn!(
+            "Warn in /findByImdbId with imdbId: '{}' [{}]",
+            imdb_id,
+            AppError::NotFound
+        );
+        Err(AppError::NotFound)
+    }
+}
+
+impl Database {
+    async fn global_average_rating(&self) -> Result<f64, AppError> {
+        let pipeline = vec![doc! { "$group": { "_id": null, "avg": { "$avg": "$rating" } } }];
+        let docs: Vec<Document> = self
+            .reviews
+            .aggregate(pipeline, None)
+            .await
+            .map_err(internal_error(
+                "Error in titles /popular computing global rating",
+            ))?
+            .try_collect()
+            .await
+            .map_err(internal_error(
+                "Error in titles /popular reading global rating",
+            ))?;
+        Ok(docs
+            .first()
+            .and_then(|avg_doc| avg_doc.get_f64("avg").ok())
+            .unwrap_or(0.0))
+    }
+}
+
+async fn present_imdb_ids(
+    collection: Collection<Document>,
+    imdb_ids: &[String],
+) -> Result<HashSet<String>, AppError> {
+    let options = FindOptions::builder()
+        .projection(doc! { "_id": 0, "imdbId": 1 })
+        .build();
+    let cursor = match collection
+        .find(doc! { "imdbId": { "$in": imdb_ids } }, options)
+        .await
+    {
+        Ok(cursor) => cursor,
+        Err(_) => {
+            error!(
+                "Error in titles /missing finding {} [{}]",
+                collection.name(),
+                AppError::InternalServerError
+            );
+            return Err(AppError::InternalServerError);
+        }
+    };
+    let docs: Vec<Document> = match cursor.try_collect().await {
+        Ok(docs) => docs,
+        Err(_) => {
+            error!(
+                "Error in titles /missing collecting {} [{}]",
+                collection.name(),
+                AppError::InternalServerError
+            );
+            return Err(AppError::InternalServerError);
+        }
+    };
+    Ok(docs
+        .iter()
+        .filter_map(|title_doc| title_doc.get_str("imdbId").ok())
+        .map(|imdb_id| imdb_id.to_string())
+        .collect())
+}
+
+/// Requested ids not present, in request order and without repeats.
+fn missing_imdb_ids(requested: &[String], present: &HashSet<String>) -> Vec<String> {
+    let mut seen: HashSet<&str> = HashSet::new();
+    requested
+        .iter()
+        .filter(|imdb_id| !present.contains(*imdb_id) && seen.insert(imdb_id.as_str()))
+        .cloned()
+        .collect()
+}
+
+/// First `fetch` hits of one collection, ordered like the merged list (title, then `_id`).
+/// Title hits of movies and series in one aggregation: the series are unioned in, then the
+/// merged list is sorted (movies first on ties) and paged by the database.
+fn search_pipeline(filter: Document, series_coll: &str, skip: u64, limit: i64) -> Vec<Document> {
+    let hit_stages = |media_type: &str| {
+        vec![
+            doc! { "$match": filter.clone() },
+            doc! { "$project": {
+                "imdbId": 1,
+                "title": 1,
+                "releaseDate": 1,
+                "poster": 1,
+                "type": { "$literal": media_type },
+            } },
+        ]
+    };
+    let mut pipeline = hit_stages("movie");
+    pipeline.extend([
+        doc! { "$unionWith": { "coll": series_coll, "pipeline": hit_stages("series") } },
+        doc! { "$sort": { "title": 1, "type": 1, "_id": 1 } },
+        doc! { "$skip": skip as i64 },
+        doc! { "$limit": limit },
+    ]);
+    pipeline
+}
+
+async fn collect_search_hits(
+    movies: Collection<Document>,
+    pipeline: Vec<Document>,
+) -> Result<Vec<SearchHit>, AppError> {
+    let cursor = movies
+        .aggregate(pipeline, None)
+        .await
+        .map_err(internal_error("Error in /search aggregating titles"))?;
+    let docs: Vec<Document> = cursor
+        .try_collect()
+        .await
+        .map_err(internal_error("Error in /search collecting titles"))?;
+    Ok(docs
+        .into_iter()
+        .filter_map(|hit_doc| from_document::<SearchHit>(hit_doc).ok())
+        .collect())
+}
+
+/// Titles of `genre` with the most reviews first, the same stages run on movies and series.
+fn most_reviewed_pipeline(genre: &str, limit: i64) -> Vec<Document> {
+    vec![
+        doc! { "$match": { "genres": genre } },
+        doc! { "$project": {
+            "_id": 0,
+            "imdbId": 1,
+            "title": 1,
+            "poster": 1,
+            "reviewCount": { "$size": { "$ifNull": ["$reviewIds", []] } },
+        }},
+        doc! { "$sort": { "reviewCount": -1, "title": 1 } },
+        doc! { "$limit": limit },
+    ]
+}
+
+async fn collect_most_reviewed(
+    collection: Collection<Document>,
+    pipeline: Vec<Document>,
+    media_type: &str,
+) -> Result<Vec<MostReviewedTitle>, AppError> {
+    let cursor = collection
+        .aggregate(pipeline, None)
+        .await
+        .map_err(internal_error(format!(
+            "Error in titles /mostReviewed aggregating {}",
+            media_type
+        )))?;
+    let docs: Vec<Document> = cursor.try_collect().await.map_err(internal_error(format!(
+        "Error in titles /mostReviewed collecting {}",
+        media_type
+    )))?;
+    Ok(docs
+        .into_iter()
+        .filter_map(|title_doc| from_document::<MostReviewedTitle>(title_doc).ok())
+        .map(|mut title| {
+            title.media_type = media_type.to_string();
+            title
+        })
+        .collect())
+}
+
+/// Merges both leaderboards by review count (descending, title on ties) and keeps `limit`.
+fn merge_most_reviewed(
+    movie_titles: Vec<MostReviewedTitle>,
+    series_titles: Vec<MostReviewedTitle>,
+    limit: usize,
+) -> Vec<MostReviewedTitle> {
+    let mut titles = movie_titles;
+    titles.extend(series_titles);
+    titles.sort_by(|a, b| {
+        b.review_count
+            .cmp(&a.review_count)
+            .then_with(|| a.title.cmp(&b.title))
+    });
+    titles.truncate(limit);
+    titles
+}
+
+/// Most popular titles with reviews, the same stages run on movies and series. The score is the
+/// Bayesian average `score = (v / (v + m)) * R + (m / (v + m)) * C`, where `v` is the review
+/// count, `R` the title's average rating, `C` the average rating of every review and `m` is
+/// [`POPULARITY_MIN_REVIEWS`]. Titles with few reviews are pulled towards the global mean.
+fn popular_pipeline(reviews_coll: &str, global_mean: f64, limit: i64) -> Vec<Document> {
+    let weight = |numerator: Bson| {
+        doc! { "$divide": [numerator, { "$add": ["$reviewCount", POPULARITY_MIN_REVIEWS] }] }
+    };
+    vec![
+        doc! { "$match": { "reviewIds.0": { "$exists": true } } },
+        doc! { "$lookup": {
+            "from": reviews_coll,
+            "localField": "reviewIds",
+            "foreignField": "_id",
+            "as": "reviews"
+        }},
+        doc! { "$project": {
+            "_id": 0,
+            "imdbId": 1,
+            "title": 1,
+            "poster": 1,
+            "reviewCount": { "$size": "$reviewIds" },
+            "averageRating": { "$ifNull": [{ "$avg": "$reviews.rating" }, 0.0] }
+        }},
+        doc! { "$addFields": { "score": { "$add": [
+            { "$multiply": [weight(Bson::from("$reviewCount")), "$averageRating"] },
+            { "$multiply": [weight(Bson::from(POPULARITY_MIN_REVIEWS)), global_mean] },
+        ] } } },
+        doc! { "$sort": { "score": -1, "reviewCount": -1, "imdbId": 1 } },
+        doc! { "$limit": limit },
+    ]
+}
+
+async fn collect_popular(
+    collection: Collection<Document>,
+    pipeline: Vec<Document>,
+    media_type: &str,
+) -> Result<Vec<PopularTitle>, AppError> {
+    let cursor = collection
+        .aggregate(pipeline, None)
+        .await
+        .map_err(internal_error(format!(
+            "Error in titles /popular aggregating {}",
+            media_type
+        )))?;
+    let docs: Vec<Document> = cursor.try_collect().await.map_err(internal_error(format!(
+        "Error in titles /popular collecting {}",
+        media_type
+    )))?;
+    Ok(docs
+        .into_iter()
+        .filter_map(|title_doc| from_document::<PopularTitle>(title_doc).ok())
+        .map(|mut title| {
+            title.media_type = media_type.to_string();
+            title
+        })
+        .collect())
+}
+
+/// Merges both already scored lists by score (review count on ties) and keeps `limit`.
+fn merge_popular(
+    movie_titles: Vec<PopularTitle>,
+    series_titles: Vec<PopularTitle>,
+    limit: usize,
+) -> Vec<PopularTitle> {
+    let mut titles = movie_titles;
+    titles.extend(series_titles);
+    titles.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then(b.review_count.cmp(&a.review_count))
+    });
+    titles.truncate(limit);
+    titles
+}
+
+/// The movie or series doc with a `type` field, as `/findByImdbId` answers it.
+fn tag_title<T: Serialize>(media_type: &str, title_doc: T) -> Map<String, Value> {
+    let mut title_map = match serde_json::to_value(title_doc) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    };
+    title_map.insert("type".to_string(), Value::from(media_type));
+    title_map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Auxiliary Functions
+
+    /// Evaluates the `$add`/`$multiply`/`$divide` expressions of the popularity score on a
+    /// projected title document, the way the `$addFields` stage does.
+    fn eval_expr(expr: &Bson, title_doc: &Document) -> f64 {
+        match expr {
+            Bson::String(field) => eval_expr(title_doc.get(&field[1..]).unwrap(), title_doc),
+            Bson::Document(op) => {
+                let (name, args) = op.iter().next().unwrap();
+                let args: Vec<f64> = args
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|arg| eval_expr(arg, title_doc))
+                    .collect();
+                match name.as_str() {
+                    "$add" => args.iter().sum(),
+                    "$multiply" => args.iter().product(),
+                    "$divide" => args[0] / args[1],
+                    _ => panic!("unexpected operator {name}"),
+                }
+            }
+            other => other
+                .as_f64()
+                .or_else(|| other.as_i32().map(f64::from))
+                .unwrap(),
+        }
+    }
+
+    /// A title as the pipeline returns it, scored with the `$addFields` stage of `pipeline`.
+    fn build_scored_title(
+        pipeline: &[Document],
+        media_type: &str,
+        imdb_id: &str,
+        count: i32,
+        avg: f64,
+    ) -> PopularTitle {
+        let mut title_doc = doc! {
+            "imdbId": imdb_id,
+            "title": format!("Title {}", imdb_id),
+            "poster": "https://moviedb.com/poster.jpg",
+            "reviewCount": count,
+            "averageRating": avg,
+        };
+        let score = pipeline[3]
+            .get_document("$addFields")
+            .unwrap()
+            .get("score")
+            .unwrap();
+        let score = eval_expr(score, &title_doc);
+        title_doc.insert("score", score);
+        PopularTitle {
+            media_type: media_type.to_string(),
+            ..from_document(title_doc).unwrap()
+        }
+    }
+
+    // Unit Tests
+
+    #[test]
+    fn test_popular_pipeline_scores_bayesian_average() {
+        let pipeline = popular_pipeline("reviews", 3.0, 10);
+        assert_eq!(
+            pipeline[0],
+            doc! { "$match": { "reviewIds.0": { "$exists": true } } }
+        );
+        assert_eq!(
+            pipeline[4],
+            doc! { "$sort": { "score": -1, "reviewCount": -1, "imdbId": 1 } }
+        );
+        assert_eq!(pipeline[5], doc! { "$limit": 10_i64 });
+
+        // `m` reviews sit halfway between the title and the global mean, many approach the title
+        let title = build_scored_title(&pipeline, "movie", "tt1", 5, 5.0);
+        assert!((title.score - 4.0).abs() < 1e-9);
+        let title = build_scored_title(&pipeline, "movie", "tt2", 995, 5.0);
+        assert!((title.score - 4.99).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_merge_popular_ordering() {
+        let pipeline = popular_pipeline("reviews", 3.0, 10);
+        let movie_titles = vec![
+            // A single perfect review should not beat many very good ones
+            build_scored_title(&pipeline, "movie", "tt1", 1, 5.0),
+            build_scored_title(&pipeline, "movie", "tt3", 10, 2.0),
+        ];
+        let series_titles = vec![build_scored_title(&pipeline, "series", "tt2", 40, 4.5)];
+
+        let ranked = merge_popular(movie_titles, series_titles, 10);
+        let ids: Vec<&str> = ranked.iter().map(|t| t.imdb_id.as_str()).collect();
+        assert_eq!(ids, vec!["tt2", "tt1", "tt3"]);
+        assert_eq!(ranked[0].media_type, "series");
+    }
+
+    #[test]
+    fn test_merge_popular_limit() {
+        let pipeline = popular_pipeline("reviews", 3.0, 10);
+        let ranked = merge_popular(
+            vec![build_scored_title(&pipeline, "movie", "tt1", 3, 4.0)],
+            vec![build_scored_title(&pipeline, "series", "tt2", 3, 5.0)],
+            1,
+        );
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].imdb_id, "tt2");
+    }
+
+    #[actix_web::test]
+    async fn test_find_popular_titles_empty_list() {
+        let mut mock = MockTitleRepository::new();
+
+        mock.expect_find_popular_titles()
+            .returning(|_| Err(AppError::Empty));
+
+        let result = mock.find_popular_titles(Some(10)).await;
+        assert!(result.is_err_and(|err| err == AppError::Empty));
+    }
+
+    fn build_most_reviewed_mock(media_type: &str, title: &str, count: u32) -> MostReviewedTitle {
+        MostReviewedTitle {
+            media_type: media_type.to_string(),
+            imdb_id: format!("tt{}", count),
+            title: title.to_string(),
+            poster: "https://moviedb.com/poster.jpg".to_string(),
+            review_count: count,
+        }
+    }
+
+    #[test]
+    fn test_most_reviewed_pipeline_filters_genre() {
+        let pipeline = most_reviewed_pipeline("Drama", 10);
+        assert_eq!(pipeline[0], doc! { "$match": { "genres": "Drama" } });
+        let sort = pipeline[2].get_document("$sort").unwrap();
+        assert_eq!(sort.get_i32("reviewCount").unwrap(), -1);
+        assert_eq!(pipeline[3], doc! { "$limit": 10_i64 });
+    }
+
+    #[test]
+    fn test_merge_most_reviewed_descending() {
+        let movie_titles = vec![
+            build_most_reviewed_mock("movie", "El padrino", 30),
+            build_most_reviewed_mock("movie", "Cadena perpetua", 8),
+        ];
+        let series_titles = vec![
+            build_most_reviewed_mock("series", "Breaking Bad", 45),
+            build_most_reviewed_mock("series", "The Wire", 12),
+        ];
+
+        let merged = merge_most_reviewed(movie_titles, series_titles, 3);
+        let counts: Vec<(&str, u32)> = merged
+            .iter()
+            .map(|title| (title.media_type.as_str(), title.review_count))
+            .collect();
+        assert_eq!(counts, vec![("series", 45), ("movie", 30), ("series", 12)]);
+    }
+
+    #[actix_web::test]
+    async fn test_find_most_reviewed_titles_empty_genre() {
+        let mut mock = MockTitleRepository::new();
+
+        mock.expect_find_most_reviewed_titles()
+            .returning(|_, _| Err(AppError::Empty));
+
+        let result = mock.find_most_reviewed_titles("Western", None).await;
+        assert!(result.is_err_and(|err| err == AppError::Empty));
+    }
+
+    fn build_hit_mock(media_type: &str, title: &str) -> SearchHit {
+        SearchHit {
+            media_type: media_type.to_string(),
+            imdb_id: format!("tt{}", title.len()),
+            title: title.to_string(),
+            release_date: "2008-01-20".to_string(),
+            poster: "https://moviedb.com/poster.jpg".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_search_pipeline_pages_both_collections_in_the_database() {
+        let filter = doc! { "title": { "$regex": "padrino", "$options": "i" } };
+        let pipeline = search_pipeline(filter.clone(), "series", 20, 10);
+
+        assert_eq!(pipeline[0], doc! { "$match": filter.clone() });
+        let union = pipeline[2].get_document("$unionWith").unwrap();
+        assert_eq!(union.get_str("coll").unwrap(), "series");
+        let series_stages = union.get_array("pipeline").unwrap();
+        assert_eq!(series_stages[0], Bson::Document(doc! { "$match": filter }));
+        // Only the merged list is sorted and paged, nothing is fetched per collection
+        assert_eq!(
+            pipeline[3],
+            doc! { "$sort": { "title": 1, "type": 1, "_id": 1 } }
+        );
+        assert_eq!(pipeline[4], doc! { "$skip": 20_i64 });
+        assert_eq!(pipeline[5], doc! { "$limit": 10_i64 });
+
+        for (stages, media_type) in [
+            (&pipeline[..2], "movie"),
+            (
+                &series_stages
+                    .iter()
+                    .map(|stage| stage.as_document().unwrap().clone())
+                    .collect::<Vec<_>>()[..],
+                "series",
+            ),
+        ] {
+            let tag = stages[1]
+                .get_document("$project")
+                .and_then(|project| project.get_document("type"))
+                .unwrap();
+            assert_eq!(tag, &doc! { "$literal": media_type });
+            let hit: SearchHit = from_document(doc! {
+                "_id": mongodb::bson::oid::ObjectId::new(),
+                "imdbId": "tt0068646",
+                "title": "El padrino",
+                "releaseDate": "1972-03-24",
+                "poster": "https://moviedb.com/poster.jpg",
+                "type": media_type,
+            })
+            .unwrap();
+            assert_eq!(hit.media_type, media_type);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_search_all_ok() {
+        let mut mock = MockTitleRepository::new();
+
+        mock.expect_search_all().returning(|_, _, _| {
+            let hits = vec![
+                build_hit_mock("movie", "Breaking Away"),
+                build_hit_mock("series", "Breaking Bad"),
+            ];
+            let mut result_map = Map::new();
+            result_map.insert("results".to_string(), serde_json::to_value(hits).unwrap());
+            result_map.insert("totalItems".to_string(), Value::from(2));
+            Ok(result_map)
+        });
+
+        let result = mock.search_all("breaking", None, None).await.unwrap();
+        let types: Vec<&str> = result["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| hit["type"].as_str().unwrap())
+            .collect();
+        assert_eq!(types, vec!["movie", "series"]);
+    }
+
+    #[actix_web::test]
+    async fn test_search_all_empty() {
+        let mut mock = MockTitleRepository::new();
+
+        mock.expect_search_all()
+            .returning(|_, _, _| Err(AppError::Empty));
+
+        let result = mock.search_all("zzz", None, None).await;
+        assert!(result.is_err_and(|err| err == AppError::Empty));
+    }
+
+    #[test]
+    fn test_missing_imdb_ids_partially_present() {
+        let requested: Vec<String> = [
+            "tt0068646",
+            "tt0071562",
+            "tt0903747",
+            "tt0071562",
+            "tt9999999",
+        ]
+        .iter()
+        .map(|imdb_id| imdb_id.to_string())
+        .collect();
+        // tt0068646 is a movie and tt0903747 a series
+        let present: HashSet<String> = ["tt0068646", "tt0903747"]
+            .iter()
+            .map(|imdb_id| imdb_id.to_string())
+            .collect();
+
+        assert_eq!(
+            missing_imdb_ids(&requested, &present),
+            vec!["tt0071562", "tt9999999"]
+        );
+        assert!(missing_imdb_ids(&requested[..1], &present).is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_find_missing_imdb_ids_ok() {
+        let mut mock = MockTitleRepository::new();
+
+        mock.expect_find_missing_imdb_ids().returning(|imdb_ids| {
+            let present: HashSet<String> = HashSet::from(["tt0068646".to_string()]);
+            let missing = missing_imdb_ids(&imdb_ids, &present);
+            let mut result_map = Map::new();
+            result_map.insert("missingCount".to_string(), Value::from(missing.len()));
+            result_map.insert(
+                "missingImdbIds".to_string(),
+                serde_json::to_value(missing).unwrap(),
+            );
+            Ok(result_map)
+        });
+
+        let result = mock
+            .find_missing_imdb_ids(vec!["tt0068646".to_string(), "tt0071562".to_string()])
+            .await;
+        assert!(result
+            .is_ok_and(|map| map["missingCount"] == 1 && map["missingImdbIds"][0] == "tt0071562"));
+    }
+
+    #[test]
+    fn test_tag_title_adds_type() {
+        let movie = tag_title(
+            "movie",
+            serde_json::json!({"imdbId": "tt0068646", "title": "El padrino"}),
+        );
+        assert_eq!(movie["type"], "movie");
+        assert_eq!(movie["imdbId"], "tt0068646");
+        assert_eq!(movie["title"], "El padrino");
+    }
+
+    #[actix_web::test]
+    async fn test_find_title_by_imdb_id_movie() {
+        let mut mock = MockTitleRepository::new();
+
+        mock.expect_find_title_by_imdb_id()
+            .withf(|imdb_id| imdb_id == "tt0068646")
+            .returning(|imdb_id| {
+                Ok(tag_title(
+                    "movie",
+                    serde_json::json!({"imdbId": imdb_id, "title": "El padrino"}),
+                ))
+            });
+
+        let result = mock.find_title_by_imdb_id("tt0068646").await;
+        assert!(result.is_ok_and(|map| map["type"] == "movie" && map["title"] == "El padrino"));
+    }
+
+    #[actix_web::test]
+    async fn test_find_title_by_imdb_id_series() {
+        let mut mock = MockTitleRepository::new();
+
+        mock.expect_find_title_by_imdb_id()
+            .withf(|imdb_id| imdb_id == "tt0903747")
+            .returning(|imdb_id| {
+                Ok(tag_title(
+                    "series",
+                    serde_json::json!({"imdbId": imdb_id, "title": "Breaking Bad"}),
+                ))
+            });
+
+        let result = mock.find_title_by_imdb_id("tt0903747").await;
+        assert!(result.is_ok_and(|map| map["type"] == "series" && map["title"] == "Breaking Bad"));
+    }
+
+    #[actix_web::test]
+    async fn test_find_title_by_imdb_id_not_found() {
+        let mut mock = MockTitleRepository::new();
+
+        mock.expect_find_title_by_imdb_id()
+            .returning(|_| Err(AppError::NotFound));
+
+        let result = mock.find_title_by_imdb_id("tt9999999").await;
+        assert!(result.is_err_and(|err| err == AppError::NotFound));
+    }
+}
@@ -1,5 +1,8 @@
 use actix_web::{
-    http::{header::ContentType, StatusCode},
+    http::{
+        header::{self, ContentType},
+        StatusCode,
+    },
     test, App,
 };
 
@@ -22,19 +25,21 @@ async fn test_ping_ok() {
 
 #[actix_web::test]
 async fn test_health_ok() {
-    let app = test::init_service(App::new().service(health)).await;
-    let req = test::TestRequest::get()
-        .uri("/health")
-        .insert_header(ContentType::json())
-        .to_request();
-    let resp = test::call_service(&app, req).await;
+    let mut checker = services::db::MockHealthCheck::new();
+    checker.expect_ping().returning(|| Ok(()));
+
+    let resp = health_response(&checker).await;
     assert_eq!(resp.status(), StatusCode::MULTI_STATUS);
-    let body = test::read_body(resp).await;
+    let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
     let mut expected_res = Map::new();
     expected_res.insert(
         "status".to_string(),
         serde_json::Value::String("UP".to_string()),
     );
+    expected_res.insert(
+        "dbStatus".to_string(),
+        serde_json::Value::String("UP".to_string()),
+    );
     expected_res.insert(
         "message".to_string(),
         serde_json::Value::String("All systems working correctly.".to_string()),
@@ -44,3 +49,827 @@ async fn test_health_ok() {
         serde_json::to_string(&expected_res).unwrap()
     )
 }
+
+#[actix_web::test]
+async fn test_health_db_down() {
+    let mut checker = services::db::MockHealthCheck::new();
+    checker
+        .expect_ping()
+        .returning(|| Err(error::AppError::InternalServerError));
+
+    let resp = health_response(&checker).await;
+    assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body: serde_json::Value =
+        serde_json::from_slice(&actix_web::body::to_bytes(resp.into_body()).await.unwrap())
+            .unwrap();
+    assert_eq!(body["status"], "DOWN");
+    assert_eq!(body["dbStatus"], "DOWN");
+}
+
+#[actix_web::test]
+async fn test_cors_only_allows_configured_origins() {
+    let app = test::init_service(
+        App::new()
+            .wrap(build_cors(Some("https://cinema.example.com")))
+            .service(ping),
+    )
+    .await;
+
+    let preflight = test::TestRequest::default()
+        .method(actix_web::http::Method::OPTIONS)
+        .uri("/ping")
+        .insert_header((header::ORIGIN, "https://cinema.example.com"))
+        .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "PATCH"))
+        .to_request();
+    let resp = test::call_service(&app, preflight).await;
+    assert!(resp.status().is_success());
+    assert_eq!(
+        resp.headers()
+            .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .unwrap(),
+        "https://cinema.example.com"
+    );
+    let allowed_methods = resp
+        .headers()
+        .get(header::ACCESS_CONTROL_ALLOW_METHODS)
+        .unwrap()
+        .to_str()
+        .unwrap();
+    assert!(allowed_methods.contains("PATCH") && allowed_methods.contains("DELETE"));
+
+    let req = test::TestRequest::get()
+        .uri("/ping")
+        .insert_header((header::ORIGIN, "https://evil.example.com"))
+        .to_request();
+    // Rejected with an error response, never with the allow-origin header
+    if let Ok(resp) = test::try_call_service(&app, req).await {
+        assert!(!resp
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+}
+
+#[actix_web::test]
+async fn test_movie_patchable_fields_match_registry() {
+    let app = test::init_service(App::new().service(get_movie_patchable_fields)).await;
+    let req = test::TestRequest::get()
+        .uri("/patchableFields")
+        .insert_header(ContentType::json())
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(
+        body,
+        serde_json::to_value(models::fields::MOVIE_PATCHABLE_FIELDS).unwrap()
+    );
+    assert_eq!(body[0]["name"], "imdbId");
+    assert_eq!(body[0]["fieldType"], "string");
+}
+
+const TEST_JWT_SECRET: &str = "cinema-test-secret";
+
+/// Bearer header value signed with `secret`, expiring in an hour.
+fn build_bearer(secret: &str) -> String {
+    let claims = middleware::Claims {
+        sub: "tester".to_string(),
+        exp: (chrono::Utc::now().timestamp() + 3600) as usize,
+        role: middleware::Role::User,
+    };
+    let token = jsonwebtoken::encode(
+        &jsonwebtoken::Header::default(),
+        &claims,
+        &jsonwebtoken::EncodingKey::from_secret(secret.as_bytes()),
+    )
+    .unwrap();
+    format!("Bearer {}", token)
+}
+
+/// Lazy client: building it needs no server. Tests that do reach MongoDB are `#[ignore]`d, start
+/// one at localhost:27017 (a replica set for the transactional ones) and run
+/// `cargo test -- --ignored`.
+pub(crate) async fn build_test_db() -> Database {
+    let client = mongodb::Client::with_uri_str("mongodb://localhost:27017")
+        .await
+        .unwrap();
+    let db = client.database("cinema-rust-db-test");
+    Database {
+        client: client.clone(),
+        movies: db.collection("movies"),
+        series: db.collection("series"),
+        reviews: db.collection("reviews"),
+        movie_cache: services::cache::FindByIdCache::new(std::time::Duration::ZERO),
+        series_cache: services::cache::FindByIdCache::new(std::time::Duration::ZERO),
+    }
+}
+
+#[actix_web::test]
+async fn test_admin_raw_movie_unauthorized() {
+    env::set_var("JWT_SECRET", TEST_JWT_SECRET);
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(build_test_db().await))
+            .service(web::scope("/admin").service(get_raw_movie_by_id)),
+    )
+    .await;
+    let req = test::TestRequest::get()
+        .uri("/admin/movies/6640ce2b8d0a4a4e3c5a7d31/raw")
+        .insert_header(("X-Admin-Token", "wrong-token"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    // A valid token of a regular user is not enough either
+    let req = test::TestRequest::get()
+        .uri("/admin/movies/6640ce2b8d0a4a4e3c5a7d31/raw")
+        .insert_header((header::AUTHORIZATION, build_bearer(TEST_JWT_SECRET)))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn test_reviews_list_last_modified_then_not_modified() {
+    let res: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_value(serde_json::json!({
+            "reviews": [
+                { "title": "Obra maestra", "updatedAt": "2024-05-07T11:56:05.792+00:00" },
+                { "title": "Muy buena", "updatedAt": "2024-05-08T09:00:00.120+00:00" },
+            ],
+            "totalItems": 2,
+        }))
+        .unwrap();
+
+    let first = routes::etag::conditional_list_response(
+        &test::TestRequest::get().to_http_request(),
+        &res,
+        "reviews",
+    );
+    assert_eq!(first.status(), StatusCode::OK);
+    let last_modified = first.headers().get(header::LAST_MODIFIED).unwrap();
+    assert_eq!(last_modified, "Wed, 08 May 2024 09:00:00 GMT");
+    let etag = first.headers().get(header::ETAG).unwrap().clone();
+
+    let cached = test::TestRequest::get()
+        .insert_header((header::IF_NONE_MATCH, etag))
+        .to_http_request();
+    let second = routes::etag::conditional_list_response(&cached, &res, "reviews");
+    assert_eq!(second.status(), StatusCode::NOT_MODIFIED);
+
+    let mut grown = res.clone();
+    grown.insert("totalItems".to_string(), serde_json::json!(3));
+    let third = routes::etag::conditional_list_response(&cached, &grown, "reviews");
+    assert_eq!(third.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn test_movie_find_by_id_sparse_fields() {
+    let movie = models::movie::Movie {
+        _id: mongodb::bson::oid::ObjectId::new(),
+        imdb_id: "tt0068646".to_string(),
+        title: "El padrino".to_string(),
+        overview: "La historia de la familia Corleone.".to_string(),
+        duration: "2h 55m".parse().unwrap(),
+        director: "Francis Coppola".to_string(),
+        release_date: "1972-03-24".to_string(),
+        trailer_link: "https://youtu.be/sY1S34973zA".to_string(),
+        genres: vec!["Crimen".to_string(), "Drama".to_string()],
+        poster: "https://image.tmdb.org/t/p/original/padrino_poster.jpg".to_string(),
+        backdrop: "https://image.tmdb.org/t/p/original/padrino_backdrop.jpg".to_string(),
+        review_ids: vec![],
+        created_at: mongodb::bson::DateTime::now(),
+        updated_at: mongodb::bson::DateTime::now(),
+    };
+    let fields = models::fields::parse_sparse_fields(
+        "title, poster, createdAt",
+        models::fields::MOVIE_FIELDS,
+    )
+    .unwrap();
+
+    let projection = models::fields::build_movie_projection(&fields);
+    assert_eq!(
+        projection,
+        mongodb::bson::doc! { "_id": 1, "title": 1, "poster": 1, "createdAt": 1 }
+    );
+    // What MongoDB answers for that projection
+    let stored = mongodb::bson::to_document(&movie).unwrap();
+    let projected: mongodb::bson::Document = projection
+        .keys()
+        .filter_map(|key| Some((key.clone(), stored.get(key)?.clone())))
+        .collect();
+    let sparse = models::fields::projected_to_json(projected);
+    let mut keys: Vec<&str> = sparse.keys().map(|key| key.as_str()).collect();
+    keys.sort();
+    assert_eq!(keys, vec!["_id", "createdAt", "poster", "title"]);
+    assert_eq!(sparse["_id"], movie._id.to_hex());
+    assert!(sparse["createdAt"].is_string());
+    assert_eq!(sparse["title"], "El padrino");
+
+    let rating_fields =
+        models::fields::parse_sparse_fields("averageRating", models::fields::MOVIE_FIELDS).unwrap();
+    assert_eq!(
+        models::fields::build_movie_projection(&rating_fields),
+        mongodb::bson::doc! { "_id": 1, "reviewIds": 1 }
+    );
+
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(build_test_db().await))
+            .service(web::scope("/movies").service(get_movie_by_id)),
+    )
+    .await;
+    let req = test::TestRequest::get()
+        .uri("/movies/findById/6640ce2b8d0a4a4e3c5a7d31?fields%5Bmovie%5D=title,budget")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body: String = test::read_body_json(resp).await;
+    assert!(body.contains("budget"));
+}
+
+#[actix_web::test]
+async fn test_movies_find_all_sparse_fields() {
+    let res: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_value(serde_json::json!({
+            "movies": [
+                { "imdbId": "tt0068646", "title": "El padrino", "duration": "2h 55m", "releaseDate": "1972-03-24", "poster": "https://image.tmdb.org/p1.jpg" },
+                { "imdbId": "tt0071562", "title": "El padrino II", "duration": "3h 22m", "releaseDate": "1974-12-20", "poster": "https://image.tmdb.org/p2.jpg" },
+            ],
+            "currentPage": 0,
+            "totalItems": 2,
+            "totalPages": 1,
+        }))
+        .unwrap();
+    let fields =
+        models::fields::parse_sparse_fields("title", models::fields::MOVIE_RESPONSE_FIELDS)
+            .unwrap();
+
+    let sparse = routes::movie::sparse_movie_list(res, &fields);
+    assert_eq!(sparse["totalItems"], 2);
+    assert_eq!(
+        sparse["movies"],
+        serde_json::json!([{ "title": "El padrino" }, { "title": "El padrino II" }])
+    );
+
+    // overview exists on Movie but not on the list items
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(build_test_db().await))
+            .service(web::scope("/movies").service(get_movies)),
+    )
+    .await;
+    let req = test::TestRequest::get()
+        .uri("/movies/findAll?fields%5Bmovie%5D=title,overview")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn test_max_query_length_rejects_long_query() {
+    let app = test::init_service(
+        App::new()
+            .wrap(middleware::MaxQueryLength::new(32))
+            .service(ping),
+    )
+    .await;
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/ping?title={}", "a".repeat(40)))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::URI_TOO_LONG);
+    let body: String = test::read_body_json(resp).await;
+    assert_eq!(body, error::AppError::UriTooLong.to_string());
+
+    let req = test::TestRequest::get()
+        .uri("/ping?title=casino")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert!(resp.status().is_success());
+}
+
+fn build_detail_entities(
+    oid: mongodb::bson::oid::ObjectId,
+    review_oid: mongodb::bson::oid::ObjectId,
+) -> (models::movie::Movie, models::series::Series) {
+    let movie = models::movie::Movie {
+        _id: oid,
+        imdb_id: "tt0068646".to_string(),
+        title: "El padrino".to_string(),
+        overview: "La historia de la familia Corleone.".to_string(),
+        duration: "2h 55m".parse().unwrap(),
+        director: "Francis Coppola".to_string(),
+        release_date: "1972-03-24".to_string(),
+        trailer_link: "https://youtu.be/sY1S34973zA".to_string(),
+        genres: vec!["Crimen".to_string()],
+        poster: "https://image.tmdb.org/t/p/original/padrino_poster.jpg".to_string(),
+        backdrop: "https://image.tmdb.org/t/p/original/padrino_backdrop.jpg".to_string(),
+        review_ids: vec![review_oid],
+        created_at: mongodb::bson::DateTime::now(),
+        updated_at: mongodb::bson::DateTime::now(),
+    };
+    let series = models::series::Series {
+        _id: oid,
+        imdb_id: "tt0903747".to_string(),
+        title: "Breaking Bad".to_string(),
+        overview: "Un profesor de química se convierte en fabricante de metanfetamina.".to_string(),
+        number_of_seasons: 0,
+        creator: "Vince Gilligan".to_string(),
+        release_date: "2008-01-20".to_string(),
+        trailer_link: "https://youtu.be/HhesaQXLuRY".to_string(),
+        genres: vec!["Drama".to_string()],
+        season_list: vec![],
+        poster: "https://image.tmdb.org/t/p/original/bb_poster.jpg".to_string(),
+        backdrop: "https://image.tmdb.org/t/p/original/bb_backdrop.jpg".to_string(),
+        review_ids: vec![review_oid],
+        created_at: mongodb::bson::DateTime::now(),
+        updated_at: mongodb::bson::DateTime::now(),
+    };
+    (movie, series)
+}
+
+/// imdbId derived from the ObjectId counter, so leftovers of other runs never collide with it.
+fn build_unique_imdb_id(lead: u8, oid: &mongodb::bson::oid::ObjectId) -> String {
+    let bytes = oid.bytes();
+    let counter = u32::from_be_bytes([0, bytes[9], bytes[10], bytes[11]]);
+    format!("tt{}{:07}", lead, counter % 10_000_000)
+}
+
+#[actix_web::test]
+async fn test_find_by_id_docs_serialize_object_ids_as_strings() {
+    let (oid, review_oid) = (
+        mongodb::bson::oid::ObjectId::new(),
+        mongodb::bson::oid::ObjectId::new(),
+    );
+    let (movie, series) = build_detail_entities(oid, review_oid);
+
+    let movie_json =
+        serde_json::to_value(models::movie::MovieDoc::try_from(movie).unwrap()).unwrap();
+    let series_json =
+        serde_json::to_value(models::series::SeriesDoc::try_from(series).unwrap()).unwrap();
+    for doc_json in [movie_json, series_json] {
+        // Plain strings, never extended JSON like {"$oid": "..."} or {"$date": ...}
+        assert!(doc_json["_id"].is_string());
+        assert!(doc_json["createdAt"].is_string() && doc_json["updatedAt"].is_string());
+        assert_eq!(doc_json["_id"], oid.to_hex());
+        assert_eq!(
+            doc_json["reviewIds"],
+            serde_json::json!([review_oid.to_hex()])
+        );
+        assert!(doc_json["averageRating"].is_null());
+    }
+}
+
+#[actix_web::test]
+async fn test_find_by_id_docs_include_reviews_flag() {
+    let (oid, review_oid) = (
+        mongodb::bson::oid::ObjectId::new(),
+        mongodb::bson::oid::ObjectId::new(),
+    );
+    let (movie, series) = build_detail_entities(oid, review_oid);
+    let review = models::review::ReviewResponseDoc::from(models::review::ReviewResponse {
+        _id: review_oid,
+        title: "Una obra maestra".to_string(),
+        rating: 5.0,
+        body: "Imprescindible".to_string(),
+        created_at: mongodb::bson::DateTime::now(),
+        updated_at: mongodb::bson::DateTime::now(),
+    });
+    let movie_doc = models::movie::MovieDoc::try_from(movie).unwrap();
+    let series_doc = models::series::SeriesDoc::try_from(series).unwrap();
+
+    // Without includeReviews the docs keep their usual shape
+    let movie_json = serde_json::to_value(&movie_doc).unwrap();
+    let series_json = serde_json::to_value(&series_doc).unwrap();
+    assert!(movie_json.get("reviews").is_none());
+    assert!(series_json.get("reviews").is_none());
+
+    let movie_doc = models::movie::MovieDoc {
+        reviews: Some(vec![review]),
+        ..movie_doc
+    };
+    let series_doc = models::series::SeriesDoc {
+        reviews: Some(vec![]),
+        ..series_doc
+    };
+    let movie_json = serde_json::to_value(&movie_doc).unwrap();
+    let series_json = serde_json::to_value(&series_doc).unwrap();
+    assert_eq!(movie_json["reviews"][0]["_id"], review_oid.to_hex());
+    assert_eq!(movie_json["reviews"][0]["title"], "Una obra maestra");
+    assert!(movie_json["reviews"][0]["createdAt"].is_string());
+    assert_eq!(series_json["reviews"], serde_json::json!([]));
+}
+
+#[actix_web::test]
+async fn test_invalid_query_param_validation_error() {
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(build_test_db().await))
+            .configure(routes_config),
+    )
+    .await;
+
+    for uri in [
+        "/api/v1/movies/findAll?page=abc",
+        "/api/v1/series/findAll?page=abc",
+        "/api/v1/reviews/findAll?size=-1",
+    ] {
+        let req = test::TestRequest::get().uri(uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{uri}");
+        let body: String = test::read_body_json(resp).await;
+        assert!(
+            body.starts_with("Error in Validation: (query: "),
+            "{uri}: {body}"
+        );
+    }
+}
+
+#[actix_web::test]
+async fn test_series_request_bad_imdb_id_validation_error() {
+    env::set_var("JWT_SECRET", TEST_JWT_SECRET);
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(build_test_db().await))
+            .configure(routes_config),
+    )
+    .await;
+    let series_request = serde_json::json!({
+        "imdbId": "breaking-bad",
+        "title": "Breaking Bad",
+        "overview": "Walter White, un profesor de química, se convierte en fabricante de metanfetamina.",
+        "numberOfSeasons": 1,
+        "creator": "Vince Gilligan",
+        "releaseDate": "2008-01-20",
+        "trailerLink": "https://youtu.be/HhesaQXLuRY",
+        "genres": ["Drama"],
+        "seasonList": [],
+        "poster": "https://image.tmdb.org/t/p/original/ggFHVNu6YYI5L9pCfOacjizRGt.jpg",
+        "backdrop": "https://image.tmdb.org/t/p/original/tsRy63Mu5cu8etL1X7ZLyf7UP1M.jpg",
+    });
+
+    for req in [
+        test::TestRequest::post().uri("/api/v1/series/new"),
+        test::TestRequest::put().uri("/api/v1/series/update/6640ce2b8d0a4a4e3c5a7d31"),
+    ] {
+        let req = req
+            .insert_header((header::AUTHORIZATION, build_bearer(TEST_JWT_SECRET)))
+            .set_json(&series_request)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body[0]["field"], "imdbId", "{body}");
+        assert!(body[0]["messages"][0].is_string());
+    }
+}
+
+#[actix_web::test]
+async fn test_require_auth_protects_writes() {
+    env::set_var("JWT_SECRET", TEST_JWT_SECRET);
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(build_test_db().await))
+            .configure(routes_config),
+    )
+    .await;
+    let invalid_review =
+        serde_json::json!({ "title": "", "rating": 3.7, "body": "", "imdbId": "tt0068646" });
+
+    for authorization in [
+        None,
+        Some("Bearer not-a-jwt".to_string()),
+        Some(build_bearer("other-secret")),
+    ] {
+        let mut req = test::TestRequest::post().uri("/api/v1/reviews/new");
+        if let Some(authorization) = authorization {
+            req = req.insert_header((header::AUTHORIZATION, authorization));
+        }
+        let resp = test::call_service(&app, req.set_json(&invalid_review).to_request()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let body: String = test::read_body_json(resp).await;
+        assert_eq!(body, error::AppError::Unauthorized.to_string());
+    }
+
+    // A valid token reaches the handler, which rejects the body
+    let req = test::TestRequest::post()
+        .uri("/api/v1/reviews/new")
+        .insert_header((header::AUTHORIZATION, build_bearer(TEST_JWT_SECRET)))
+        .set_json(&invalid_review)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    // Reads stay public
+    let req = test::TestRequest::get()
+        .uri("/api/v1/movies/findAll?page=abc")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn test_require_auth_without_secret_only_opens_writes_behind_the_flag() {
+    for (allow_anonymous, expected) in [(false, StatusCode::UNAUTHORIZED), (true, StatusCode::OK)] {
+        let app = test::init_service(
+            App::new()
+                .wrap(middleware::RequireAuth::new(None, allow_anonymous))
+                .route("/write", web::post().to(HttpResponse::Ok)),
+        )
+        .await;
+        let req = test::TestRequest::post().uri("/write").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), expected);
+    }
+}
+
+#[actix_web::test]
+async fn test_rate_limit_rejects_over_limit() {
+    let limit = 3;
+    let app = test::init_service(
+        App::new()
+            .wrap(middleware::RateLimit::new(
+                limit,
+                std::time::Duration::from_secs(60),
+            ))
+            .service(ping),
+    )
+    .await;
+    let client: std::net::SocketAddr = "203.0.113.7:40000".parse().unwrap();
+
+    for _ in 0..limit {
+        let req = test::TestRequest::get()
+            .uri("/ping")
+            .peer_addr(client)
+            .to_request();
+        assert!(test::call_service(&app, req).await.status().is_success());
+    }
+    let req = test::TestRequest::get()
+        .uri("/ping")
+        .peer_addr(client)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    let body: String = test::read_body_json(resp).await;
+    assert_eq!(body, error::AppError::TooManyRequests.to_string());
+
+    // Other clients have their own window
+    let req = test::TestRequest::get()
+        .uri("/ping")
+        .peer_addr("198.51.100.2:40000".parse().unwrap())
+        .to_request();
+    assert!(test::call_service(&app, req).await.status().is_success());
+}
+
+#[actix_web::test]
+async fn test_malformed_json_body_bad_request() {
+    env::set_var("JWT_SECRET", TEST_JWT_SECRET);
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(build_test_db().await))
+            .configure(routes_config),
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/movies/new")
+        .insert_header((header::AUTHORIZATION, build_bearer(TEST_JWT_SECRET)))
+        .insert_header(ContentType::json())
+        .set_payload(r#"{"imdbId": "tt0068646", "title": }"#)
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+        resp.headers().get(header::CONTENT_TYPE).unwrap(),
+        "application/json"
+    );
+    let body: String = test::read_body_json(resp).await;
+    assert!(body.starts_with("Bad Request: ("), "{body}");
+}
+
+#[actix_web::test]
+#[ignore = "needs a MongoDB at localhost:27017, run with `cargo test -- --ignored`"]
+async fn test_delete_missing_entity_not_found() {
+    env::set_var("JWT_SECRET", TEST_JWT_SECRET);
+    let app = test::init_service(
+        App::new()
+            .app_data(Data::new(build_test_db().await))
+            .configure(routes_config),
+    )
+    .await;
+    let missing_id = mongodb::bson::oid::ObjectId::new().to_hex();
+
+    for resource in ["movies", "series", "reviews"] {
+        let req = test::TestRequest::delete()
+            .uri(&format!("/api/v1/{}/delete/{}", resource, missing_id))
+            .insert_header((header::AUTHORIZATION, build_bearer(TEST_JWT_SECRET)))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND, "{resource}");
+        let body: String = test::read_body_json(resp).await;
+        assert_eq!(body, error::AppError::NotFound.to_string());
+    }
+}
+
+#[actix_web::test]
+#[ignore = "needs a MongoDB at localhost:27017, run with `cargo test -- --ignored`"]
+async fn test_forced_seed_does_not_duplicate_reviews() {
+    let db = build_test_db().await;
+    let fixture = r#"{
+        "movies": [
+            {
+                "imdbId": "tt9990001",
+                "title": "Película de prueba",
+                "overview": "Sembrada por los tests.",
+                "duration": "1h 30m",
+                "director": "Nadie",
+                "releaseDate": "2020-01-01",
+                "trailerLink": "https://youtu.be/DEMZSa0esCU",
+                "genres": ["Drama"],
+                "poster": "https://image.tmdb.org/t/p/original/poster.jpg",
+                "backdrop": "https://image.tmdb.org/t/p/original/backdrop.jpg"
+            }
+        ],
+        "reviews": [
+            { "title": "Correcta", "rating": 3, "body": "Sin más.", "imdbId": "tt9990001" }
+        ]
+    }"#;
+
+    for _ in 0..2 {
+        let data = services::seed::parse_seed(fixture).unwrap();
+        services::seed::seed(&db, services::seed::prepare_seed(data), true).await;
+    }
+
+    let movie = db
+        .movies
+        .find_one(mongodb::bson::doc! { "imdbId": "tt9990001" }, None)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(movie.review_ids.len(), 1);
+    let reviews = db
+        .reviews
+        .count_documents(
+            mongodb::bson::doc! { "title": "Correcta", "body": "Sin más." },
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(reviews, 1);
+}
+
+#[actix_web::test]
+async fn test_trailing_slash_resolves_same_route() {
+    let app = test::init_service(
+        App::new().service(
+            web::scope("/api/v1")
+                .wrap(NormalizePath::trim())
+                .route("/movies/findAll", web::get().to(HttpResponse::Ok)),
+        ),
+    )
+    .await;
+
+    for uri in ["/api/v1/movies/findAll", "/api/v1/movies/findAll/"] {
+        let req = test::TestRequest::get().uri(uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success(), "{uri}: {}", resp.status());
+    }
+}
+
+#[actix_web::test]
+async fn test_request_id_header_present_and_unique() {
+    use actix_web::{dev::Service, HttpMessage};
+
+    #[derive(Clone)]
+    struct SeenByInnerCall(String);
+
+    let app = test::init_service(
+        App::new()
+            .wrap_fn(|req, srv| {
+                // Synchronous part of an inner service's call
+                let seen = middleware::current_request_id().unwrap_or_default();
+                req.extensions_mut().insert(SeenByInnerCall(seen));
+                srv.call(req)
+            })
+            .wrap(middleware::RequestIdHeader)
+            .service(ping)
+            .route(
+                "/requestId",
+                web::get().to(|req: actix_web::HttpRequest| async move {
+                    // Every helper sees the id the response header echoes
+                    let from_request = middleware::request_id(&req).unwrap_or_default();
+                    let from_task = middleware::current_request_id().unwrap_or_default();
+                    let from_inner_call = req
+                        .extensions()
+                        .get::<SeenByInnerCall>()
+                        .map(|seen| seen.0.clone())
+                        .unwrap_or_default();
+                    HttpResponse::Ok().json(serde_json::json!([
+                        from_request,
+                        from_task,
+                        from_inner_call
+                    ]))
+                }),
+            ),
+    )
+    .await;
+
+    let mut ids = Vec::new();
+    for _ in 0..2 {
+        let req = test::TestRequest::get().uri("/ping").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        let request_id = resp
+            .headers()
+            .get(middleware::REQUEST_ID_HEADER)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert!(!request_id.is_empty());
+        ids.push(request_id);
+    }
+    assert_ne!(ids[0], ids[1]);
+
+    let req = test::TestRequest::get().uri("/requestId").to_request();
+    let resp = test::call_service(&app, req).await;
+    let header_id = resp
+        .headers()
+        .get(middleware::REQUEST_ID_HEADER)
+        .unwrap()
+        .clone();
+    let body: Vec<String> = test::read_body_json(resp).await;
+    assert_eq!(body, vec![header_id.to_str().unwrap(); 3]);
+    assert_eq!(middleware::current_request_id(), None);
+}
+
+#[actix_web::test]
+#[ignore = "needs a MongoDB replica set at localhost:27017, run with `cargo test -- --ignored`"]
+async fn test_merge_movies_reparents_moved_reviews() {
+    use services::{admin_repo::AdminRepository, review_repo::ReviewRepository};
+
+    let db = build_test_db().await;
+    let (keep_oid, remove_oid, review_oid) = (
+        mongodb::bson::oid::ObjectId::new(),
+        mongodb::bson::oid::ObjectId::new(),
+        mongodb::bson::oid::ObjectId::new(),
+    );
+    let (movie, _) = build_detail_entities(keep_oid, review_oid);
+    let kept = models::movie::Movie {
+        imdb_id: build_unique_imdb_id(1, &keep_oid),
+        review_ids: vec![],
+        ..movie.clone()
+    };
+    let removed = models::movie::Movie {
+        _id: remove_oid,
+        imdb_id: build_unique_imdb_id(2, &remove_oid),
+        ..movie
+    };
+    db.movies
+        .insert_many([&kept, &removed], None)
+        .await
+        .unwrap();
+    db.reviews
+        .insert_one(
+            models::review::Review {
+                _id: review_oid,
+                title: "Una obra maestra".to_string(),
+                rating: 5.0,
+                body: "Imprescindible".to_string(),
+                created_at: mongodb::bson::DateTime::now(),
+                updated_at: mongodb::bson::DateTime::now(),
+                parent_id: Some(remove_oid),
+                parent_type: Some("movie".to_string()),
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+    db.merge_movies(&keep_oid.to_hex(), &remove_oid.to_hex())
+        .await
+        .unwrap();
+    let review = db
+        .reviews
+        .find_one(mongodb::bson::doc! { "_id": review_oid }, None)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(review.parent_id, Some(keep_oid));
+
+    // The delete now pulls the id from the kept movie instead of leaving it dangling
+    db.delete_review(&review_oid.to_hex()).await.unwrap();
+    let kept = db
+        .movies
+        .find_one(mongodb::bson::doc! { "_id": keep_oid }, None)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(kept.review_ids.is_empty());
+
+    db.movies
+        .delete_one(mongodb::bson::doc! { "_id": keep_oid }, None)
+        .await
+        .unwrap();
+}